@@ -0,0 +1,58 @@
+use easy_fs::BlockDevice;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::Mutex;
+
+const BLOCK_SZ: usize = 512;
+
+/// A `BlockDevice` backed by a plain host file, shared by the fuse packer
+/// (`main.rs`) and host-side tests so both go through the same seek/bounds
+/// behavior instead of duplicating it. Block `block_id` lives at byte
+/// offset `block_id * BLOCK_SZ` in the file; the file must already be at
+/// least that large, the same precondition `main.rs` meets by calling
+/// `File::set_len` up front.
+pub struct FileBlockDevice(Mutex<File>);
+
+impl FileBlockDevice {
+    pub fn new(file: File) -> Self {
+        Self(Mutex::new(file))
+    }
+}
+
+impl BlockDevice for FileBlockDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        assert_eq!(buf.len(), BLOCK_SZ, "buf must be exactly one block");
+        let mut file = self.0.lock().unwrap();
+        file.seek(SeekFrom::Start((block_id * BLOCK_SZ) as u64))
+            .expect("Error when seeking!");
+        assert_eq!(file.read(buf).unwrap(), BLOCK_SZ, "Not a complete block!");
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        assert_eq!(buf.len(), BLOCK_SZ, "buf must be exactly one block");
+        let mut file = self.0.lock().unwrap();
+        file.seek(SeekFrom::Start((block_id * BLOCK_SZ) as u64))
+            .expect("Error when seeking!");
+        assert_eq!(file.write(buf).unwrap(), BLOCK_SZ, "Not a complete block!");
+    }
+
+    /// A contiguous span is a single seek and a single read on a plain
+    /// file, so there's no need to fall back to the default one-block-at-a
+    /// -time loop here.
+    fn read_blocks(&self, start_block_id: usize, buf: &mut [u8]) {
+        assert_eq!(buf.len() % BLOCK_SZ, 0, "buf must hold whole blocks");
+        let mut file = self.0.lock().unwrap();
+        file.seek(SeekFrom::Start((start_block_id * BLOCK_SZ) as u64))
+            .expect("Error when seeking!");
+        file.read_exact(buf).expect("Not a complete read!");
+    }
+
+    /// See `read_blocks`.
+    fn write_blocks(&self, start_block_id: usize, buf: &[u8]) {
+        assert_eq!(buf.len() % BLOCK_SZ, 0, "buf must hold whole blocks");
+        let mut file = self.0.lock().unwrap();
+        file.seek(SeekFrom::Start((start_block_id * BLOCK_SZ) as u64))
+            .expect("Error when seeking!");
+        file.write_all(buf).expect("Not a complete write!");
+    }
+}
@@ -0,0 +1,80 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `alloc_data`'s `hint` steers allocation toward the bitmap block holding
+/// the hint rather than whichever bitmap block happens to contain the
+/// globally-lowest free bit, so a growing file's blocks cluster near each
+/// other instead of scattering to wherever the next free bit is. Each
+/// data bitmap block covers 4096 bits, so a big enough image is needed to
+/// have more than one of them to demonstrate the preference.
+#[test]
+fn alloc_data_prefers_the_hints_bitmap_block_over_a_lower_free_bit_elsewhere() {
+    const BITS_PER_BITMAP_BLOCK: u32 = 4096;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-alloc-data-locality-hint-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    // Large enough to need three data bitmap blocks.
+    file.set_len(10000 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 10000, 1, 0).unwrap();
+
+    let first_block = {
+        let mut fs = efs.lock();
+        fs.alloc_data(0, None).unwrap()
+    };
+
+    // Fill the rest of the first bitmap block completely.
+    let last_in_first_block = {
+        let mut fs = efs.lock();
+        let mut last = first_block;
+        for _ in 1..BITS_PER_BITMAP_BLOCK {
+            last = fs.alloc_data(0, None).unwrap();
+        }
+        last
+    };
+
+    // Move into the second bitmap block and allocate a small run there —
+    // this is the "file" whose locality we care about.
+    let mut fs = efs.lock();
+    let run_start = fs.alloc_data(0, None).unwrap();
+    assert!(
+        run_start >= first_block + BITS_PER_BITMAP_BLOCK,
+        "the first bitmap block is full, so this should have spilled into the second"
+    );
+    let mut last_in_run = run_start;
+    for _ in 0..4 {
+        last_in_run = fs.alloc_data(0, Some(last_in_run)).unwrap();
+    }
+
+    // Free up a "decoy" hole back in the first bitmap block, far from the
+    // run — the globally-lowest free bit, but not where the run lives.
+    fs.dealloc_data(0, last_in_first_block);
+    let decoy_hole = last_in_first_block;
+
+    // With the hint pointing at the run, the next block should still land
+    // in the second bitmap block, close to the run...
+    let next_with_hint = fs.alloc_data(0, Some(last_in_run)).unwrap();
+    assert!(
+        next_with_hint >= first_block + BITS_PER_BITMAP_BLOCK,
+        "a hint inside the run should keep allocating in the same bitmap block, \
+         not jump back to the decoy hole at {decoy_hole}"
+    );
+
+    // ...whereas a hint-less (round-robin-style) allocation falls back to
+    // scanning from the start of the disk and picks up the decoy hole
+    // instead, even though it's much farther from any of the run's blocks.
+    let next_without_hint = fs.alloc_data(0, None).unwrap();
+    assert_eq!(next_without_hint, decoy_hole);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,40 @@
+use easy_fs::{Bitmap, BlockDevice};
+use std::sync::Mutex;
+
+struct MemoryDevice {
+    data: Mutex<Vec<u8>>,
+}
+impl MemoryDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+        }
+    }
+}
+impl BlockDevice for MemoryDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+}
+
+/// `try_dealloc` returns `false` instead of panicking on a bit that's
+/// already free, unlike the asserting `dealloc` it backs — the property an
+/// fsck/recovery tool needs to walk into already-freed blocks safely.
+#[test]
+fn try_dealloc_twice_on_the_same_bit_returns_false_the_second_time() {
+    let device: std::sync::Arc<dyn BlockDevice> = std::sync::Arc::new(MemoryDevice::new(8));
+    let bitmap = Bitmap::new(0, 4);
+
+    let bit = bitmap.alloc(&device, None).unwrap();
+    assert!(bitmap.try_dealloc(&device, bit));
+    assert!(!bitmap.try_dealloc(&device, bit));
+
+    // The bit is free again and can be reused normally.
+    let reallocated = bitmap.alloc(&device, None).unwrap();
+    assert_eq!(reallocated, bit);
+}
@@ -0,0 +1,60 @@
+use easy_fs::{get_block_cache, DiskInode, EasyFileSystem};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `can_reclaim` is true only once a file has no directory entries left
+/// (`hardlink_count() == 0`) *and* nothing else still has it open
+/// (`open_count() <= 1`, the `1` being the caller's own reference). There's
+/// no `link`/`unlink` on `Inode` yet to drive `nlink` down for real, so
+/// this pokes it directly — what a future unlink would do — while holding
+/// a second `Arc` to stand in for another open handle.
+#[test]
+fn can_reclaim_stays_false_until_both_the_link_and_the_extra_open_are_gone() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-can-reclaim-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let target = root_inode.create("reclaim-me").unwrap();
+    assert_eq!(target.hardlink_count(), 1);
+    assert!(
+        !target.can_reclaim(),
+        "still linked, shouldn't be reclaimable"
+    );
+
+    let second_handle = target.clone();
+    assert_eq!(target.open_count(), 2);
+
+    let (block_id, block_offset) = efs.lock().get_disk_inode_pos(target.inode_id());
+    get_block_cache(block_id as usize, block_device.clone())
+        .lock()
+        .modify(block_offset, |disk_inode: &mut DiskInode| {
+            disk_inode.nlink = 0;
+        });
+
+    assert_eq!(target.hardlink_count(), 0);
+    assert!(
+        !target.can_reclaim(),
+        "unlinked but still open elsewhere, shouldn't be reclaimable yet"
+    );
+
+    drop(second_handle);
+    assert_eq!(target.open_count(), 1);
+    assert!(
+        target.can_reclaim(),
+        "unlinked and no longer open elsewhere, should be reclaimable"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,52 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `set_case_insensitive` makes `find` match this directory's entries
+/// ASCII-case-insensitively, without touching the original casing stored
+/// on disk. Off by default.
+#[test]
+fn find_matches_case_insensitively_only_once_enabled() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-case-insensitive-lookup-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let created = root_inode.create("File.txt").unwrap();
+
+    assert!(!root_inode.is_case_insensitive());
+    assert!(root_inode.find("file.TXT").is_none());
+    assert!(root_inode.find("File.txt").is_some());
+
+    root_inode.set_case_insensitive(true);
+    assert!(root_inode.is_case_insensitive());
+    let found = root_inode.find("file.TXT").unwrap();
+    assert_eq!(found.inode_id(), created.inode_id());
+
+    // Original casing on disk is unaffected; an exact-case lookup still
+    // works too.
+    assert_eq!(
+        root_inode.ls(),
+        vec!["File.txt".to_string()],
+        "the stored name shouldn't change just because lookups are now case-insensitive"
+    );
+    assert_eq!(
+        root_inode.find("File.txt").unwrap().inode_id(),
+        created.inode_id()
+    );
+
+    root_inode.set_case_insensitive(false);
+    assert!(root_inode.find("file.TXT").is_none());
+
+    std::fs::remove_file(&path).unwrap();
+}
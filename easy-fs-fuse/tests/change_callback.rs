@@ -0,0 +1,52 @@
+use easy_fs::{EasyFileSystem, FsEvent, FsEventKind};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::{Arc, Mutex};
+
+/// `set_change_callback` is the extension point a future inotify would
+/// hang off of: registering one makes `create`/`write_at` report an
+/// `FsEvent` naming the inode and the kind of change, after it's already
+/// taken effect.
+#[test]
+fn registered_callback_fires_with_the_right_inode_id_and_kind() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-change-callback-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let events: Arc<Mutex<Vec<FsEvent>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorded = events.clone();
+    efs.lock().set_change_callback(Some(Box::new(move |event| {
+        recorded.lock().unwrap().push(event);
+    })));
+
+    let target = root_inode.create("watched.bin").unwrap();
+    let after_create = events.lock().unwrap().clone();
+    assert_eq!(after_create.len(), 1);
+    assert_eq!(after_create[0].inode_id, target.inode_id());
+    assert_eq!(after_create[0].kind, FsEventKind::Create);
+
+    target.write_at(0, &[1u8; 16]);
+    let after_write = events.lock().unwrap().clone();
+    assert_eq!(after_write.len(), 2);
+    assert_eq!(after_write[1].inode_id, target.inode_id());
+    assert_eq!(after_write[1].kind, FsEventKind::Write);
+
+    // Unregistering stops further events without disturbing the ones
+    // already recorded.
+    efs.lock().set_change_callback(None);
+    target.write_at(16, &[2u8; 16]);
+    assert_eq!(events.lock().unwrap().len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
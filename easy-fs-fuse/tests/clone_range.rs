@@ -0,0 +1,45 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `clone_range` copies a byte range from one inode to another without
+/// routing it back through a caller-supplied buffer in between.
+#[test]
+fn clone_range_copies_bytes_between_files() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-clone-range-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let src = root_inode.create("src.bin").unwrap();
+    let payload: Vec<u8> = (0..2000).map(|i| (i % 256) as u8).collect();
+    src.write_at(0, &payload);
+
+    let dst = root_inode.create("dst.bin").unwrap();
+    // Leave the first 100 bytes of dst alone and splice the middle 800
+    // bytes of src in after them.
+    dst.write_at(0, &[0xAAu8; 100]);
+    let copied = src.clone_range(500, &dst, 100, 800);
+    assert_eq!(copied, 800);
+
+    let mut head = [0u8; 100];
+    assert_eq!(dst.read_at(0, &mut head), 100);
+    assert_eq!(head, [0xAAu8; 100]);
+
+    let mut spliced = vec![0u8; 800];
+    assert_eq!(dst.read_at(100, &mut spliced), 800);
+    assert_eq!(spliced, payload[500..1300]);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,63 @@
+use easy_fs::{get_block_cache, BlockDevice};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A `BlockDevice` that counts how many times each of `read_block` and
+/// `read_blocks` gets called, to prove `BlockCacheManager` issues one
+/// batched `read_blocks` request per cluster on a cold cache rather than
+/// one `read_block` request per block.
+struct CountingDevice {
+    data: Mutex<Vec<u8>>,
+    read_block_calls: AtomicUsize,
+    read_blocks_calls: AtomicUsize,
+}
+
+impl CountingDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+            read_block_calls: AtomicUsize::new(0),
+            read_blocks_calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl BlockDevice for CountingDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.read_block_calls.fetch_add(1, Ordering::SeqCst);
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+
+    fn read_blocks(&self, start_block_id: usize, buf: &mut [u8]) {
+        self.read_blocks_calls.fetch_add(1, Ordering::SeqCst);
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[start_block_id * 512..start_block_id * 512 + buf.len()]);
+    }
+}
+
+#[test]
+fn touching_a_cluster_issues_one_batched_read() {
+    let counting = Arc::new(CountingDevice::new(16));
+    let device: Arc<dyn BlockDevice> = counting.clone();
+
+    for block_id in 0..8 {
+        get_block_cache(block_id, device.clone());
+    }
+
+    assert_eq!(
+        counting.read_block_calls.load(Ordering::SeqCst),
+        0,
+        "no block in the cluster should fall back to a single-block read"
+    );
+    assert_eq!(
+        counting.read_blocks_calls.load(Ordering::SeqCst),
+        1,
+        "8 consecutive blocks share one cluster and should issue exactly one read_blocks call"
+    );
+}
@@ -0,0 +1,52 @@
+use easy_fs::{get_block_cache, DataBlock, DiskInode, EasyFileSystem, DIRENT_SZ};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `DirEntry::name` returns `None` instead of panicking when the on-disk
+/// name has no NUL terminator, and directory iteration (`ls`, `find`'s
+/// index build) just skips entries it can't name rather than crashing.
+#[test]
+fn unterminated_dirent_name_is_skipped_not_panicked_on() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-corrupt-dirent-name-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    root_inode.create("good").unwrap();
+    root_inode.create("bad").unwrap();
+    easy_fs::block_cache_sync_all();
+
+    let (meta_block, meta_offset) = efs.lock().get_disk_inode_pos(root_inode.inode_id());
+    let data_block_id = get_block_cache(meta_block as usize, block_device.clone())
+        .lock()
+        .read(meta_offset, |disk_inode: &DiskInode| disk_inode.direct[0]);
+
+    // "bad"'s dirent is the second one written, at offset DIRENT_SZ. Fill
+    // its name field with non-zero bytes so there's no NUL terminator
+    // anywhere in it.
+    get_block_cache(data_block_id as usize, block_device.clone())
+        .lock()
+        .modify(0, |data_block: &mut DataBlock| {
+            for byte in &mut data_block[DIRENT_SZ..DIRENT_SZ + DIRENT_SZ - 4] {
+                *byte = 0x41;
+            }
+        });
+    easy_fs::block_cache_sync_all();
+
+    let names = root_inode.ls();
+    assert_eq!(names, vec!["good".to_string()]);
+    assert!(root_inode.find("good").is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,40 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// The rendezvous-blocking half of named pipes (`sys_mknod`, `open_fifo_read`/
+/// `open_fifo_write`) lives in `os/` and needs real process scheduling to
+/// exercise, but the on-disk half — `Inode::create_fifo` stamping a
+/// `DiskInodeType::Fifo` inode that `is_fifo()`/`find` report correctly — is
+/// plain `easy-fs` and testable here.
+#[test]
+fn create_fifo_makes_a_findable_fifo_inode() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-create-fifo-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let fifo = root_inode.create_fifo("named-pipe").unwrap();
+    assert!(fifo.is_fifo());
+    assert!(!fifo.is_dir());
+    assert!(!fifo.is_device());
+
+    let reopened = root_inode.find("named-pipe").unwrap();
+    assert!(reopened.is_fifo());
+    assert_eq!(reopened.inode_id(), fifo.inode_id());
+
+    assert!(root_inode.create_fifo("named-pipe").is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
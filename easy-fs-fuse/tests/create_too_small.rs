@@ -0,0 +1,32 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `create` rejects a `total_blocks` too small to fit the superblock, its
+/// backup, the requested inode region, and at least one data bitmap block
+/// plus one data block, instead of underflowing the subtraction that
+/// derives `data_total_blocks`.
+#[test]
+fn create_with_an_absurdly_small_total_blocks_returns_none() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-create-too-small-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+
+    assert!(EasyFileSystem::create(block_device.clone(), 1, 1, 0).is_none());
+    assert!(EasyFileSystem::create(block_device.clone(), 4, 1, 0).is_none());
+
+    // A large enough image with the same bitmap size still works.
+    assert!(EasyFileSystem::create(block_device, 8192, 1, 0).is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
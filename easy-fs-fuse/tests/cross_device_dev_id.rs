@@ -0,0 +1,46 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `Inode::dev_id` forwards the `dev_id` its `EasyFileSystem` was created
+/// with, rather than a hardcoded value, so `Stat.dev` can reflect which
+/// mounted image a file actually came from.
+///
+/// This can't be shown with two filesystems mounted at once in the same
+/// process: `BLOCK_CACHE_MANAGER` pins the superblock/bitmap blocks by
+/// block id alone, with no notion of which device they belong to, so a
+/// second image sharing those same low block ids would read back the
+/// first image's pinned content instead of its own (confirmed — creating
+/// a second filesystem this way makes its own root directory appear to
+/// already contain entries from the first). So this mounts one image with
+/// a non-default `dev_id` and checks it comes through untouched, instead
+/// of the `0` a hardcoded value would report.
+#[test]
+fn inode_dev_id_matches_the_value_the_filesystem_was_created_with() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-cross-device-dev-id-test.img");
+    let _ = std::fs::remove_file(&path);
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 77).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let child = root_inode.create("tagged.txt").unwrap();
+
+    assert_eq!(root_inode.dev_id(), 77);
+    assert_eq!(child.dev_id(), 77);
+    assert_ne!(
+        child.dev_id(),
+        0,
+        "dev_id must come from the constructor, not a hardcoded default"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,45 @@
+use easy_fs::{get_block_cache, DiskInode, EasyFileSystem};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// Nothing in this tree creates a device special file yet (there's no
+/// `create_device` on `Inode`), but the `DiskInodeType::Device` layout and
+/// `Inode::is_device`/`device` plumbing are in place. Poke a freshly
+/// allocated inode into a device directly via `DiskInode::initialize_device`
+/// — what a future `create_device` would do — and confirm the `Inode`
+/// wrapper reports it correctly.
+#[test]
+fn an_inode_initialized_as_a_device_reports_its_type_and_major_minor() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-device-inode-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let placeholder = root_inode.create("dev-placeholder").unwrap();
+    let (block_id, block_offset) = efs.lock().get_disk_inode_pos(placeholder.inode_id());
+    get_block_cache(block_id as usize, block_device.clone())
+        .lock()
+        .modify(block_offset, |disk_inode: &mut DiskInode| {
+            disk_inode.initialize_device(8, 1);
+        });
+    easy_fs::block_cache_sync_all();
+
+    let reopened = root_inode.find("dev-placeholder").unwrap();
+    assert!(reopened.is_device());
+    assert_eq!(reopened.device(), (8, 1));
+    assert!(!reopened.is_dir());
+    assert!(!reopened.is_fifo());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,41 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// The cached name index built by the first `find` in a directory must be
+/// invalidated by a later `create`, or the new entry (and anything after
+/// it in iteration order) would stay invisible to `find` until something
+/// else happened to rebuild the cache.
+#[test]
+fn find_sees_entries_created_after_the_name_index_was_built() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-dir-index-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    root_inode.create("a").unwrap();
+    root_inode.create("b").unwrap();
+    // Force the name index to build with only "a" and "b" in it.
+    assert!(root_inode.find("a").is_some());
+    assert!(root_inode.find("missing").is_none());
+
+    root_inode.create("c").unwrap();
+    assert!(
+        root_inode.find("c").is_some(),
+        "find should see a file created after the name index was already built"
+    );
+    assert!(root_inode.find("a").is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,57 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `read_at`/`write_at` take a `direct_only` fast path that indexes
+/// `DiskInode::direct` straight through instead of going via
+/// `get_block_id`'s direct/indirect1/indirect2 cascade, whenever the
+/// accessed range stays within `INODE_DIRECT_COUNT * BLOCK_SZ`. The
+/// highest-risk spot for that kind of boundary check is exactly at the
+/// edge, so this writes a file straddling it and reads back across it.
+#[test]
+fn read_write_round_trip_across_the_direct_only_boundary() {
+    const BLOCK_SZ: usize = 512;
+    const INODE_DIRECT_COUNT: usize = 28;
+    const BOUNDARY: usize = INODE_DIRECT_COUNT * BLOCK_SZ;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-direct-only-boundary-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let straddling = root_inode.create("straddling.bin").unwrap();
+
+    // 64 bytes straddling the boundary: the first 32 land entirely
+    // within the direct-only region, the next 32 spill past it into the
+    // first indirect1-addressed block.
+    let payload: Vec<u8> = (0..64u32).map(|i| i as u8).collect();
+    let start = BOUNDARY - 32;
+    straddling.write_at(start, &payload);
+
+    let mut readback = vec![0u8; 64];
+    assert_eq!(straddling.read_at(start, &mut readback), 64);
+    assert_eq!(readback, payload);
+
+    // Read just the direct-only half and just the spilled-over half on
+    // their own too, since the fast path is keyed on where *the read*
+    // ends, not where the file's total size ends.
+    let mut only_direct = vec![0u8; 32];
+    assert_eq!(straddling.read_at(start, &mut only_direct), 32);
+    assert_eq!(only_direct, payload[..32]);
+
+    let mut only_indirect = vec![0u8; 32];
+    assert_eq!(straddling.read_at(BOUNDARY, &mut only_indirect), 32);
+    assert_eq!(only_indirect, payload[32..]);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,67 @@
+use easy_fs::{EasyFileSystem, FsError};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// Caps the root owner's quota at one data block to simulate a full disk
+/// without actually writing thousands of files, then confirms the create
+/// that first needs a second data block fails cleanly with `NoSpace`
+/// rather than panicking, and that the inode it had to roll back doesn't
+/// leak: the next `alloc_inode` gets it right back.
+#[test]
+fn full_filesystem_returns_no_space_cleanly_and_does_not_leak_inodes() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-enospc-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    // The root directory's own entries are charged to owner 0, same as
+    // every inode's default `owner`.
+    efs.lock().set_quota(0, 1);
+
+    let mut created = 0usize;
+    loop {
+        match root_inode.create(&format!("f{created}")) {
+            Ok(_) => created += 1,
+            Err(FsError::NoSpace) => break,
+            Err(e) => panic!("unexpected error filling filesystem: {e:?}"),
+        }
+        assert!(created < 10_000, "filesystem never reported NoSpace");
+    }
+    assert!(
+        created > 0,
+        "one data block should hold more than zero directory entries"
+    );
+
+    // No phantom entries: a leaked inode on the failed attempt would still
+    // show up as an extra, half-initialized directory entry.
+    assert_eq!(root_inode.ls().len(), created);
+
+    // Retrying immediately keeps failing cleanly (no panic, no corruption)
+    // rather than panicking the way a bare `.unwrap()` on the bitmap would.
+    for _ in 0..3 {
+        assert_eq!(
+            root_inode.create("still-no-space").err(),
+            Some(FsError::NoSpace)
+        );
+    }
+
+    // The failed create above allocated inode `created + 1` for the new
+    // file, then had to roll it back when growing the root directory past
+    // its quota. If that rollback leaked the inode, the next `alloc_inode`
+    // would skip past it to `created + 2`; bitmaps allocate the lowest
+    // free id, so getting it back confirms it was freed.
+    let reclaimed_id = efs.lock().alloc_inode().expect("inode bitmap has room");
+    assert_eq!(reclaimed_id, created as u32 + 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
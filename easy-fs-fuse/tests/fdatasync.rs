@@ -0,0 +1,82 @@
+use easy_fs::{block_cache_sync_all, BlockDevice, EasyFileSystem};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A `BlockDevice` that counts `write_block` calls per block id, so a test
+/// can tell whether one particular block was actually rewritten.
+struct CountingDevice {
+    data: Mutex<Vec<u8>>,
+    write_counts: Mutex<HashMap<usize, usize>>,
+}
+
+impl CountingDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+            write_counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn writes_to(&self, block_id: usize) -> usize {
+        *self
+            .write_counts
+            .lock()
+            .unwrap()
+            .get(&block_id)
+            .unwrap_or(&0)
+    }
+}
+
+impl BlockDevice for CountingDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        *self
+            .write_counts
+            .lock()
+            .unwrap()
+            .entry(block_id)
+            .or_insert(0) += 1;
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+}
+
+/// `set_owner` (like `set_case_insensitive`) dirties the inode's metadata
+/// (`meta_dirty`) without an immediate `block_cache_sync_all` of its own —
+/// every write path eagerly flushes as
+/// part of the same call, leaving nothing for a later `fsync`/`fdatasync`
+/// to actually do, so it's the only way to observe `fdatasync`'s
+/// conditional metadata flush do real work: it writes the metadata block
+/// exactly once when it's genuinely dirty, and not again once it's clean.
+#[test]
+fn fdatasync_flushes_dirty_metadata_once_and_is_a_no_op_once_clean() {
+    let device = Arc::new(CountingDevice::new(8192));
+    let efs = EasyFileSystem::create(device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("watched.bin").unwrap();
+    let (metadata_block, _offset) = efs.lock().get_disk_inode_pos(file.inode_id());
+    let metadata_block = metadata_block as usize;
+    block_cache_sync_all();
+
+    file.set_owner(7);
+    let before = device.writes_to(metadata_block);
+    file.fdatasync();
+    assert_eq!(
+        device.writes_to(metadata_block) - before,
+        1,
+        "fdatasync should flush metadata that a set_owner left genuinely dirty"
+    );
+
+    let before = device.writes_to(metadata_block);
+    file.fdatasync();
+    assert_eq!(
+        device.writes_to(metadata_block) - before,
+        0,
+        "fdatasync shouldn't rewrite a metadata block that's already clean"
+    );
+    assert_eq!(file.owner(), 7);
+}
@@ -0,0 +1,40 @@
+use easy_fs::{EasyFileSystem, Inode};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `find_path` walks `/`-separated components, treating `.` as a no-op
+/// and resolving `..` via the directory an inode was last reached
+/// through — or, for an inode with no recorded parent (nothing else in
+/// this flat filesystem creates subdirectories yet), leaving it in place
+/// rather than panicking or wrongly failing the lookup.
+#[test]
+fn find_path_resolves_dot_and_plain_components() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-find-path-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    root_inode.create("leaf.txt").unwrap();
+
+    assert!(Inode::find_path(&root_inode, "leaf.txt").is_some());
+    assert!(Inode::find_path(&root_inode, "./leaf.txt").is_some());
+    assert!(Inode::find_path(&root_inode, "missing.txt").is_none());
+
+    // The root has no recorded parent, so ".." from it is a no-op that
+    // still lands back on root rather than erroring.
+    let via_dotdot = Inode::find_path(&root_inode, "../leaf.txt");
+    assert!(via_dotdot.is_some());
+    assert!(root_inode.parent().is_none());
+
+    std::fs::remove_file(&path).unwrap();
+}
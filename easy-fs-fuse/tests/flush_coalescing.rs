@@ -0,0 +1,63 @@
+use easy_fs::{block_cache_sync_all, get_block_cache, BlockDevice};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+struct CountingDevice {
+    data: Mutex<Vec<u8>>,
+    write_block_calls: AtomicUsize,
+    write_blocks_calls: AtomicUsize,
+}
+impl CountingDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+            write_block_calls: AtomicUsize::new(0),
+            write_blocks_calls: AtomicUsize::new(0),
+        }
+    }
+}
+impl BlockDevice for CountingDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.write_block_calls.fetch_add(1, Ordering::Relaxed);
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+    fn write_blocks(&self, start_block_id: usize, buf: &[u8]) {
+        self.write_blocks_calls.fetch_add(1, Ordering::Relaxed);
+        let mut data = self.data.lock().unwrap();
+        let start = start_block_id * 512;
+        data[start..start + buf.len()].copy_from_slice(buf);
+    }
+}
+
+/// `block_cache_sync_all` detects runs of physically adjacent dirty blocks
+/// and flushes each run with a single `write_blocks` call instead of one
+/// `write_block` per block.
+#[test]
+fn sync_all_coalesces_a_contiguous_dirty_run_into_one_write_blocks_call() {
+    let device = Arc::new(CountingDevice::new(32));
+
+    for block_id in 10..20 {
+        get_block_cache(block_id, device.clone())
+            .lock()
+            .modify(0, |data: &mut [u8; 512]| data[0] = block_id as u8);
+    }
+    block_cache_sync_all();
+
+    assert_eq!(
+        device.write_blocks_calls.load(Ordering::Relaxed),
+        1,
+        "ten contiguous dirty blocks should flush as one write_blocks call"
+    );
+    assert_eq!(device.write_block_calls.load(Ordering::Relaxed), 0);
+
+    for block_id in 10..20 {
+        let mut buf = [0u8; 512];
+        device.read_block(block_id, &mut buf);
+        assert_eq!(buf[0], block_id as u8);
+    }
+}
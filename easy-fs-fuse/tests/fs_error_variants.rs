@@ -0,0 +1,39 @@
+use easy_fs::{EasyFileSystem, FsError};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `create` reports `FsError::TooLong` for a name that won't fit in a
+/// `DirEntry`, distinctly from `Exists`/`NoSpace` (covered by
+/// `create_fifo.rs`/`enospc.rs`) — the whole point of a typed error over
+/// a bare `None`/panic.
+#[test]
+fn create_with_a_too_long_name_returns_too_long() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-fs-error-variants-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let too_long_name = "x".repeat(64);
+    assert_eq!(
+        root_inode.create(&too_long_name).err(),
+        Some(FsError::TooLong)
+    );
+
+    // A name that fits is unaffected.
+    let fitting_name = "ok.txt";
+    assert!(root_inode.create(fitting_name).is_ok());
+    assert_eq!(root_inode.create(fitting_name).err(), Some(FsError::Exists));
+
+    std::fs::remove_file(&path).unwrap();
+}
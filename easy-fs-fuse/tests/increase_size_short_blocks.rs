@@ -0,0 +1,59 @@
+use easy_fs::{get_block_cache, DiskInode, EasyFileSystem};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `DiskInode::increase_size` returns `false` without touching the inode
+/// when handed fewer blocks than `blocks_num_needed` calls for, instead of
+/// panicking partway through on `new_blocks.next().unwrap()`.
+#[test]
+fn increase_size_with_a_short_block_vec_returns_false_and_leaves_the_inode_alone() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-increase-size-short-blocks-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device: Arc<dyn easy_fs::BlockDevice> = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let target = root_inode.create("grows.bin").unwrap();
+    let (block_id, block_offset) = efs.lock().get_disk_inode_pos(target.inode_id());
+
+    let new_size = 100 * 512;
+    let (needed, grew) = get_block_cache(block_id as usize, block_device.clone())
+        .lock()
+        .modify(block_offset, |disk_inode: &mut DiskInode| {
+            let needed = disk_inode.blocks_num_needed(new_size);
+            assert!(
+                needed > 1,
+                "test needs a growth that spans more than one block"
+            );
+            // One block short of what `new_size` actually needs.
+            let short_supply: Vec<u32> = (1000..1000 + needed - 1).collect();
+            let grew = disk_inode.increase_size(new_size, short_supply, &block_device);
+            (needed, grew)
+        });
+    assert!(
+        !grew,
+        "a short block supply should be rejected, not panicked on"
+    );
+    assert!(needed > 1);
+
+    get_block_cache(block_id as usize, block_device.clone())
+        .lock()
+        .read(block_offset, |disk_inode: &DiskInode| {
+            assert_eq!(
+                disk_inode.size, 0,
+                "the inode should be untouched after a rejected growth"
+            );
+            assert!(disk_inode.is_inline());
+        });
+
+    std::fs::remove_file(&path).unwrap();
+}
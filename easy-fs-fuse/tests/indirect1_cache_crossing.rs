@@ -0,0 +1,59 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `DiskInode::read_at`/`write_at` cache the current indirect1 block's 128
+/// entries while iterating within its range, only re-fetching when the
+/// scan crosses into a different indirect1 block (directly, or one of the
+/// ones `indirect2` points at). The highest-risk spot for that kind of
+/// cache is exactly at a crossing, so this reads/writes a span straddling
+/// the boundary between two indirect2-addressed indirect1 blocks.
+#[test]
+fn read_write_round_trip_across_an_indirect1_block_crossing() {
+    const BLOCK_SZ: usize = 512;
+    const DIRECT_BOUND: usize = 28;
+    const INODE_INDIRECT1_COUNT: usize = 128;
+    // First block addressed through the *second* indirect1 block that
+    // indirect2 points at.
+    const CROSSING_BLOCK: usize = DIRECT_BOUND + INODE_INDIRECT1_COUNT + INODE_INDIRECT1_COUNT;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-indirect1-cache-crossing-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    // Big enough to hold well past the crossing point.
+    file.set_len(32768 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 32768, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let big = root_inode.create("crossing.bin").unwrap();
+
+    // 64 bytes straddling the indirect1-block crossing, 32 on each side.
+    let payload: Vec<u8> = (0..64u32).map(|i| i as u8).collect();
+    let start = CROSSING_BLOCK * BLOCK_SZ - 32;
+    big.write_at(start, &payload);
+
+    let mut readback = vec![0u8; 64];
+    assert_eq!(big.read_at(start, &mut readback), 64);
+    assert_eq!(readback, payload);
+
+    let mut before_crossing = vec![0u8; 32];
+    assert_eq!(big.read_at(start, &mut before_crossing), 32);
+    assert_eq!(before_crossing, payload[..32]);
+
+    let mut after_crossing = vec![0u8; 32];
+    assert_eq!(
+        big.read_at(CROSSING_BLOCK * BLOCK_SZ, &mut after_crossing),
+        32
+    );
+    assert_eq!(after_crossing, payload[32..]);
+
+    std::fs::remove_file(&path).unwrap();
+}
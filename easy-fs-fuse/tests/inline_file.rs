@@ -0,0 +1,60 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// A fresh file is created as `DiskInodeType::InlineFile`: content up to
+/// `INLINE_CAPACITY` (the 28 direct pointers' own 112 bytes, reused as
+/// storage) lives in the inode itself, needing no data block and no extra
+/// I/O to read it back. Growing past that limit converts it to a regular
+/// `File` with real data blocks, transparently to the caller.
+#[test]
+fn a_tiny_file_stays_inline_until_it_outgrows_the_limit() {
+    const INLINE_CAPACITY: usize = 112;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-inline-file-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let tiny = root_inode.create("tiny.txt").unwrap();
+
+    let payload = vec![b'x'; 50];
+    tiny.write_at(0, &payload);
+    let mut readback = vec![0u8; 50];
+    assert_eq!(tiny.read_at(0, &mut readback), 50);
+    assert_eq!(readback, payload);
+    assert_eq!(
+        tiny.blocks_used(),
+        0,
+        "a 50-byte file should fit entirely inline, with no data block allocated"
+    );
+
+    // Grow past the inline limit: this should convert the file and start
+    // allocating real data blocks, without losing what was already there.
+    let more = vec![b'y'; 200];
+    tiny.write_at(INLINE_CAPACITY, &more);
+    // `read_at` flushes the write-combining buffer before reading, so the
+    // conversion has definitely happened by the time we check below.
+    let mut full_readback = vec![0u8; INLINE_CAPACITY + 200];
+    assert_eq!(tiny.read_at(0, &mut full_readback), INLINE_CAPACITY + 200);
+    assert_eq!(&full_readback[..50], payload.as_slice());
+    assert!(full_readback[50..INLINE_CAPACITY].iter().all(|&b| b == 0));
+    assert_eq!(&full_readback[INLINE_CAPACITY..], more.as_slice());
+
+    assert!(
+        tiny.blocks_used() > 0,
+        "growing past {INLINE_CAPACITY} bytes should convert to a regular file with real data blocks"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,51 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `Stat.ino` is `Inode::inode_id()` forwarded as-is by `read_stat`, so the
+/// property that actually has to hold is here at the `easy-fs` level: every
+/// file gets a distinct id, and those ids match the order `alloc_inode`
+/// handed them out in, not the block slot they happen to land in.
+#[test]
+fn distinct_files_report_distinct_ino_values_matching_allocation_order() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-ino-allocation-order-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let names = ["alpha", "beta", "gamma"];
+    let ids: Vec<u32> = names
+        .iter()
+        .map(|name| root_inode.create(name).unwrap().inode_id())
+        .collect();
+
+    let mut sorted = ids.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    assert_eq!(
+        sorted.len(),
+        ids.len(),
+        "every file should get a distinct ino"
+    );
+    assert!(
+        ids.windows(2).all(|w| w[1] > w[0]),
+        "ino values should increase in the order the files were created: {ids:?}"
+    );
+
+    for (name, expected_id) in names.iter().zip(ids.iter()) {
+        assert_eq!(root_inode.find(name).unwrap().inode_id(), *expected_id);
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
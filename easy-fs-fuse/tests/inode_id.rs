@@ -0,0 +1,48 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `Inode::inode_id` is the logical inode number used by
+/// `EasyFileSystem::get_disk_inode_pos` — the root is always 0, each new
+/// file gets the id `alloc_inode` handed out for it, and the mapping is
+/// stable across a remount rather than tied to whatever happens to be
+/// cached.
+#[test]
+fn inode_id_matches_allocation_order_and_survives_a_remount() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-inode-id-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    assert_eq!(root_inode.inode_id(), 0);
+
+    let first = root_inode.create("first").unwrap();
+    let second = root_inode.create("second").unwrap();
+    assert_ne!(first.inode_id(), second.inode_id());
+    assert_eq!(second.inode_id(), first.inode_id() + 1);
+
+    let first_id = first.inode_id();
+    let second_id = second.inode_id();
+    easy_fs::block_cache_sync_all();
+    drop(first);
+    drop(second);
+    drop(root_inode);
+
+    let efs = EasyFileSystem::open(block_device, 0);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    assert_eq!(root_inode.inode_id(), 0);
+    assert_eq!(root_inode.find("first").unwrap().inode_id(), first_id);
+    assert_eq!(root_inode.find("second").unwrap().inode_id(), second_id);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,36 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `is_dir` is what `open_file`'s `O_DIRECTORY` handling (os/src/fs/inode.rs)
+/// checks before letting a non-directory through: the root inode should
+/// report itself as a directory, while a regular file or a fifo created
+/// inside it should not.
+#[test]
+fn is_dir_distinguishes_the_root_from_files_and_fifos() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-is-dir-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    assert!(root_inode.is_dir());
+
+    let plain = root_inode.create("plain.txt").unwrap();
+    assert!(!plain.is_dir());
+
+    let pipe = root_inode.create_fifo("a-pipe").unwrap();
+    assert!(!pipe.is_dir());
+
+    std::fs::remove_file(&path).unwrap();
+}
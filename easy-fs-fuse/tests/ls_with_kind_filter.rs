@@ -0,0 +1,52 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `sys_getdents`'s type filter (os/src/syscall) is built on
+/// `Inode::ls_with_kind`, which pairs each directory entry's name with
+/// whether it's itself a directory — that's the piece host-testable from
+/// here. This tree has no `mkdir`/subdirectory-creation API (`create`/
+/// `create_fifo` are the only ways to add an entry), so a listing mixing
+/// real subdirectories with files can't actually be constructed outside
+/// the kernel; what's checked instead is that every entry this layer can
+/// create comes back correctly tagged as "not a directory", so a
+/// directories-only filter built on top of this would correctly come back
+/// empty rather than silently matching everything.
+#[test]
+fn ls_with_kind_tags_every_creatable_entry_as_not_a_directory() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-ls-with-kind-filter-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    root_inode.create("plain.txt").unwrap();
+    root_inode.create_fifo("a-pipe").unwrap();
+
+    let entries = root_inode.ls_with_kind();
+    let mut names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["a-pipe", "plain.txt"]);
+    assert!(
+        entries.iter().all(|(_, is_dir)| !is_dir),
+        "neither a plain file nor a fifo should be tagged as a directory"
+    );
+
+    let directories_only: Vec<&str> = entries
+        .iter()
+        .filter(|(_, is_dir)| *is_dir)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert!(directories_only.is_empty());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,55 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// Quotas are tracked per owner id, not globally: capping one owner's
+/// quota blocks only that owner's further allocations, while a different
+/// owner with no quota set keeps writing freely. `enospc.rs` already
+/// covers a single owner hitting its own limit; this is about isolation
+/// between two owners sharing the same disk.
+#[test]
+fn a_tight_quota_on_one_owner_does_not_affect_another_owners_writes() {
+    const TENANT_A: u32 = 1;
+    const TENANT_B: u32 = 2;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-per-owner-quota-isolation-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let file_a = root_inode.create("tenant-a.bin").unwrap();
+    file_a.set_owner(TENANT_A);
+    let file_b = root_inode.create("tenant-b.bin").unwrap();
+    file_b.set_owner(TENANT_B);
+
+    efs.lock().set_quota(TENANT_A, 2);
+
+    // Tenant A can use its 2 blocks, but no more.
+    assert_eq!(file_a.write_at(0, &[1u8; 2 * 512]), 2 * 512);
+    assert_eq!(
+        file_a.write_at(2 * 512, &[1u8; 512]),
+        0,
+        "tenant A should be refused a third block once its quota of 2 is used up"
+    );
+
+    // Tenant B, with no quota configured, keeps writing well past what
+    // would have been tenant A's limit.
+    let big = vec![2u8; 10 * 512];
+    assert_eq!(file_b.write_at(0, &big), big.len());
+    let mut readback = vec![0u8; big.len()];
+    assert_eq!(file_b.read_at(0, &mut readback), big.len());
+    assert_eq!(readback, big);
+
+    std::fs::remove_file(&path).unwrap();
+}
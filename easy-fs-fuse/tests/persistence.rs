@@ -0,0 +1,49 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// Creates a filesystem on a `FileBlockDevice`, writes a file, drops the
+/// filesystem and inode handles (which flushes the write), reopens the same
+/// host file through a new `FileBlockDevice`, and reads the file back. The
+/// process-wide block cache still holds blocks (and the first device) from
+/// the first mount, so the reads may be served from that cache rather than
+/// from the host file.
+#[test]
+fn survives_close_and_reopen() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-persistence-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.set_len(8192 * 512).unwrap();
+        let block_device = Arc::new(FileBlockDevice::new(file));
+        let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+        let root_inode = EasyFileSystem::root_inode(&efs);
+        let inode = root_inode.create("hello.txt").unwrap();
+        inode.write_at(0, b"persisted across reopen");
+    }
+
+    {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+        let block_device = Arc::new(FileBlockDevice::new(file));
+        let efs = EasyFileSystem::open(block_device, 0);
+        let root_inode = EasyFileSystem::root_inode(&efs);
+        let inode = root_inode.find("hello.txt").unwrap();
+        let mut buf = [0u8; 23];
+        assert_eq!(inode.read_at(0, &mut buf), 23);
+        assert_eq!(&buf, b"persisted across reopen");
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,73 @@
+use easy_fs::{block_cache_sync_all, get_block_cache, EasyFileSystem};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// The superblock and bitmap blocks are pinned by `EasyFileSystem::open`/
+/// `create` so `BLOCK_CACHE_SIZE` eviction pressure never touches them:
+/// `get_block_cache` on a pinned id always hands back the very same
+/// `BlockCache` object, never a freshly reloaded one, no matter how much
+/// unrelated data-block churn happens in between. An ordinary data block
+/// gets no such guarantee — enough churn replaces its cache entry with a
+/// new one once it's evicted and touched again.
+#[test]
+fn superblocks_cache_entry_survives_cache_thrashing_unlike_an_ordinary_data_block() {
+    const SUPERBLOCK_ID: usize = 0;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-pinned-superblock-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    block_cache_sync_all();
+
+    let superblock_before = get_block_cache(SUPERBLOCK_ID, block_device.clone());
+    let first_data_file = root_inode.create("first.bin").unwrap();
+    first_data_file.write_at(0, &[1u8; 512]);
+    block_cache_sync_all();
+    // An ordinary, unpinned metadata block: the area holding inodes isn't
+    // part of `pin_metadata_blocks`'s set, only the superblock and the
+    // bitmaps are.
+    let (unpinned_block, _offset) = efs.lock().get_disk_inode_pos(first_data_file.inode_id());
+    let unpinned_block = unpinned_block as usize;
+    // Captured as a raw address rather than keeping the `Arc` itself alive
+    // here — holding a live reference would make the manager's own
+    // strong count > 1 and thus ineligible for eviction, masking the
+    // contrast this test wants to draw against the pinned superblock.
+    let unpinned_addr_before =
+        Arc::as_ptr(&get_block_cache(unpinned_block, block_device.clone())) as usize;
+
+    // `BLOCK_CACHE_SIZE` is 16; 40 separate single-block files spread
+    // across well more than that many distinct data blocks, forcing the
+    // evictable pool to churn repeatedly.
+    for i in 0..40 {
+        let f = root_inode.create(&format!("thrash{i}.bin")).unwrap();
+        f.write_at(0, &[i as u8; 512]);
+    }
+    block_cache_sync_all();
+
+    let superblock_after = get_block_cache(SUPERBLOCK_ID, block_device.clone());
+    assert!(
+        Arc::ptr_eq(&superblock_before, &superblock_after),
+        "the pinned superblock's cache entry should be the exact same object, never re-created"
+    );
+
+    let unpinned_addr_after =
+        Arc::as_ptr(&get_block_cache(unpinned_block, block_device.clone())) as usize;
+    assert_ne!(
+        unpinned_addr_before, unpinned_addr_after,
+        "an ordinary (unpinned) block should have been evicted and reloaded as a new object \
+         under this much churn, unlike the pinned superblock"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,62 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `punch_hole` frees the fully-covered blocks in its range back to the
+/// data bitmap for real, not just cosmetically in `Inode::blocks_used()`
+/// (covered separately by `sparse_blocks_used.rs`): once they're freed,
+/// the owner's quota has room again, so an allocation that was blocked
+/// before the hole starts succeeding afterward.
+#[test]
+fn punch_hole_frees_blocks_back_to_the_owners_quota() {
+    const BLOCK_SZ: u32 = 512;
+    const OWNER: u32 = 3;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-punch-hole-frees-quota-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let target = root_inode.create("owned.bin").unwrap();
+    target.set_owner(OWNER);
+
+    // 10 blocks used; cap the owner's quota at exactly that, so there's no
+    // room left for anything else until some of it is freed.
+    target.write_at(0, &[1u8; 10 * 512]);
+    efs.lock().set_quota(OWNER, 10);
+    assert!(
+        efs.lock().alloc_data(OWNER, None).is_none(),
+        "the owner's quota should already be exhausted by the file's 10 blocks"
+    );
+
+    // Punch out 4 whole blocks from the middle.
+    target.punch_hole(3 * BLOCK_SZ, 4 * BLOCK_SZ);
+
+    // Those 4 blocks are genuinely back in the free pool, not just
+    // unaccounted for in the inode's own bookkeeping: the owner can now
+    // allocate up to 4 more blocks, and a 5th still fails.
+    let mut reclaimed = Vec::new();
+    for _ in 0..4 {
+        reclaimed.push(
+            efs.lock()
+                .alloc_data(OWNER, None)
+                .expect("punch_hole should have freed exactly 4 blocks back to the quota"),
+        );
+    }
+    assert!(
+        efs.lock().alloc_data(OWNER, None).is_none(),
+        "no more than the 4 punched blocks should have been freed"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,39 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `sys_fexecve` (os/src/syscall/process.rs) reads the fd's whole inode
+/// via `Inode::read_all` and hands the bytes to `TaskControlBlock::exec` —
+/// the fd lookup, readability/type checks, and actual process replacement
+/// all need a running kernel's task scheduler, so they aren't host
+/// testable from here. What *is* testable at this layer is the one thing
+/// `sys_fexecve` depends on from `easy-fs`: that `read_all` returns a
+/// multi-block file's complete, correctly ordered content in one call,
+/// including a file that spans several reads internally (`read_all` pulls
+/// it in 512 bytes at a time).
+#[test]
+fn read_all_returns_a_multi_block_files_full_content_in_order() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-read-all-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let elf_like = root_inode.create("program.elf").unwrap();
+
+    let payload: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+    elf_like.write_at(0, &payload);
+
+    assert_eq!(elf_like.read_all(), payload);
+
+    std::fs::remove_file(&path).unwrap();
+}
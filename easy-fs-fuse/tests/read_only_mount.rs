@@ -0,0 +1,50 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `EasyFileSystem::open_read_only` refuses every mutating operation —
+/// here, `create` — while leaving reads working normally.
+#[test]
+fn read_only_mount_rejects_writes_but_allows_reads() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-read-only-mount-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    {
+        let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+        let root_inode = EasyFileSystem::root_inode(&efs);
+        let existing = root_inode.create("existing.txt").unwrap();
+        existing.write_at(0, b"hello");
+        easy_fs::block_cache_sync_all();
+    }
+
+    let efs = EasyFileSystem::open_read_only(block_device, 0);
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    assert!(root_inode.is_read_only());
+
+    let existing = root_inode.find("existing.txt").unwrap();
+    let mut buf = [0u8; 5];
+    assert_eq!(existing.read_at(0, &mut buf), 5);
+    assert_eq!(&buf, b"hello");
+
+    assert!(root_inode.create("new.txt").is_err());
+    assert!(root_inode.find("new.txt").is_none());
+
+    // `clear` has no error to return, so under read-only it silently
+    // no-ops; confirm it really left the data alone.
+    existing.clear();
+    let mut buf = [0u8; 5];
+    assert_eq!(existing.read_at(0, &mut buf), 5);
+    assert_eq!(&buf, b"hello");
+
+    std::fs::remove_file(&path).unwrap();
+}
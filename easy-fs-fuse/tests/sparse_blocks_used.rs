@@ -0,0 +1,51 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `Stat.blocks`/`blksize` (an `os/`-level detail not host-testable here)
+/// are populated from `Inode::blocks_used()` and `BLOCK_SZ`. The property
+/// that matters is at this level: for a sparse file, `blocks_used()`
+/// tracks actual allocation, not logical size — a hole punched out with
+/// `punch_hole` should shrink it even though the file's length is
+/// unchanged.
+#[test]
+fn blocks_used_tracks_actual_allocation_not_logical_size_for_a_sparse_file() {
+    const BLOCK_SZ: u32 = 512;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-sparse-blocks-used-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let sparse = root_inode.create("sparse.bin").unwrap();
+
+    sparse.write_at(0, &[1u8; 10 * 512]);
+    let full_blocks = sparse.blocks_used();
+    assert_eq!(full_blocks, 10);
+
+    sparse.punch_hole(3 * BLOCK_SZ, 4 * BLOCK_SZ);
+    let after_hole = sparse.blocks_used();
+    assert_eq!(
+        after_hole,
+        full_blocks - 4,
+        "punching 4 whole blocks out of the middle should free exactly those 4"
+    );
+
+    let mut readback = vec![0u8; 10 * 512];
+    assert_eq!(sparse.read_at(0, &mut readback), 10 * 512);
+    assert!(readback[..3 * 512].iter().all(|&b| b == 1));
+    assert!(readback[3 * 512..7 * 512].iter().all(|&b| b == 0));
+    assert!(readback[7 * 512..].iter().all(|&b| b == 1));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,75 @@
+use easy_fs::{block_cache_sync_all, get_block_cache, EasyFileSystem, SuperBlock};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `open` migrates an older-version superblock up to `EFS_VERSION` in
+/// place, on both the primary and its backup, before handing the
+/// filesystem back. Simulated by zeroing the version field's on-disk
+/// bytes — what an image predating that field would actually contain,
+/// since `create`'s zero-fill is all those bytes ever got.
+#[test]
+fn open_migrates_a_pre_version_superblock_to_the_current_version() {
+    // `SuperBlock` is `#[repr(C)]` with six `u32`s (the private `magic`
+    // plus five `pub` layout fields) ahead of `version`.
+    const VERSION_OFFSET: usize = 6 * 4;
+
+    let path = std::env::temp_dir().join("easy-fs-fuse-superblock-migration-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    {
+        let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+        let root_inode = EasyFileSystem::root_inode(&efs);
+        root_inode.create("predates-versioning.txt").unwrap();
+        block_cache_sync_all();
+    }
+
+    for block_id in [0usize, 1] {
+        get_block_cache(block_id, block_device.clone())
+            .lock()
+            .modify(0, |raw: &mut [u8; 512]| {
+                raw[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&0u32.to_ne_bytes());
+            });
+    }
+    block_cache_sync_all();
+    get_block_cache(0, block_device.clone())
+        .lock()
+        .read(0, |super_block: &SuperBlock| {
+            assert!(
+                super_block.needs_migration(),
+                "zeroing the version bytes should read back as needing migration"
+            );
+        });
+
+    let efs = EasyFileSystem::open(block_device.clone(), 0);
+    get_block_cache(0, block_device.clone())
+        .lock()
+        .read(0, |super_block: &SuperBlock| {
+            assert!(
+                !super_block.needs_migration(),
+                "open() should have migrated the primary superblock"
+            );
+        });
+    get_block_cache(1, block_device.clone())
+        .lock()
+        .read(0, |super_block: &SuperBlock| {
+            assert!(
+                !super_block.needs_migration(),
+                "open() should have migrated the backup superblock too"
+            );
+        });
+
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    assert!(root_inode.find("predates-versioning.txt").is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
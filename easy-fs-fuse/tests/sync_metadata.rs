@@ -0,0 +1,54 @@
+use easy_fs::{block_cache_sync_all, BlockOp, EasyFileSystem, TracingBlockDevice};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `sync_metadata` flushes only the inode's own metadata block, leaving
+/// its data blocks untouched — useful for a caller that wants to control
+/// the relative order metadata and data durability land in, rather than
+/// have `fsync` write both together. `set_owner` (like
+/// `set_case_insensitive`) dirties metadata without an immediate
+/// `block_cache_sync_all` of its own, so it's what leaves something for
+/// `sync_metadata` to actually do.
+#[test]
+fn sync_metadata_writes_only_the_inodes_own_block() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-sync-metadata-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(TracingBlockDevice::new(Arc::new(FileBlockDevice::new(
+        file,
+    ))));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let target = root_inode.create("watched.bin").unwrap();
+    target.write_at(0, &[1u8; 1024]);
+    let (metadata_block, _offset) = efs.lock().get_disk_inode_pos(target.inode_id());
+    block_cache_sync_all();
+
+    target.set_owner(7);
+    block_device.clear_log();
+    target.sync_metadata();
+
+    let writes: Vec<usize> = block_device
+        .log()
+        .into_iter()
+        .filter(|(op, _)| *op == BlockOp::Write)
+        .map(|(_, block_id)| block_id)
+        .collect();
+    assert_eq!(
+        writes,
+        vec![metadata_block as usize],
+        "sync_metadata should write exactly the inode's own block, nothing else"
+    );
+    assert_eq!(target.owner(), 7);
+
+    std::fs::remove_file(&path).unwrap();
+}
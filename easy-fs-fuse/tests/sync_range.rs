@@ -0,0 +1,81 @@
+use easy_fs::{block_cache_sync_all, BlockDevice, EasyFileSystem};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// A `BlockDevice` that records which block ids have been written to, so a
+/// test can check a flush touched exactly the blocks it should have and no
+/// others.
+struct RecordingDevice {
+    data: Mutex<Vec<u8>>,
+    written_blocks: Mutex<HashSet<usize>>,
+}
+
+impl RecordingDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+            written_blocks: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn take_written(&self) -> HashSet<usize> {
+        std::mem::take(&mut *self.written_blocks.lock().unwrap())
+    }
+}
+
+impl BlockDevice for RecordingDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.written_blocks.lock().unwrap().insert(block_id);
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+}
+
+/// `sync_range` flushes only the data blocks backing the requested byte
+/// range — it should leave other dirty state on the same inode (here, a
+/// metadata-only change from `set_owner`) untouched, unlike a full
+/// `block_cache_sync_all`.
+#[test]
+fn sync_range_leaves_unrelated_dirty_metadata_untouched() {
+    let device = Arc::new(RecordingDevice::new(8192));
+    let efs = EasyFileSystem::create(device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let file = root_inode.create("ranged.bin").unwrap();
+    let (metadata_block, _offset) = efs.lock().get_disk_inode_pos(file.inode_id());
+    let metadata_block = metadata_block as usize;
+
+    file.write_at(0, &[1u8; 512]);
+    block_cache_sync_all();
+    device.take_written();
+
+    // Dirty only the metadata block, without going through a path that
+    // would flush it immediately.
+    file.set_owner(9);
+
+    let written = device.take_written();
+    assert!(
+        written.is_empty(),
+        "set_owner alone shouldn't have written anything yet"
+    );
+
+    file.sync_range(0, 512);
+    let written_by_sync_range = device.take_written();
+    assert!(
+        !written_by_sync_range.contains(&metadata_block),
+        "sync_range shouldn't flush metadata dirtied by an unrelated change"
+    );
+
+    // The metadata change is still pending; a full sync should catch it.
+    block_cache_sync_all();
+    let written_by_full_sync = device.take_written();
+    assert!(
+        written_by_full_sync.contains(&metadata_block),
+        "the metadata block should still have been dirty for the full sync to catch"
+    );
+    assert_eq!(file.owner(), 9);
+}
@@ -0,0 +1,63 @@
+use easy_fs::{BlockDevice, BlockOp, TracingBlockDevice};
+use std::sync::{Arc, Mutex};
+
+/// Minimal in-memory `BlockDevice` with no overrides of its own, so every
+/// logged op in these tests comes from `TracingBlockDevice` itself rather
+/// than from whatever it wraps.
+struct MemoryDevice {
+    data: Mutex<Vec<u8>>,
+}
+
+impl MemoryDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+        }
+    }
+}
+
+impl BlockDevice for MemoryDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+}
+
+/// `read_block`/`write_block` each append one `(op, block_id)` entry, and
+/// every byte still round-trips through to the wrapped device.
+#[test]
+fn read_and_write_are_logged_and_still_reach_the_inner_device() {
+    let inner = Arc::new(MemoryDevice::new(4));
+    let device = TracingBlockDevice::new(inner);
+
+    device.write_block(2, &[0x42u8; 512]);
+    let mut buf = [0u8; 512];
+    device.read_block(2, &mut buf);
+    assert_eq!(buf, [0x42u8; 512]);
+
+    assert_eq!(device.log(), vec![(BlockOp::Write, 2), (BlockOp::Read, 2)]);
+
+    device.clear_log();
+    assert!(device.log().is_empty());
+}
+
+/// `write_blocks` is overridden to log a single entry at the span's start,
+/// rather than the per-block flood the default `BlockDevice::write_blocks`
+/// would otherwise produce through repeated `write_block` calls.
+#[test]
+fn write_blocks_logs_one_entry_for_the_whole_span() {
+    let inner = Arc::new(MemoryDevice::new(4));
+    let device = TracingBlockDevice::new(inner);
+
+    device.write_blocks(1, &[0x7u8; 512 * 3]);
+    assert_eq!(device.log(), vec![(BlockOp::Write, 1)]);
+
+    let mut buf = [0u8; 512];
+    device.read_block(2, &mut buf);
+    assert_eq!(buf, [0x7u8; 512]);
+}
@@ -0,0 +1,87 @@
+use easy_fs::{get_block_cache, DiskInode, EasyFileSystem};
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `Inode::verify_chain` walks the direct/indirect1/indirect2 pointers and
+/// reports `false` if any of them lands outside the data area or repeats
+/// a block id already used elsewhere in the chain — an fsck-style check,
+/// not something the ordinary read path pays for.
+#[test]
+fn verify_chain_detects_an_out_of_range_direct_pointer() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-verify-chain-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let target = root_inode.create("chained.bin").unwrap();
+
+    // Grow past the inline limit so `direct` holds real block pointers.
+    target.write_at(0, &[1u8; 512]);
+    assert!(
+        target.verify_chain(),
+        "a freshly grown file should verify clean"
+    );
+
+    let (meta_block, meta_offset) = efs.lock().get_disk_inode_pos(target.inode_id());
+    get_block_cache(meta_block as usize, block_device.clone())
+        .lock()
+        .modify(meta_offset, |disk_inode: &mut DiskInode| {
+            // Point well past the end of the image's data area.
+            disk_inode.direct[0] = 1_000_000;
+        });
+    easy_fs::block_cache_sync_all();
+
+    assert!(
+        !target.verify_chain(),
+        "an out-of-range direct pointer should fail verification"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn verify_chain_detects_an_aliased_direct_pointer() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-verify-chain-alias-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+    let target = root_inode.create("aliased.bin").unwrap();
+
+    target.write_at(0, &[1u8; 1024]);
+    assert!(target.verify_chain());
+
+    let (meta_block, meta_offset) = efs.lock().get_disk_inode_pos(target.inode_id());
+    get_block_cache(meta_block as usize, block_device.clone())
+        .lock()
+        .modify(meta_offset, |disk_inode: &mut DiskInode| {
+            disk_inode.direct[1] = disk_inode.direct[0];
+        });
+    easy_fs::block_cache_sync_all();
+
+    assert!(
+        !target.verify_chain(),
+        "two direct entries aliasing the same block should fail verification"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,59 @@
+use easy_fs::EasyFileSystem;
+use easy_fs_fuse::FileBlockDevice;
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+/// `walk` visits every entry reachable from a directory exactly once,
+/// with its path relative to where the walk started. This tree has no
+/// `mkdir`/subdirectory-creation API, so there's no way to build a real
+/// nested tree to exercise the recursive-descent half of `walk` from
+/// here — what's checked is its behavior over the root's own flat set of
+/// entries, which is the only tree shape this layer can construct.
+#[test]
+fn walk_visits_every_entry_exactly_once_with_its_name_as_the_path() {
+    let path = std::env::temp_dir().join("easy-fs-fuse-walk-test.img");
+    let _ = std::fs::remove_file(&path);
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    file.set_len(8192 * 512).unwrap();
+    let block_device = Arc::new(FileBlockDevice::new(file));
+    let efs = EasyFileSystem::create(block_device, 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let created = ["alpha.txt", "beta.txt", "gamma.bin"];
+    for name in created {
+        root_inode.create(name).unwrap();
+    }
+
+    let mut visited = Vec::new();
+    root_inode.walk(&mut |visit_path, inode| {
+        visited.push((visit_path.to_string(), inode.inode_id()));
+    });
+
+    let mut visited_names: Vec<&str> = visited.iter().map(|(p, _)| p.as_str()).collect();
+    visited_names.sort();
+    let mut expected = created.to_vec();
+    expected.sort();
+    assert_eq!(visited_names, expected);
+
+    // Each visited path matches the inode `find` resolves it to, and
+    // nothing was visited twice.
+    for (visit_path, inode_id) in &visited {
+        assert_eq!(root_inode.find(visit_path).unwrap().inode_id(), *inode_id);
+    }
+    let unique_count = {
+        let mut ids: Vec<u32> = visited.iter().map(|(_, id)| *id).collect();
+        ids.sort();
+        ids.dedup();
+        ids.len()
+    };
+    assert_eq!(unique_count, visited.len());
+
+    std::fs::remove_file(&path).unwrap();
+}
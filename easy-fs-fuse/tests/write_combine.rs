@@ -0,0 +1,75 @@
+use easy_fs::{BlockDevice, EasyFileSystem};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A `BlockDevice` that counts `write_block` calls, to measure how many
+/// times data actually reaches the "disk" rather than just the in-memory
+/// block cache.
+struct CountingDevice {
+    data: Mutex<Vec<u8>>,
+    write_block_calls: AtomicUsize,
+}
+
+impl CountingDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+            write_block_calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl BlockDevice for CountingDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.write_block_calls.fetch_add(1, Ordering::SeqCst);
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+}
+
+/// Appending 1000 short lines through `write_at` without flushing in
+/// between combines them in `write_buf` instead of dirtying (and, once
+/// flushed here, writing back) a block per line — far fewer `write_block`
+/// calls than flushing after every single line, the write-combining
+/// buffer's whole point.
+#[test]
+fn combined_small_appends_cause_far_fewer_block_writes_than_flushing_every_line() {
+    let device = Arc::new(CountingDevice::new(8192));
+    let efs = EasyFileSystem::create(device.clone(), 8192, 1, 0).unwrap();
+    let root_inode = EasyFileSystem::root_inode(&efs);
+
+    let combined = root_inode.create("combined.log").unwrap();
+    let before = device.write_block_calls.load(Ordering::SeqCst);
+    let mut offset = 0;
+    for i in 0..1000 {
+        let line = format!("line {i}\n");
+        offset += combined.write_at(offset, line.as_bytes());
+    }
+    combined.fsync();
+    let combined_writes = device.write_block_calls.load(Ordering::SeqCst) - before;
+
+    let uncombined = root_inode.create("uncombined.log").unwrap();
+    let before = device.write_block_calls.load(Ordering::SeqCst);
+    let mut offset = 0;
+    for i in 0..1000 {
+        let line = format!("line {i}\n");
+        offset += uncombined.write_at(offset, line.as_bytes());
+        uncombined.fsync();
+    }
+    let uncombined_writes = device.write_block_calls.load(Ordering::SeqCst) - before;
+
+    assert!(
+        combined_writes * 4 < uncombined_writes,
+        "combined {combined_writes} write_block calls should be far fewer than \
+         flush-every-line's {uncombined_writes}"
+    );
+
+    let mut buf = [0u8; 7];
+    assert_eq!(combined.read_at(0, &mut buf), 7);
+    assert_eq!(&buf, b"line 0\n");
+}
@@ -0,0 +1,63 @@
+use easy_fs::{get_block_cache, tick, writeback_stale, BlockDevice};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+struct CountingDevice {
+    data: Mutex<Vec<u8>>,
+    write_count: AtomicUsize,
+}
+impl CountingDevice {
+    fn new(blocks: usize) -> Self {
+        Self {
+            data: Mutex::new(vec![0u8; blocks * 512]),
+            write_count: AtomicUsize::new(0),
+        }
+    }
+}
+impl BlockDevice for CountingDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let data = self.data.lock().unwrap();
+        buf.copy_from_slice(&data[block_id * 512..block_id * 512 + 512]);
+    }
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.write_count.fetch_add(1, Ordering::Relaxed);
+        let mut data = self.data.lock().unwrap();
+        data[block_id * 512..block_id * 512 + 512].copy_from_slice(buf);
+    }
+}
+
+/// `writeback_stale` flushes a dirty block once `tick` has advanced past
+/// its age threshold, with no explicit `sync`/`block_cache_sync_all` call
+/// needed — the periodic-writeback hook a timer interrupt would drive.
+#[test]
+fn writeback_stale_flushes_an_aged_dirty_block_without_an_explicit_sync() {
+    let device = Arc::new(CountingDevice::new(8));
+
+    get_block_cache(0, device.clone())
+        .lock()
+        .modify(0, |data: &mut [u8; 512]| data[0] = 0xAB);
+
+    // Too fresh: a writeback with a wide age window shouldn't touch it yet.
+    writeback_stale(100);
+    assert_eq!(device.write_count.load(Ordering::Relaxed), 0);
+
+    for _ in 0..5 {
+        tick();
+    }
+
+    // Still not old enough for a 100-tick threshold.
+    writeback_stale(100);
+    assert_eq!(device.write_count.load(Ordering::Relaxed), 0);
+
+    for _ in 0..96 {
+        tick();
+    }
+
+    // Now 101 ticks old: past the threshold, gets flushed with no explicit sync call.
+    writeback_stale(100);
+    assert_eq!(device.write_count.load(Ordering::Relaxed), 1);
+
+    let mut buf = [0u8; 512];
+    device.read_block(0, &mut buf);
+    assert_eq!(buf[0], 0xAB);
+}
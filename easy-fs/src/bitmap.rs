@@ -0,0 +1,152 @@
+use super::{get_block_cache, BlockDevice, BLOCK_SZ};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+type BitmapBlock = [u64; 64];
+
+const BLOCK_BITS: usize = BLOCK_SZ * 8;
+
+/// A persistent bitmap spanning a contiguous run of blocks, used to track
+/// free/used data blocks and inodes.
+pub struct Bitmap {
+    start_block_id: usize,
+    blocks: usize,
+}
+
+/// Decompose a global bit position into (block offset within the bitmap,
+/// u64 word index within the block, bit index within the word).
+fn decomposition(mut bit: usize) -> (usize, usize, usize) {
+    let block_pos = bit / BLOCK_BITS;
+    bit %= BLOCK_BITS;
+    (block_pos, bit / 64, bit % 64)
+}
+
+impl Bitmap {
+    pub fn new(start_block_id: usize, blocks: usize) -> Self {
+        Self {
+            start_block_id,
+            blocks,
+        }
+    }
+
+    /// Allocate a bit, returning its global index, or `None` if the bitmap
+    /// is full. `hint`, if given, is a previously allocated bit to
+    /// allocate near: the bitmap block containing it is tried first, then
+    /// the rest of the bitmap in order of increasing distance from it, so
+    /// a file's blocks tend to land close together instead of scattering
+    /// wherever the next free bit happens to be.
+    pub fn alloc(&self, block_device: &Arc<dyn BlockDevice>, hint: Option<usize>) -> Option<usize> {
+        let hint_block = hint
+            .map(|bit| bit / BLOCK_BITS)
+            .filter(|block| *block < self.blocks)
+            .unwrap_or(0);
+        let mut search_order: Vec<usize> = (0..self.blocks).collect();
+        search_order.sort_by_key(|block| block.abs_diff(hint_block));
+        for block_id in search_order {
+            let pos = get_block_cache(block_id + self.start_block_id, Arc::clone(block_device))
+                .lock()
+                .modify(0, |bitmap_block: &mut BitmapBlock| {
+                    if let Some((bits64_pos, inner_pos)) = bitmap_block
+                        .iter()
+                        .enumerate()
+                        .find(|(_, bits64)| **bits64 != u64::MAX)
+                        .map(|(bits64_pos, bits64)| (bits64_pos, bits64.trailing_ones() as usize))
+                    {
+                        bitmap_block[bits64_pos] |= 1u64 << inner_pos;
+                        Some(block_id * BLOCK_BITS + bits64_pos * 64 + inner_pos)
+                    } else {
+                        None
+                    }
+                });
+            if pos.is_some() {
+                return pos;
+            }
+        }
+        None
+    }
+
+    /// Deallocate a previously-allocated bit. Panics if it's already free,
+    /// which should never happen on the normal free path.
+    pub fn dealloc(&self, block_device: &Arc<dyn BlockDevice>, bit: usize) {
+        assert!(self.try_dealloc(block_device, bit));
+    }
+
+    /// Like `dealloc`, but returns `false` instead of panicking if `bit` is
+    /// already free. Meant for fsck/recovery tools, which may walk into an
+    /// already-freed block and shouldn't crash the kernel over it.
+    pub fn try_dealloc(&self, block_device: &Arc<dyn BlockDevice>, bit: usize) -> bool {
+        let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
+        get_block_cache(block_pos + self.start_block_id, Arc::clone(block_device))
+            .lock()
+            .modify(0, |bitmap_block: &mut BitmapBlock| {
+                if bitmap_block[bits64_pos] & (1u64 << inner_pos) == 0 {
+                    return false;
+                }
+                bitmap_block[bits64_pos] -= 1u64 << inner_pos;
+                true
+            })
+    }
+
+    /// Allocate `n` *consecutive* bits, returning their global indices in
+    /// ascending order, or `None` if no run that long is free. Unlike
+    /// `alloc`, this scans the whole bitmap bit by bit rather than
+    /// block-at-a-time, since a long enough run can straddle a block
+    /// boundary; meant for the rare, offline `Inode::defragment`
+    /// call rather than the hot allocation path `alloc` serves.
+    pub fn alloc_contiguous(
+        &self,
+        block_device: &Arc<dyn BlockDevice>,
+        n: usize,
+    ) -> Option<Vec<usize>> {
+        if n == 0 {
+            return Some(Vec::new());
+        }
+        let maximum = self.maximum();
+        let mut run_start = None;
+        let mut found = None;
+        'scan: for block in 0..self.blocks {
+            let bitmap_block =
+                get_block_cache(block + self.start_block_id, Arc::clone(block_device))
+                    .lock()
+                    .read(0, |bitmap_block: &BitmapBlock| *bitmap_block);
+            for (word_idx, word) in bitmap_block.iter().enumerate() {
+                for bit_idx in 0..64 {
+                    let global = block * BLOCK_BITS + word_idx * 64 + bit_idx;
+                    if global >= maximum {
+                        break 'scan;
+                    }
+                    if word & (1u64 << bit_idx) == 0 {
+                        let start = *run_start.get_or_insert(global);
+                        if global - start + 1 == n {
+                            found = Some(start);
+                            break 'scan;
+                        }
+                    } else {
+                        run_start = None;
+                    }
+                }
+            }
+        }
+        let start = found?;
+        for bit in start..start + n {
+            let (block_pos, bits64_pos, inner_pos) = decomposition(bit);
+            get_block_cache(block_pos + self.start_block_id, Arc::clone(block_device))
+                .lock()
+                .modify(0, |bitmap_block: &mut BitmapBlock| {
+                    bitmap_block[bits64_pos] |= 1u64 << inner_pos;
+                });
+        }
+        Some((start..start + n).collect())
+    }
+
+    /// The block ids this bitmap itself occupies (not the bits it tracks),
+    /// so callers like `EasyFileSystem::pin_metadata_blocks` can pin every
+    /// bitmap block without reaching into private fields.
+    pub(crate) fn block_ids(&self) -> core::ops::Range<usize> {
+        self.start_block_id..self.start_block_id + self.blocks
+    }
+
+    pub fn maximum(&self) -> usize {
+        self.blocks * BLOCK_BITS
+    }
+}
@@ -0,0 +1,351 @@
+use super::{BlockDevice, BLOCK_SZ};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
+use spin::Mutex;
+
+/// Monotonically increasing counter bumped once per call to [`tick`],
+/// meant to be wired up to the kernel's timer interrupt. `BlockCache`
+/// stamps the tick a block was first dirtied at so [`writeback_stale`] can
+/// tell how long it's sat unflushed, without easy-fs needing access to a
+/// wall-clock source of its own.
+static TICK: AtomicUsize = AtomicUsize::new(0);
+
+/// Advance the tick counter used to age dirty blocks for
+/// [`writeback_stale`]. Call once per timer interrupt.
+pub fn tick() {
+    TICK.fetch_add(1, Ordering::Relaxed);
+}
+
+fn current_tick() -> usize {
+    TICK.load(Ordering::Relaxed)
+}
+
+/// Cached copy of a single on-disk block, kept in memory so that repeated
+/// accesses to the same block don't round-trip through the block device.
+pub struct BlockCache {
+    cache: [u8; BLOCK_SZ],
+    block_id: usize,
+    block_device: Arc<dyn BlockDevice>,
+    modified: bool,
+    /// Tick (per [`tick`]) at which this block was first modified since
+    /// its last flush, or `None` if it's currently clean.
+    dirtied_at: Option<usize>,
+}
+
+impl BlockCache {
+    /// Load a new block cache entry from disk.
+    pub fn new(block_id: usize, block_device: Arc<dyn BlockDevice>) -> Self {
+        let mut cache = [0u8; BLOCK_SZ];
+        block_device.read_block(block_id, &mut cache);
+        Self {
+            cache,
+            block_id,
+            block_device,
+            modified: false,
+            dirtied_at: None,
+        }
+    }
+
+    /// Like `new`, but for a block whose bytes were already read as part
+    /// of a `CLUSTER_BLOCKS`-wide `read_blocks` call — used by
+    /// `BlockCacheManager::get_block_cache` to populate every block in a
+    /// cluster from the one read that brought any of them in, instead of
+    /// re-reading this one block on its own.
+    fn from_bytes(block_id: usize, block_device: Arc<dyn BlockDevice>, bytes: &[u8]) -> Self {
+        let mut cache = [0u8; BLOCK_SZ];
+        cache.copy_from_slice(bytes);
+        Self {
+            cache,
+            block_id,
+            block_device,
+            modified: false,
+            dirtied_at: None,
+        }
+    }
+
+    fn addr_of_offset(&self, offset: usize) -> usize {
+        &self.cache[offset] as *const _ as usize
+    }
+
+    pub fn get_ref<T>(&self, offset: usize) -> &T
+    where
+        T: Sized,
+    {
+        let type_size = core::mem::size_of::<T>();
+        assert!(offset + type_size <= BLOCK_SZ);
+        let addr = self.addr_of_offset(offset);
+        unsafe { &*(addr as *const T) }
+    }
+
+    pub fn get_mut<T>(&mut self, offset: usize) -> &mut T
+    where
+        T: Sized,
+    {
+        let type_size = core::mem::size_of::<T>();
+        assert!(offset + type_size <= BLOCK_SZ);
+        if !self.modified {
+            self.dirtied_at = Some(current_tick());
+        }
+        self.modified = true;
+        let addr = self.addr_of_offset(offset);
+        unsafe { &mut *(addr as *mut T) }
+    }
+
+    pub fn read<T, V>(&self, offset: usize, f: impl FnOnce(&T) -> V) -> V {
+        f(self.get_ref(offset))
+    }
+
+    pub fn modify<T, V>(&mut self, offset: usize, f: impl FnOnce(&mut T) -> V) -> V {
+        f(self.get_mut(offset))
+    }
+
+    pub fn sync(&mut self) {
+        if self.modified {
+            self.modified = false;
+            self.dirtied_at = None;
+            self.block_device.write_block(self.block_id, &self.cache);
+        }
+    }
+
+    /// Whether this block has unflushed modifications.
+    pub fn is_dirty(&self) -> bool {
+        self.modified
+    }
+
+    /// Tick this block was first dirtied at since its last flush, or
+    /// `None` if it's currently clean.
+    pub fn dirtied_at(&self) -> Option<usize> {
+        self.dirtied_at
+    }
+}
+
+impl Drop for BlockCache {
+    fn drop(&mut self) {
+        self.sync();
+    }
+}
+
+/// Number of cached blocks kept in memory at once.
+const BLOCK_CACHE_SIZE: usize = 16;
+
+/// Number of consecutive `BLOCK_SZ` sectors `BlockCacheManager` reads from
+/// the device in one `read_blocks` call whenever a cache miss brings any
+/// of them in. Filesystem layout still addresses individual `BLOCK_SZ`
+/// blocks everywhere else — this only changes how many of them show up
+/// in cache together after the first touch, trading a bit of wasted
+/// reads for a cluster that turns out to be cold against fewer, larger
+/// device requests for one that's hot.
+const CLUSTER_BLOCKS: usize = 8;
+
+/// First block id of the `CLUSTER_BLOCKS`-sized cluster containing `block_id`.
+fn cluster_start(block_id: usize) -> usize {
+    block_id / CLUSTER_BLOCKS * CLUSTER_BLOCKS
+}
+
+/// LRU-ish manager: evicts the oldest unreferenced block when full.
+/// `pinned` blocks (set via [`pin_block`]) are kept in a separate map that
+/// `BLOCK_CACHE_SIZE` eviction never touches, so hot metadata — the
+/// superblock and bitmap blocks, pinned by `EasyFileSystem::open`/
+/// `create` — stays resident no matter how much data-block churn the
+/// `queue` side sees.
+pub struct BlockCacheManager {
+    queue: VecDeque<(usize, Arc<Mutex<BlockCache>>)>,
+    pinned: BTreeMap<usize, Arc<Mutex<BlockCache>>>,
+}
+
+impl BlockCacheManager {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            pinned: BTreeMap::new(),
+        }
+    }
+
+    /// Evict the oldest unreferenced entry from `queue` if it's at
+    /// capacity. Panics if every slot is still referenced elsewhere.
+    fn evict_if_full(&mut self) {
+        if self.queue.len() == BLOCK_CACHE_SIZE {
+            if let Some((idx, _)) = self
+                .queue
+                .iter()
+                .enumerate()
+                .find(|(_, pair)| Arc::strong_count(&pair.1) == 1)
+            {
+                self.queue.drain(idx..=idx);
+            } else {
+                panic!("Run out of BlockCache!");
+            }
+        }
+    }
+
+    /// On a miss, reads the whole `CLUSTER_BLOCKS`-sized cluster
+    /// containing `block_id` in one `read_blocks` call and caches every
+    /// block in it that isn't already cached, not just the one asked
+    /// for — so touching the rest of the cluster afterward is a plain
+    /// cache hit instead of another device read.
+    pub fn get_block_cache(
+        &mut self,
+        block_id: usize,
+        block_device: Arc<dyn BlockDevice>,
+    ) -> Arc<Mutex<BlockCache>> {
+        if let Some(cache) = self.pinned.get(&block_id) {
+            return Arc::clone(cache);
+        }
+        if let Some(pair) = self.queue.iter().find(|pair| pair.0 == block_id) {
+            return Arc::clone(&pair.1);
+        }
+        let start = cluster_start(block_id);
+        let mut buf = vec![0u8; CLUSTER_BLOCKS * BLOCK_SZ];
+        block_device.read_blocks(start, &mut buf);
+        let mut requested = None;
+        for i in 0..CLUSTER_BLOCKS {
+            let id = start + i;
+            if self.pinned.contains_key(&id) || self.queue.iter().any(|pair| pair.0 == id) {
+                continue;
+            }
+            self.evict_if_full();
+            let bytes = &buf[i * BLOCK_SZ..(i + 1) * BLOCK_SZ];
+            let cache = Arc::new(Mutex::new(BlockCache::from_bytes(
+                id,
+                Arc::clone(&block_device),
+                bytes,
+            )));
+            self.queue.push_back((id, Arc::clone(&cache)));
+            if id == block_id {
+                requested = Some(cache);
+            }
+        }
+        requested.expect("block_id lies within its own cluster")
+    }
+
+    /// Exempt `block_id` from `queue`'s eviction from now on. If it's
+    /// already cached in `queue` (or already pinned), its loaded
+    /// `BlockCache` — dirty state and all — is reused rather than reread;
+    /// otherwise it's loaded fresh straight into `pinned`.
+    pub fn pin(&mut self, block_id: usize, block_device: Arc<dyn BlockDevice>) {
+        if self.pinned.contains_key(&block_id) {
+            return;
+        }
+        let cache = if let Some(idx) = self.queue.iter().position(|pair| pair.0 == block_id) {
+            self.queue.remove(idx).unwrap().1
+        } else {
+            Arc::new(Mutex::new(BlockCache::new(block_id, block_device)))
+        };
+        self.pinned.insert(block_id, cache);
+    }
+
+    /// Every cached `(block_id, BlockCache)`, pinned and evictable alike —
+    /// the one place `block_cache_sync_all`/`flush_block_range`/
+    /// `writeback_stale` need to look at both pools.
+    fn all_cached(&self) -> impl Iterator<Item = (usize, &Arc<Mutex<BlockCache>>)> {
+        self.queue
+            .iter()
+            .map(|(id, cache)| (*id, cache))
+            .chain(self.pinned.iter().map(|(id, cache)| (*id, cache)))
+    }
+}
+
+lazy_static! {
+    pub static ref BLOCK_CACHE_MANAGER: Mutex<BlockCacheManager> =
+        Mutex::new(BlockCacheManager::new());
+}
+
+pub fn get_block_cache(
+    block_id: usize,
+    block_device: Arc<dyn BlockDevice>,
+) -> Arc<Mutex<BlockCache>> {
+    BLOCK_CACHE_MANAGER
+        .lock()
+        .get_block_cache(block_id, block_device)
+}
+
+/// Pin `block_id` so it's never evicted by `BLOCK_CACHE_SIZE` pressure; see
+/// `BlockCacheManager::pin`. Used by `EasyFileSystem::open`/`create` to keep
+/// the superblock and bitmap blocks resident.
+pub fn pin_block(block_id: usize, block_device: Arc<dyn BlockDevice>) {
+    BLOCK_CACHE_MANAGER.lock().pin(block_id, block_device);
+}
+
+/// Flush every dirty `BlockCache`. Dirty blocks are collected out of
+/// `queue`'s insertion order into ascending block-id order before any
+/// writes happen, so the underlying device always sees a monotonic
+/// sequence of writes regardless of the order the blocks were dirtied in —
+/// better for rotational/virtio seek cost than flushing in LRU order.
+/// Consecutive ids within that ascending sequence are then flushed one run
+/// at a time, so a workload that dirties a contiguous region (e.g. growing
+/// a file) issues one `write_blocks` per run instead of one `write_block`
+/// per block.
+pub fn block_cache_sync_all() {
+    let manager = BLOCK_CACHE_MANAGER.lock();
+    let mut dirty: Vec<(usize, Arc<Mutex<BlockCache>>)> = manager
+        .all_cached()
+        .filter(|(_, cache)| cache.lock().is_dirty())
+        .map(|(block_id, cache)| (block_id, Arc::clone(cache)))
+        .collect();
+    dirty.sort_by_key(|(block_id, _)| *block_id);
+    let mut i = 0;
+    while i < dirty.len() {
+        let mut j = i + 1;
+        while j < dirty.len() && dirty[j].0 == dirty[j - 1].0 + 1 {
+            j += 1;
+        }
+        flush_run(&dirty[i..j]);
+        i = j;
+    }
+}
+
+/// Flush one run of `BlockCache`s with consecutive block ids. A run longer
+/// than one block is written with a single `write_blocks` call; a lone
+/// block just goes through the ordinary `sync` path.
+fn flush_run(run: &[(usize, Arc<Mutex<BlockCache>>)]) {
+    if run.len() == 1 {
+        run[0].1.lock().sync();
+        return;
+    }
+    let block_device = Arc::clone(&run[0].1.lock().block_device);
+    let mut buf = Vec::with_capacity(run.len() * BLOCK_SZ);
+    for (_, cache) in run {
+        let mut cache = cache.lock();
+        buf.extend_from_slice(&cache.cache);
+        cache.modified = false;
+        cache.dirtied_at = None;
+    }
+    block_device.write_blocks(run[0].0, &buf);
+}
+
+/// Flush only the cached blocks whose id is in `block_ids`, leaving any
+/// other dirty blocks in the cache untouched. Used by
+/// `Inode::sync_range` to checkpoint part of a file without paying for a
+/// full `block_cache_sync_all`.
+pub fn flush_block_range(block_ids: &[usize]) {
+    let manager = BLOCK_CACHE_MANAGER.lock();
+    for (block_id, cache) in manager.all_cached() {
+        if block_ids.contains(&block_id) {
+            cache.lock().sync();
+        }
+    }
+}
+
+/// Flush every dirty block whose `dirtied_at` tick is at least
+/// `max_age_ticks` old, leaving more recently dirtied blocks (which a
+/// write burst might still be appending to) cached. Meant to be called
+/// periodically from the timer interrupt, bounding how long a write can
+/// sit in volatile cache without paying the cost of a full
+/// `block_cache_sync_all` on every tick.
+pub fn writeback_stale(max_age_ticks: usize) {
+    let now = current_tick();
+    let manager = BLOCK_CACHE_MANAGER.lock();
+    for (_, cache) in manager.all_cached() {
+        let mut cache = cache.lock();
+        if cache
+            .dirtied_at()
+            .is_some_and(|dirtied_at| now.saturating_sub(dirtied_at) >= max_age_ticks)
+        {
+            cache.sync();
+        }
+    }
+}
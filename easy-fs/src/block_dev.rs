@@ -0,0 +1,37 @@
+use super::BLOCK_SZ;
+use core::any::Any;
+
+/// Abstraction of a raw, block-addressed storage device.
+///
+/// The file system only ever talks to storage through this trait, so it
+/// can run unmodified on top of a virtio block device in the kernel or a
+/// plain host file when packing an image with `easy-fs-fuse`.
+pub trait BlockDevice: Send + Sync + Any {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]);
+    fn write_block(&self, block_id: usize, buf: &[u8]);
+
+    /// Fill `buf`, a concatenation of consecutive blocks starting at
+    /// `start_block_id`, in one request. `buf.len()` must be a multiple of
+    /// `BLOCK_SZ`. The default implementation just calls `read_block` once
+    /// per block; devices that can batch contiguous reads should override
+    /// this to do so. `BlockCacheManager` calls this to pull in a whole
+    /// cluster (see its `CLUSTER_BLOCKS`) on a cache miss, so overriding it
+    /// is where a real device gets to turn that into one larger I/O
+    /// request instead of `CLUSTER_BLOCKS` separate ones.
+    fn read_blocks(&self, start_block_id: usize, buf: &mut [u8]) {
+        for (i, chunk) in buf.chunks_mut(BLOCK_SZ).enumerate() {
+            self.read_block(start_block_id + i, chunk);
+        }
+    }
+
+    /// Write `buf`, a concatenation of consecutive blocks starting at
+    /// `start_block_id`, in one request. `buf.len()` must be a multiple of
+    /// `BLOCK_SZ`. The default implementation just calls `write_block` once
+    /// per block; devices that can batch contiguous writes should override
+    /// this to do so.
+    fn write_blocks(&self, start_block_id: usize, buf: &[u8]) {
+        for (i, chunk) in buf.chunks(BLOCK_SZ).enumerate() {
+            self.write_block(start_block_id + i, chunk);
+        }
+    }
+}
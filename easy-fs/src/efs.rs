@@ -0,0 +1,436 @@
+use super::{
+    block_cache_sync_all, get_block_cache, pin_block, Bitmap, BlockDevice, DiskInode,
+    DiskInodeType, Inode, SuperBlock,
+};
+use crate::BLOCK_SZ;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// Kinds of change an `Inode`'s mutating methods report through
+/// `EasyFileSystem::set_change_callback`, for a future inotify-style
+/// watcher. Only operations this filesystem actually performs are
+/// covered: there's no real hard-link-aware unlink, rename, or
+/// subdirectory creation here yet (`sys_unlinkat`/`sys_linkat` are
+/// unconditional stubs, and `create`/`create_fifo` are the only ways a
+/// directory entry is ever added), so this doesn't invent events for
+/// operations that can't happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsEventKind {
+    /// `Inode::write_at` wrote (or buffered) new data.
+    Write,
+    /// `Inode::create`/`create_fifo` added a new entry to a directory.
+    Create,
+}
+
+/// A single change reported to a registered callback: which inode
+/// changed, and how.
+#[derive(Debug, Clone, Copy)]
+pub struct FsEvent {
+    pub inode_id: u32,
+    pub kind: FsEventKind,
+}
+
+type ChangeCallback = Box<dyn Fn(FsEvent) + Send + Sync>;
+
+/// Block 1 permanently holds a copy of the superblock, so a corrupted
+/// block 0 can still be recovered without needing to know `total_blocks`
+/// (which `BlockDevice` has no way to query) ahead of time.
+const BACKUP_SUPER_BLOCK_ID: usize = 1;
+
+pub struct EasyFileSystem {
+    pub block_device: Arc<dyn BlockDevice>,
+    pub inode_bitmap: Bitmap,
+    pub data_bitmap: Bitmap,
+    inode_area_start_block: u32,
+    data_area_start_block: u32,
+    /// Live `Inode` handles by logical inode id, so two lookups of the same
+    /// on-disk inode share one in-memory handle instead of duplicating its
+    /// buffered-write and directory-index state.
+    open_inodes: BTreeMap<u32, Weak<Inode>>,
+    /// Set by `open_read_only`. `alloc_inode`/`alloc_data` refuse to hand
+    /// out new inodes/blocks while this is set, and `Inode`'s mutating
+    /// methods (`create`, `write_at`, `clear`) check it before touching any
+    /// block, so a read-only mount never dirties a `BlockCache` entry.
+    read_only: bool,
+    /// Caller-assigned identifier for the underlying `block_device`, passed
+    /// in at `create`/`open`/`open_read_only` and otherwise opaque to this
+    /// filesystem. Threaded through to `Inode::dev_id` so callers mounting
+    /// more than one image can tell which one a given `Inode` came from.
+    dev_id: u64,
+    /// Set by `set_change_callback`; invoked by `notify` after a mutating
+    /// `Inode` operation covered by `FsEventKind` completes. `None` by
+    /// default, in which case `notify` is a no-op and nothing about
+    /// existing behavior changes.
+    change_callback: Option<ChangeCallback>,
+    /// Per-owner block quotas set via `set_quota`. An owner with no entry
+    /// here is unlimited.
+    quotas: BTreeMap<u32, u32>,
+    /// Cached count of data blocks currently charged to each owner id
+    /// (`DiskInode::owner`), kept up to date by `alloc_data`/`alloc_contiguous`/
+    /// `dealloc_data` rather than recomputed by scanning every inode on
+    /// every allocation.
+    owner_usage: BTreeMap<u32, u32>,
+}
+
+impl EasyFileSystem {
+    /// Format a block device into a fresh easy-fs image. Returns `None` if
+    /// `total_blocks` is too small to hold the superblock, its backup, the
+    /// requested inode region, and at least one data bitmap block plus one
+    /// data block — without this check the `total_blocks - 2 -
+    /// inode_total_blocks` subtraction below would underflow and silently
+    /// produce a garbage layout instead.
+    pub fn create(
+        block_device: Arc<dyn BlockDevice>,
+        total_blocks: u32,
+        inode_bitmap_blocks: u32,
+        dev_id: u64,
+    ) -> Option<Arc<Mutex<Self>>> {
+        let inode_bitmap = Bitmap::new(2, inode_bitmap_blocks as usize);
+        let inode_num = inode_bitmap.maximum();
+        let inode_area_blocks =
+            (inode_num * core::mem::size_of::<DiskInode>()).div_ceil(BLOCK_SZ) as u32;
+        let inode_total_blocks = inode_bitmap_blocks + inode_area_blocks;
+        if total_blocks <= 2 + inode_total_blocks + 1 {
+            return None;
+        }
+        let data_total_blocks = total_blocks - 2 - inode_total_blocks;
+        let data_bitmap_blocks = data_total_blocks.div_ceil(4097);
+        let data_area_blocks = data_total_blocks - data_bitmap_blocks;
+        let data_bitmap = Bitmap::new(
+            (2 + inode_bitmap_blocks + inode_area_blocks) as usize,
+            data_bitmap_blocks as usize,
+        );
+        let mut efs = Self {
+            block_device: Arc::clone(&block_device),
+            inode_bitmap,
+            data_bitmap,
+            inode_area_start_block: 2 + inode_bitmap_blocks,
+            data_area_start_block: 2 + inode_total_blocks + data_bitmap_blocks,
+            open_inodes: BTreeMap::new(),
+            read_only: false,
+            dev_id,
+            change_callback: None,
+            quotas: BTreeMap::new(),
+            owner_usage: BTreeMap::new(),
+        };
+        for i in 0..total_blocks {
+            get_block_cache(i as usize, Arc::clone(&block_device))
+                .lock()
+                .modify(0, |data_block: &mut [u8; BLOCK_SZ]| {
+                    for byte in data_block.iter_mut() {
+                        *byte = 0;
+                    }
+                });
+        }
+        for block_id in [0, BACKUP_SUPER_BLOCK_ID] {
+            get_block_cache(block_id, Arc::clone(&block_device))
+                .lock()
+                .modify(0, |super_block: &mut SuperBlock| {
+                    super_block.initialize(
+                        total_blocks,
+                        inode_bitmap_blocks,
+                        inode_area_blocks,
+                        data_bitmap_blocks,
+                        data_area_blocks,
+                    );
+                });
+        }
+        assert_eq!(efs.alloc_inode(), Some(0));
+        let (root_inode_block_id, root_inode_offset) = efs.get_disk_inode_pos(0);
+        get_block_cache(root_inode_block_id as usize, Arc::clone(&block_device))
+            .lock()
+            .modify(root_inode_offset, |disk_inode: &mut DiskInode| {
+                disk_inode.initialize(DiskInodeType::Directory);
+            });
+        block_cache_sync_all();
+        efs.pin_metadata_blocks();
+        Some(Arc::new(Mutex::new(efs)))
+    }
+
+    /// Open an existing easy-fs image. Falls back to the backup superblock
+    /// at `BACKUP_SUPER_BLOCK_ID` if block 0's magic doesn't check out, and
+    /// repairs block 0 from the backup so the recovery only has to happen
+    /// once.
+    pub fn open(block_device: Arc<dyn BlockDevice>, dev_id: u64) -> Arc<Mutex<Self>> {
+        Self::open_inner(block_device, false, dev_id)
+    }
+
+    /// Like `open`, but every mutating operation (`alloc_inode`,
+    /// `alloc_data`, and the `Inode` methods built on them) fails instead
+    /// of touching the disk. Safe to mount a possibly-damaged image with,
+    /// since nothing it does can make the corruption worse.
+    pub fn open_read_only(block_device: Arc<dyn BlockDevice>, dev_id: u64) -> Arc<Mutex<Self>> {
+        Self::open_inner(block_device, true, dev_id)
+    }
+
+    fn open_inner(
+        block_device: Arc<dyn BlockDevice>,
+        read_only: bool,
+        dev_id: u64,
+    ) -> Arc<Mutex<Self>> {
+        let primary_valid = get_block_cache(0, Arc::clone(&block_device))
+            .lock()
+            .read(0, SuperBlock::is_valid);
+        let super_block_id = if primary_valid {
+            0
+        } else {
+            BACKUP_SUPER_BLOCK_ID
+        };
+        let block = get_block_cache(super_block_id, Arc::clone(&block_device));
+        let (inner, needs_migration) = block.lock().read(0, |super_block: &SuperBlock| {
+            assert!(super_block.is_valid(), "Error loading EFS!");
+            let inode_total_blocks =
+                super_block.inode_bitmap_blocks + super_block.inode_area_blocks;
+            let inner = Self {
+                block_device: Arc::clone(&block_device),
+                inode_bitmap: Bitmap::new(2, super_block.inode_bitmap_blocks as usize),
+                data_bitmap: Bitmap::new(
+                    (2 + inode_total_blocks) as usize,
+                    super_block.data_bitmap_blocks as usize,
+                ),
+                inode_area_start_block: 2 + super_block.inode_bitmap_blocks,
+                data_area_start_block: 2 + inode_total_blocks + super_block.data_bitmap_blocks,
+                open_inodes: BTreeMap::new(),
+                read_only,
+                dev_id,
+                change_callback: None,
+                quotas: BTreeMap::new(),
+                owner_usage: BTreeMap::new(),
+            };
+            (inner, super_block.needs_migration())
+        });
+        if !primary_valid && !read_only {
+            let backup = get_block_cache(BACKUP_SUPER_BLOCK_ID, Arc::clone(&block_device))
+                .lock()
+                .read(0, |super_block: &SuperBlock| *super_block);
+            get_block_cache(0, Arc::clone(&block_device))
+                .lock()
+                .modify(0, |super_block: &mut SuperBlock| *super_block = backup);
+            block_cache_sync_all();
+        }
+        if needs_migration && !read_only {
+            for block_id in [0, BACKUP_SUPER_BLOCK_ID] {
+                get_block_cache(block_id, Arc::clone(&block_device))
+                    .lock()
+                    .modify(0, |super_block: &mut SuperBlock| super_block.migrate());
+            }
+            block_cache_sync_all();
+        }
+        inner.pin_metadata_blocks();
+        Arc::new(Mutex::new(inner))
+    }
+
+    /// Pin the superblock (block 0 and its backup at
+    /// `BACKUP_SUPER_BLOCK_ID`) and every inode/data bitmap block, so the
+    /// blocks touched on nearly every allocation stay cache-resident
+    /// instead of getting evicted under data-block churn. Called once, at
+    /// the end of `create`/`open_inner`.
+    fn pin_metadata_blocks(&self) {
+        for block_id in [0, BACKUP_SUPER_BLOCK_ID] {
+            pin_block(block_id, Arc::clone(&self.block_device));
+        }
+        for block_id in self
+            .inode_bitmap
+            .block_ids()
+            .chain(self.data_bitmap.block_ids())
+        {
+            pin_block(block_id, Arc::clone(&self.block_device));
+        }
+    }
+
+    /// Whether this filesystem was mounted via `open_read_only`.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The `dev_id` this filesystem was created/opened with.
+    pub fn dev_id(&self) -> u64 {
+        self.dev_id
+    }
+
+    /// Register `callback` to be invoked with an `FsEvent` every time a
+    /// mutating `Inode` operation covered by `FsEventKind` completes on
+    /// this filesystem. Pass `None` to unregister. A clean extension
+    /// point for a future `sys_inotify_*`; leaving it unregistered (the
+    /// default) doesn't change any existing behavior.
+    pub fn set_change_callback(&mut self, callback: Option<ChangeCallback>) {
+        self.change_callback = callback;
+    }
+
+    /// Invoke the registered callback, if any, with `event`. Called by
+    /// `Inode`'s mutating methods after the change it describes has
+    /// already taken effect.
+    pub(crate) fn notify(&self, event: FsEvent) {
+        if let Some(callback) = &self.change_callback {
+            callback(event);
+        }
+    }
+
+    pub fn root_inode(efs: &Arc<Mutex<Self>>) -> Arc<Inode> {
+        let block_device = Arc::clone(&efs.lock().block_device);
+        Self::get_inode(efs, 0, block_device)
+    }
+
+    /// Return the already-open `Inode` for `inode_id` if one is still live,
+    /// otherwise construct one and remember it, so repeated lookups of the
+    /// same on-disk inode share a single in-memory handle.
+    pub(crate) fn get_inode(
+        efs: &Arc<Mutex<Self>>,
+        inode_id: u32,
+        block_device: Arc<dyn BlockDevice>,
+    ) -> Arc<Inode> {
+        let mut fs = efs.lock();
+        if let Some(inode) = fs.open_inodes.get(&inode_id).and_then(Weak::upgrade) {
+            return inode;
+        }
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let inode = Arc::new(Inode::new(
+            inode_id,
+            block_id,
+            block_offset,
+            Arc::clone(efs),
+            block_device,
+        ));
+        fs.open_inodes.insert(inode_id, Arc::downgrade(&inode));
+        inode
+    }
+
+    /// Drop `inode_id`'s entry from the open-inode cache if it's no longer
+    /// live. Called from `Inode::drop` as its last strong reference goes away.
+    pub(crate) fn forget_inode(&mut self, inode_id: u32) {
+        if let Some(weak) = self.open_inodes.get(&inode_id) {
+            if weak.upgrade().is_none() {
+                self.open_inodes.remove(&inode_id);
+            }
+        }
+    }
+
+    pub fn get_disk_inode_pos(&self, inode_id: u32) -> (u32, usize) {
+        let inode_size = core::mem::size_of::<DiskInode>();
+        let inodes_per_block = (BLOCK_SZ / inode_size) as u32;
+        let block_id = self.inode_area_start_block + inode_id / inodes_per_block;
+        (
+            block_id,
+            (inode_id % inodes_per_block) as usize * inode_size,
+        )
+    }
+
+    pub fn get_data_block_id(&self, data_block_id: u32) -> u32 {
+        self.data_area_start_block + data_block_id
+    }
+
+    /// The half-open range of block ids `[start, end)` that a data block
+    /// pointer may legitimately point into. Used by `Inode::verify_chain`
+    /// to tell a corrupted pointer from a real one.
+    pub fn data_block_range(&self) -> (u32, u32) {
+        let start = self.data_area_start_block;
+        (start, start + self.data_bitmap.maximum() as u32)
+    }
+
+    /// Allocate a fresh inode, returning its inode number, or `None` if the
+    /// inode bitmap is full or the filesystem is mounted read-only.
+    pub fn alloc_inode(&mut self) -> Option<u32> {
+        if self.read_only {
+            return None;
+        }
+        self.inode_bitmap
+            .alloc(&self.block_device, None)
+            .map(|id| id as u32)
+    }
+
+    /// Free `inode_id`, e.g. to roll back an `alloc_inode` whose caller
+    /// couldn't finish setting up the new inode (see `Inode::create_typed`).
+    /// A no-op on a read-only filesystem, same as `dealloc_data`.
+    pub fn dealloc_inode(&mut self, inode_id: u32) {
+        if self.read_only {
+            return;
+        }
+        self.inode_bitmap
+            .dealloc(&self.block_device, inode_id as usize);
+    }
+
+    /// Set `owner`'s block quota to `blocks`, overwriting any quota
+    /// already set for it. `alloc_data`/`alloc_contiguous` refuse to hand
+    /// out a block on `owner`'s behalf once its usage would exceed this.
+    /// An owner with no quota set (the default for every synthetic owner
+    /// id) is unlimited.
+    pub fn set_quota(&mut self, owner: u32, blocks: u32) {
+        self.quotas.insert(owner, blocks);
+    }
+
+    /// Whether `owner` has room under its quota (if any) for `additional`
+    /// more blocks than it currently has allocated.
+    fn quota_allows(&self, owner: u32, additional: u32) -> bool {
+        match self.quotas.get(&owner) {
+            Some(&quota) => {
+                let used = self.owner_usage.get(&owner).copied().unwrap_or(0);
+                used.saturating_add(additional) <= quota
+            }
+            None => true,
+        }
+    }
+
+    /// Allocate a fresh data block charged against `owner`'s quota,
+    /// returning its global block id, or `None` if the disk has run out of
+    /// free blocks, `owner` has no room left under its quota, or the
+    /// filesystem is mounted read-only. `hint`, if given, is a block id
+    /// (typically the file's most recently allocated block) to allocate
+    /// near — see `Bitmap::alloc`.
+    pub fn alloc_data(&mut self, owner: u32, hint: Option<u32>) -> Option<u32> {
+        if self.read_only || !self.quota_allows(owner, 1) {
+            return None;
+        }
+        let bitmap_hint = hint.map(|id| (id - self.data_area_start_block) as usize);
+        let block_id = self
+            .data_bitmap
+            .alloc(&self.block_device, bitmap_hint)
+            .map(|id| self.data_area_start_block + id as u32)?;
+        *self.owner_usage.entry(owner).or_insert(0) += 1;
+        Some(block_id)
+    }
+
+    /// Allocate `n` contiguous data blocks charged against `owner`'s
+    /// quota, returning their block ids in ascending order, or `None` if
+    /// the free space is too fragmented to offer a run that long, `owner`
+    /// doesn't have room for all `n` under its quota, or the filesystem is
+    /// mounted read-only. Used by `Inode::defragment` to give a file's data
+    /// blocks somewhere contiguous to move into.
+    pub fn alloc_contiguous(&mut self, owner: u32, n: u32) -> Option<Vec<u32>> {
+        if self.read_only || !self.quota_allows(owner, n) {
+            return None;
+        }
+        let ids = self
+            .data_bitmap
+            .alloc_contiguous(&self.block_device, n as usize)
+            .map(|ids| {
+                ids.into_iter()
+                    .map(|id| self.data_area_start_block + id as u32)
+                    .collect::<Vec<u32>>()
+            })?;
+        *self.owner_usage.entry(owner).or_insert(0) += ids.len() as u32;
+        Some(ids)
+    }
+
+    pub fn dealloc_data(&mut self, owner: u32, block_id: u32) {
+        if self.read_only {
+            return;
+        }
+        get_block_cache(block_id as usize, Arc::clone(&self.block_device))
+            .lock()
+            .modify(0, |data_block: &mut [u8; BLOCK_SZ]| {
+                data_block.iter_mut().for_each(|p| {
+                    *p = 0;
+                })
+            });
+        self.data_bitmap.dealloc(
+            &self.block_device,
+            (block_id - self.data_area_start_block) as usize,
+        );
+        if let Some(usage) = self.owner_usage.get_mut(&owner) {
+            *usage = usage.saturating_sub(1);
+        }
+    }
+}
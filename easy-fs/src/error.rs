@@ -0,0 +1,24 @@
+/// Why an `easy-fs` operation failed. Most of the crate's API still
+/// reports failure as `Option::None` (this is `no_std`, so a heavier
+/// `Result`-with-context story isn't worth it for every lookup), but a
+/// few operations — `Inode::create`/`create_fifo` so far — have callers
+/// that need to tell "no space left" apart from "already exists" apart
+/// from "not found", which a bare `None` can't express.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsError {
+    /// No entry by that name exists.
+    NotFound,
+    /// The disk has no free inode or data block left to satisfy the
+    /// request, or the owner's quota (`EasyFileSystem::set_quota`) refused
+    /// the block.
+    NoSpace,
+    /// The underlying block device reported an error.
+    Io,
+    /// On-disk metadata didn't pass a sanity check (e.g. a corrupt
+    /// directory entry or out-of-range block pointer).
+    Corrupt,
+    /// An entry by that name already exists.
+    Exists,
+    /// The requested name is longer than the filesystem can store.
+    TooLong,
+}
@@ -0,0 +1,848 @@
+use super::{get_block_cache, BlockDevice, BLOCK_SZ};
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+const EFS_MAGIC: u32 = 0x3b800001;
+/// Current on-disk superblock layout version. Bump this whenever a field
+/// is added anywhere in the on-disk layout that an older image won't have
+/// initialized, and extend `SuperBlock::migrate` to fill in a sane default
+/// for it, gated on `self.version` before it gets bumped to the new value.
+pub const EFS_VERSION: u32 = 1;
+const INODE_DIRECT_COUNT: usize = 28;
+const INDIRECT1_BOUND: usize = DIRECT_BOUND + INODE_INDIRECT1_COUNT;
+const DIRECT_BOUND: usize = INODE_DIRECT_COUNT;
+const INODE_INDIRECT1_COUNT: usize = BLOCK_SZ / 4;
+const INODE_INDIRECT2_COUNT: usize = INODE_INDIRECT1_COUNT * INODE_INDIRECT1_COUNT;
+pub(crate) const NAME_LENGTH_LIMIT: usize = 27;
+/// A file this small fits entirely in the `direct` array's raw bytes, so
+/// `DiskInodeType::InlineFile` stores it there instead of allocating a data
+/// block for it. See `DiskInode::is_inline`.
+const INLINE_CAPACITY: usize = INODE_DIRECT_COUNT * 4;
+
+/// Superblock: stored in block 0, describes the overall layout of the image.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SuperBlock {
+    magic: u32,
+    pub total_blocks: u32,
+    pub inode_bitmap_blocks: u32,
+    pub inode_area_blocks: u32,
+    pub data_bitmap_blocks: u32,
+    pub data_area_blocks: u32,
+    /// On-disk layout version, checked against `EFS_VERSION` by
+    /// `EasyFileSystem::open` and brought up to date via `migrate` if it's
+    /// out of date. Appended after every pre-existing field so that an
+    /// image predating this field keeps all of its other fields at their
+    /// original offsets; such an image reads `version` as `0`, since those
+    /// bytes were part of `create`'s zero-fill.
+    version: u32,
+}
+
+impl core::fmt::Debug for SuperBlock {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("SuperBlock")
+            .field("total_blocks", &self.total_blocks)
+            .field("inode_bitmap_blocks", &self.inode_bitmap_blocks)
+            .field("inode_area_blocks", &self.inode_area_blocks)
+            .field("data_bitmap_blocks", &self.data_bitmap_blocks)
+            .field("data_area_blocks", &self.data_area_blocks)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl SuperBlock {
+    pub fn initialize(
+        &mut self,
+        total_blocks: u32,
+        inode_bitmap_blocks: u32,
+        inode_area_blocks: u32,
+        data_bitmap_blocks: u32,
+        data_area_blocks: u32,
+    ) {
+        *self = Self {
+            magic: EFS_MAGIC,
+            total_blocks,
+            inode_bitmap_blocks,
+            inode_area_blocks,
+            data_bitmap_blocks,
+            data_area_blocks,
+            version: EFS_VERSION,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.magic == EFS_MAGIC
+    }
+
+    /// Whether this superblock predates `EFS_VERSION` and needs `migrate`.
+    pub fn needs_migration(&self) -> bool {
+        self.version < EFS_VERSION
+    }
+
+    /// Bring an older on-disk layout up to `EFS_VERSION` in place, filling
+    /// in a default for anything a pre-version image wouldn't have
+    /// initialized. Currently a no-op beyond bumping the stamp, since
+    /// nothing has used a versioned field yet; a future migration should
+    /// check `self.version` against the version it was introduced at
+    /// before defaulting its field, so upgrading across several versions
+    /// at once still applies every intermediate step.
+    pub fn migrate(&mut self) {
+        self.version = EFS_VERSION;
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DiskInodeType {
+    File,
+    /// A regular file small enough to fit in `INLINE_CAPACITY` bytes,
+    /// stored directly in the `direct` array instead of a data block.
+    /// `initialize(DiskInodeType::File)` starts every new regular file out
+    /// this way; `DiskInode::increase_size` (reached through
+    /// `Inode::increase_size`) converts it to a plain `File` the moment it
+    /// grows past `INLINE_CAPACITY`. Never
+    /// converts back on truncation, since there's no reason to — shrinking
+    /// an already-allocated chain back down doesn't save anything `clear`
+    /// wouldn't already free.
+    InlineFile,
+    Directory,
+    /// A character or block special file; `device` on the owning
+    /// `DiskInode` holds its major/minor numbers. Nothing creates these
+    /// yet, but the layout is in place for when device files land.
+    Device,
+    /// A FIFO (named pipe). Carries no on-disk payload of its own — the
+    /// byte ring buffer backing it lives in memory, keyed by this inode's
+    /// id, for as long as some process has an end of it open.
+    Fifo,
+}
+
+#[repr(C)]
+pub struct DiskInode {
+    pub size: u32,
+    pub direct: [u32; INODE_DIRECT_COUNT],
+    pub indirect1: u32,
+    pub indirect2: u32,
+    type_: DiskInodeType,
+    /// (major, minor), meaningful only when `type_` is `Device`.
+    pub device: (u32, u32),
+    /// Number of directory entries referring to this inode. Starts at 1
+    /// for every newly created inode (its one entry in the directory that
+    /// created it); nothing increments or decrements it yet, since hard
+    /// links (`sys_linkat`/`sys_unlinkat`) aren't implemented in this tree.
+    pub nlink: u32,
+    /// Synthetic per-tenant identifier this inode's blocks are counted
+    /// against in `EasyFileSystem`'s quota accounting. Defaults to 0 for
+    /// every inode, set via `Inode::set_owner`; otherwise unused by the
+    /// filesystem itself (there's no real multi-user permission model
+    /// here, just the quota).
+    pub owner: u32,
+    /// Whether `Inode::find_inode_id` should match this directory's
+    /// entries ASCII-case-insensitively rather than byte-for-byte.
+    /// Meaningless on anything but a directory. Doesn't change what's
+    /// stored on disk — `DirEntry::new` always writes the name exactly as
+    /// given, so two entries differing only in case still collide here
+    /// the same way two identical names would.
+    pub case_insensitive: bool,
+}
+
+impl DiskInode {
+    pub fn initialize(&mut self, type_: DiskInodeType) {
+        self.size = 0;
+        self.direct.iter_mut().for_each(|v| *v = 0);
+        self.indirect1 = 0;
+        self.indirect2 = 0;
+        self.type_ = if type_ == DiskInodeType::File {
+            DiskInodeType::InlineFile
+        } else {
+            type_
+        };
+        self.device = (0, 0);
+        self.nlink = 1;
+        self.owner = 0;
+        self.case_insensitive = false;
+    }
+
+    /// Initialize this inode as a device special file with the given
+    /// major/minor numbers.
+    pub fn initialize_device(&mut self, major: u32, minor: u32) {
+        self.initialize(DiskInodeType::Device);
+        self.device = (major, minor);
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.type_ == DiskInodeType::Directory
+    }
+
+    pub fn is_file(&self) -> bool {
+        matches!(self.type_, DiskInodeType::File | DiskInodeType::InlineFile)
+    }
+
+    /// Whether this file's content is stored inline in `direct`'s raw
+    /// bytes rather than in an allocated data block.
+    pub fn is_inline(&self) -> bool {
+        self.type_ == DiskInodeType::InlineFile
+    }
+
+    /// View `direct`'s backing bytes as the inline file content they hold
+    /// while `is_inline()`. Meaningless otherwise.
+    fn inline_bytes(&self) -> &[u8; INLINE_CAPACITY] {
+        unsafe { &*(self.direct.as_ptr() as *const [u8; INLINE_CAPACITY]) }
+    }
+
+    fn inline_bytes_mut(&mut self) -> &mut [u8; INLINE_CAPACITY] {
+        unsafe { &mut *(self.direct.as_mut_ptr() as *mut [u8; INLINE_CAPACITY]) }
+    }
+
+    /// Move this inode's inline content out into `first_block` and switch
+    /// it to a plain `File`, because it's about to grow past
+    /// `INLINE_CAPACITY`. The block is already zeroed (every data block is,
+    /// until something writes to it), so only the bytes this file actually
+    /// holds need copying in.
+    fn convert_from_inline(&mut self, block_device: &Arc<dyn BlockDevice>, first_block: u32) {
+        let content = *self.inline_bytes();
+        let len = self.size as usize;
+        self.direct.iter_mut().for_each(|v| *v = 0);
+        self.indirect1 = 0;
+        self.indirect2 = 0;
+        self.type_ = DiskInodeType::File;
+        self.direct[0] = first_block;
+        get_block_cache(first_block as usize, Arc::clone(block_device))
+            .lock()
+            .modify(0, |data_block: &mut DataBlock| {
+                data_block[..len].copy_from_slice(&content[..len]);
+            });
+    }
+
+    pub fn is_device(&self) -> bool {
+        self.type_ == DiskInodeType::Device
+    }
+
+    pub fn is_fifo(&self) -> bool {
+        self.type_ == DiskInodeType::Fifo
+    }
+
+    /// Return block id of the `inner_id`-th data block.
+    pub fn get_block_id(&self, inner_id: u32, block_device: &Arc<dyn BlockDevice>) -> u32 {
+        let inner_id = inner_id as usize;
+        if inner_id < INODE_DIRECT_COUNT {
+            self.direct[inner_id]
+        } else if inner_id < INDIRECT1_BOUND {
+            get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect_block: &IndirectBlock| {
+                    indirect_block[inner_id - INODE_DIRECT_COUNT]
+                })
+        } else {
+            let last = inner_id - INDIRECT1_BOUND;
+            let indirect1 = get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    indirect2[last / INODE_INDIRECT1_COUNT]
+                });
+            get_block_cache(indirect1 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect1: &IndirectBlock| {
+                    indirect1[last % INODE_INDIRECT1_COUNT]
+                })
+        }
+    }
+
+    /// Like `get_block_id`, but for a block already known to lie within
+    /// the direct array — skips the indirect-block bounds cascade
+    /// entirely rather than just short-circuiting through it.
+    fn direct_block_id(&self, inner_id: u32) -> u32 {
+        self.direct[inner_id as usize]
+    }
+
+    /// Like `get_block_id`, but reuses `cache` — the most recently loaded
+    /// indirect1 block and its id — across calls that land in the same
+    /// indirect1 range, so a sequential scan only re-fetches an indirect1
+    /// block when it actually crosses into a new one.
+    fn get_block_id_cached(
+        &self,
+        inner_id: u32,
+        block_device: &Arc<dyn BlockDevice>,
+        cache: &mut Option<(u32, IndirectBlock)>,
+    ) -> u32 {
+        let inner_id = inner_id as usize;
+        if inner_id < INODE_DIRECT_COUNT {
+            return self.direct[inner_id];
+        }
+        let (indirect1_id, offset) = if inner_id < INDIRECT1_BOUND {
+            (self.indirect1, inner_id - INODE_DIRECT_COUNT)
+        } else {
+            let last = inner_id - INDIRECT1_BOUND;
+            let indirect1_id = get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    indirect2[last / INODE_INDIRECT1_COUNT]
+                });
+            (indirect1_id, last % INODE_INDIRECT1_COUNT)
+        };
+        if cache.map(|(id, _)| id) != Some(indirect1_id) {
+            let block = get_block_cache(indirect1_id as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect1: &IndirectBlock| *indirect1);
+            *cache = Some((indirect1_id, block));
+        }
+        cache.unwrap().1[offset]
+    }
+
+    /// Overwrite the `inner_id`-th data block pointer with `value`. Used by
+    /// `punch_hole` to zero out a leaf entry (turning it into a hole), by
+    /// `Inode::materialize_holes` to replace a hole with a freshly allocated
+    /// block before writing into it, and by `Inode::defragment` to repoint
+    /// each leaf at its relocated block.
+    pub(crate) fn set_block_id(
+        &mut self,
+        inner_id: u32,
+        value: u32,
+        block_device: &Arc<dyn BlockDevice>,
+    ) {
+        let inner_id = inner_id as usize;
+        if inner_id < INODE_DIRECT_COUNT {
+            self.direct[inner_id] = value;
+        } else if inner_id < INDIRECT1_BOUND {
+            get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
+                .lock()
+                .modify(0, |indirect_block: &mut IndirectBlock| {
+                    indirect_block[inner_id - INODE_DIRECT_COUNT] = value;
+                });
+        } else {
+            let last = inner_id - INDIRECT1_BOUND;
+            let indirect1_id = get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    indirect2[last / INODE_INDIRECT1_COUNT]
+                });
+            get_block_cache(indirect1_id as usize, Arc::clone(block_device))
+                .lock()
+                .modify(0, |indirect1: &mut IndirectBlock| {
+                    indirect1[last % INODE_INDIRECT1_COUNT] = value;
+                });
+        }
+    }
+
+    /// Number of data blocks actually allocated to this file right now,
+    /// excluding holes `punch_hole` has freed (but including index blocks,
+    /// same as `total_blocks`). Equal to `total_blocks(size)` for a file
+    /// that's never had a hole punched in it.
+    pub fn blocks_used(&self, block_device: &Arc<dyn BlockDevice>) -> u32 {
+        if self.is_inline() {
+            return 0;
+        }
+        let data_blocks = Self::_data_blocks(self.size);
+        let mut holes = 0u32;
+        let mut indirect1_cache = None;
+        for inner_id in 0..data_blocks {
+            if self.get_block_id_cached(inner_id, block_device, &mut indirect1_cache) == 0 {
+                holes += 1;
+            }
+        }
+        Self::total_blocks(self.size) - holes
+    }
+
+    /// Free every data block fully covered by `[offset, offset + len)`
+    /// (capped to `size`), zeroing a partially-covered edge block's
+    /// covered bytes in place rather than freeing it, and leaving `size`
+    /// itself unchanged. Returns the freed block ids for the caller to hand
+    /// back to the data bitmap. A block already punched (pointer 0) within
+    /// the range is left alone rather than being double-freed.
+    pub fn punch_hole(
+        &mut self,
+        offset: u32,
+        len: u32,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> Vec<u32> {
+        let offset = offset as usize;
+        let end = (offset + len as usize).min(self.size as usize);
+        if offset >= end {
+            return Vec::new();
+        }
+        if self.is_inline() {
+            self.inline_bytes_mut()[offset..end]
+                .iter_mut()
+                .for_each(|b| *b = 0);
+            return Vec::new();
+        }
+        let first_block = (offset / BLOCK_SZ) as u32;
+        let last_block = ((end - 1) / BLOCK_SZ) as u32;
+        let direct_only = last_block < INODE_DIRECT_COUNT as u32;
+        let mut freed = Vec::new();
+        let mut indirect1_cache = None;
+        for inner_id in first_block..=last_block {
+            let block_start = inner_id as usize * BLOCK_SZ;
+            let block_end = block_start + BLOCK_SZ;
+            let covered_start = offset.max(block_start);
+            let covered_end = end.min(block_end);
+            let block_id = if direct_only {
+                self.direct_block_id(inner_id)
+            } else {
+                self.get_block_id_cached(inner_id, block_device, &mut indirect1_cache)
+            };
+            if block_id == 0 {
+                continue;
+            }
+            if covered_start == block_start && covered_end == block_end {
+                freed.push(block_id);
+                self.set_block_id(inner_id, 0, block_device);
+                // The set just invalidated whatever `get_block_id_cached`
+                // had cached for this indirect1 range.
+                indirect1_cache = None;
+            } else {
+                get_block_cache(block_id as usize, Arc::clone(block_device))
+                    .lock()
+                    .modify(0, |data_block: &mut DataBlock| {
+                        data_block[covered_start - block_start..covered_end - block_start]
+                            .iter_mut()
+                            .for_each(|b| *b = 0);
+                    });
+            }
+        }
+        freed
+    }
+
+    pub(crate) fn data_blocks(&self) -> u32 {
+        if self.is_inline() {
+            return 0;
+        }
+        Self::_data_blocks(self.size)
+    }
+
+    fn _data_blocks(size: u32) -> u32 {
+        size.div_ceil(BLOCK_SZ as u32)
+    }
+
+    pub fn total_blocks(size: u32) -> u32 {
+        let data_blocks = Self::_data_blocks(size) as usize;
+        let mut total = data_blocks;
+        if data_blocks > INODE_DIRECT_COUNT {
+            total += 1;
+        }
+        if data_blocks > INDIRECT1_BOUND {
+            total += 1;
+            total += (data_blocks - INDIRECT1_BOUND).div_ceil(INODE_INDIRECT1_COUNT);
+        }
+        total as u32
+    }
+
+    pub fn blocks_num_needed(&self, new_size: u32) -> u32 {
+        assert!(new_size >= self.size);
+        if self.is_inline() {
+            return if new_size as usize <= INLINE_CAPACITY {
+                0
+            } else {
+                Self::total_blocks(new_size)
+            };
+        }
+        Self::total_blocks(new_size) - Self::total_blocks(self.size)
+    }
+
+    /// Check that every block id this inode's metadata references — its
+    /// direct entries, `indirect1` and the data blocks it lists,
+    /// `indirect2` and each level-1 block it lists along with *their* data
+    /// blocks — satisfies `in_range` and is never repeated.
+    ///
+    /// This tree's block chain is a fixed 3-level tree rather than a
+    /// generic linked structure, so a pointer can't literally send a
+    /// traversal into a cycle the way it could in a filesystem where
+    /// indirect blocks chain into further indirect blocks. What a
+    /// corrupted pointer can do here is land outside the data area (so
+    /// `read_at`/`clear_size` reads or frees garbage) or alias a block
+    /// already claimed elsewhere in the same chain (so two parts of the
+    /// file silently share storage). Both are what this checks.
+    ///
+    /// A pointer is range-checked *before* it's ever dereferenced, so a
+    /// garbage `indirect1`/`indirect2`/level-1 id is reported as `false`
+    /// without the block cache ever being asked to read it.
+    pub fn verify_chain(
+        &self,
+        block_device: &Arc<dyn BlockDevice>,
+        in_range: impl Fn(u32) -> bool,
+    ) -> bool {
+        if self.is_inline() {
+            // No block pointers at all while inline — `direct` holds raw
+            // content bytes, not block ids, so there's nothing to check.
+            return true;
+        }
+        let mut seen = BTreeSet::new();
+        let visit = |id: u32, seen: &mut BTreeSet<u32>| in_range(id) && seen.insert(id);
+        // A leaf data block entry of 0 is a hole `punch_hole` left behind,
+        // not a real pointer — always valid, and never counted toward
+        // aliasing, unlike index blocks which are never holes.
+        let visit_leaf = |id: u32, seen: &mut BTreeSet<u32>| id == 0 || visit(id, seen);
+        let data_blocks = Self::_data_blocks(self.size) as usize;
+
+        let direct_count = data_blocks.min(INODE_DIRECT_COUNT);
+        for &id in &self.direct[..direct_count] {
+            if !visit_leaf(id, &mut seen) {
+                return false;
+            }
+        }
+        if data_blocks <= INODE_DIRECT_COUNT {
+            return true;
+        }
+
+        if !visit(self.indirect1, &mut seen) {
+            return false;
+        }
+        let indirect1_count = (data_blocks - INODE_DIRECT_COUNT).min(INODE_INDIRECT1_COUNT);
+        let indirect1_block = get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |b: &IndirectBlock| *b);
+        for &id in &indirect1_block[..indirect1_count] {
+            if !visit_leaf(id, &mut seen) {
+                return false;
+            }
+        }
+        if data_blocks <= INDIRECT1_BOUND {
+            return true;
+        }
+
+        if !visit(self.indirect2, &mut seen) {
+            return false;
+        }
+        let remaining = data_blocks - INDIRECT1_BOUND;
+        let level1_count = remaining.div_ceil(INODE_INDIRECT1_COUNT);
+        let indirect2_block = get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+            .lock()
+            .read(0, |b: &IndirectBlock| *b);
+        for (i, &level1_id) in indirect2_block[..level1_count].iter().enumerate() {
+            if !visit(level1_id, &mut seen) {
+                return false;
+            }
+            let this_count = remaining
+                .saturating_sub(i * INODE_INDIRECT1_COUNT)
+                .min(INODE_INDIRECT1_COUNT);
+            let level1_block = get_block_cache(level1_id as usize, Arc::clone(block_device))
+                .lock()
+                .read(0, |b: &IndirectBlock| *b);
+            for &id in &level1_block[..this_count] {
+                if !visit_leaf(id, &mut seen) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Grow the inode, pulling new block ids from `new_blocks`. Returns
+    /// `false` without touching the inode if `new_blocks` holds fewer
+    /// entries than `blocks_num_needed(new_size)` calls for, rather than
+    /// panicking partway through on a short supply (a caller miscount or a
+    /// partial ENOSPC that wasn't fully rolled back).
+    pub fn increase_size(
+        &mut self,
+        new_size: u32,
+        new_blocks: Vec<u32>,
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> bool {
+        if new_blocks.len() as u32 != self.blocks_num_needed(new_size) {
+            return false;
+        }
+        let mut new_blocks = new_blocks;
+        if self.is_inline() {
+            if new_size as usize <= INLINE_CAPACITY {
+                self.size = new_size;
+                return true;
+            }
+            // Growing past the inline limit: hand the first allocated
+            // block to `convert_from_inline` and fall through into the
+            // ordinary growth path below for the rest, with `size`
+            // adjusted so `data_blocks()` below reports the 1 block just
+            // placed instead of the 0 an inline file's `size` implies.
+            let first_block = new_blocks.remove(0);
+            self.convert_from_inline(block_device, first_block);
+            self.size = 1;
+        }
+        let mut current_blocks = self.data_blocks();
+        self.size = new_size;
+        let mut total_blocks = self.data_blocks();
+        let mut new_blocks = new_blocks.into_iter();
+        while current_blocks < total_blocks.min(INODE_DIRECT_COUNT as u32) {
+            self.direct[current_blocks as usize] = new_blocks.next().unwrap();
+            current_blocks += 1;
+        }
+        if total_blocks > INODE_DIRECT_COUNT as u32 {
+            if current_blocks == INODE_DIRECT_COUNT as u32 {
+                self.indirect1 = new_blocks.next().unwrap();
+            }
+            current_blocks -= INODE_DIRECT_COUNT as u32;
+            total_blocks -= INODE_DIRECT_COUNT as u32;
+        } else {
+            return true;
+        }
+        get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
+            .lock()
+            .modify(0, |indirect1: &mut IndirectBlock| {
+                while current_blocks < total_blocks.min(INODE_INDIRECT1_COUNT as u32) {
+                    indirect1[current_blocks as usize] = new_blocks.next().unwrap();
+                    current_blocks += 1;
+                }
+            });
+        if total_blocks > INODE_INDIRECT1_COUNT as u32 {
+            if current_blocks == INODE_INDIRECT1_COUNT as u32 {
+                self.indirect2 = new_blocks.next().unwrap();
+            }
+            current_blocks -= INODE_INDIRECT1_COUNT as u32;
+            total_blocks -= INODE_INDIRECT1_COUNT as u32;
+        } else {
+            return true;
+        }
+        let mut a0 = current_blocks as usize / INODE_INDIRECT1_COUNT;
+        let mut b0 = current_blocks as usize % INODE_INDIRECT1_COUNT;
+        let a1 = total_blocks as usize / INODE_INDIRECT1_COUNT;
+        let b1 = total_blocks as usize % INODE_INDIRECT1_COUNT;
+        get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+            .lock()
+            .modify(0, |indirect2: &mut IndirectBlock| {
+                while (a0 < a1) || (a0 == a1 && b0 < b1) {
+                    if b0 == 0 {
+                        indirect2[a0] = new_blocks.next().unwrap();
+                    }
+                    get_block_cache(indirect2[a0] as usize, Arc::clone(block_device))
+                        .lock()
+                        .modify(0, |indirect1: &mut IndirectBlock| {
+                            indirect1[b0] = new_blocks.next().unwrap();
+                        });
+                    b0 += 1;
+                    if b0 == INODE_INDIRECT1_COUNT {
+                        b0 = 0;
+                        a0 += 1;
+                    }
+                }
+            });
+        true
+    }
+
+    /// Shrink the inode down to size 0, returning the freed block ids so
+    /// the caller can release them back to the data bitmap. A leaf data
+    /// block entry of 0 is a hole left by `punch_hole`, not a real block —
+    /// it's skipped rather than handed back for deallocation, unlike the
+    /// index blocks (`indirect1`/`indirect2`/each level-1 block), which are
+    /// never punched and so are always real.
+    pub fn clear_size(&mut self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        if self.is_inline() {
+            self.size = 0;
+            return Vec::new();
+        }
+        let mut v: Vec<u32> = Vec::new();
+        let mut data_blocks = self.data_blocks() as usize;
+        self.size = 0;
+        let mut current_blocks = 0usize;
+        while current_blocks < data_blocks.min(INODE_DIRECT_COUNT) {
+            if self.direct[current_blocks] != 0 {
+                v.push(self.direct[current_blocks]);
+            }
+            self.direct[current_blocks] = 0;
+            current_blocks += 1;
+        }
+        if data_blocks > INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+            data_blocks -= INODE_DIRECT_COUNT;
+            current_blocks = 0;
+        } else {
+            return v;
+        }
+        get_block_cache(self.indirect1 as usize, Arc::clone(block_device))
+            .lock()
+            .modify(0, |indirect1: &mut IndirectBlock| {
+                while current_blocks < data_blocks.min(INODE_INDIRECT1_COUNT) {
+                    if indirect1[current_blocks] != 0 {
+                        v.push(indirect1[current_blocks]);
+                    }
+                    current_blocks += 1;
+                }
+            });
+        self.indirect1 = 0;
+        if data_blocks > INODE_INDIRECT1_COUNT {
+            v.push(self.indirect2);
+            data_blocks -= INODE_INDIRECT1_COUNT;
+        } else {
+            return v;
+        }
+        assert!(data_blocks <= INODE_INDIRECT2_COUNT);
+        let a1 = data_blocks / INODE_INDIRECT1_COUNT;
+        let b1 = data_blocks % INODE_INDIRECT1_COUNT;
+        get_block_cache(self.indirect2 as usize, Arc::clone(block_device))
+            .lock()
+            .modify(0, |indirect2: &mut IndirectBlock| {
+                for entry in indirect2.iter_mut().take(a1) {
+                    v.push(*entry);
+                    get_block_cache(*entry as usize, Arc::clone(block_device))
+                        .lock()
+                        .modify(0, |indirect1: &mut IndirectBlock| {
+                            for entry in indirect1.iter() {
+                                if *entry != 0 {
+                                    v.push(*entry);
+                                }
+                            }
+                        });
+                }
+                if b1 > 0 {
+                    v.push(indirect2[a1]);
+                    get_block_cache(indirect2[a1] as usize, Arc::clone(block_device))
+                        .lock()
+                        .modify(0, |indirect1: &mut IndirectBlock| {
+                            for entry in indirect1.iter().take(b1) {
+                                if *entry != 0 {
+                                    v.push(*entry);
+                                }
+                            }
+                        });
+                }
+            });
+        self.indirect2 = 0;
+        v
+    }
+
+    pub fn read_at(
+        &self,
+        offset: usize,
+        buf: &mut [u8],
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> usize {
+        let end = (offset + buf.len()).min(self.size as usize);
+        if offset >= end {
+            return 0;
+        }
+        if self.is_inline() {
+            let src = &self.inline_bytes()[offset..end];
+            buf[..src.len()].copy_from_slice(src);
+            return src.len();
+        }
+        let mut start = offset;
+        let mut start_block = start / BLOCK_SZ;
+        let mut read_size = 0usize;
+        // Small files (the common case) live entirely in the direct array;
+        // skip `get_block_id`'s indirect-block bounds cascade for them.
+        let direct_only = end <= INODE_DIRECT_COUNT * BLOCK_SZ;
+        let mut indirect1_cache = None;
+        loop {
+            let mut end_current_block = (start / BLOCK_SZ + 1) * BLOCK_SZ;
+            end_current_block = end_current_block.min(end);
+            let block_read_size = end_current_block - start;
+            let dst = &mut buf[read_size..read_size + block_read_size];
+            let block_id = if direct_only {
+                self.direct_block_id(start_block as u32)
+            } else {
+                self.get_block_id_cached(start_block as u32, block_device, &mut indirect1_cache)
+            };
+            if block_id == 0 {
+                // A hole `punch_hole` left behind: reads back as zeros
+                // without ever touching the block cache.
+                dst.iter_mut().for_each(|b| *b = 0);
+            } else {
+                get_block_cache(block_id as usize, Arc::clone(block_device))
+                    .lock()
+                    .read(0, |data_block: &DataBlock| {
+                        let src = &data_block[start % BLOCK_SZ..start % BLOCK_SZ + block_read_size];
+                        dst.copy_from_slice(src);
+                    });
+            }
+            read_size += block_read_size;
+            if end_current_block == end {
+                break;
+            }
+            start_block += 1;
+            start = end_current_block;
+        }
+        read_size
+    }
+
+    pub fn write_at(
+        &mut self,
+        offset: usize,
+        buf: &[u8],
+        block_device: &Arc<dyn BlockDevice>,
+    ) -> usize {
+        let end = (offset + buf.len()).min(self.size as usize);
+        assert!(offset <= end);
+        if self.is_inline() {
+            let len = end - offset;
+            self.inline_bytes_mut()[offset..end].copy_from_slice(&buf[..len]);
+            return len;
+        }
+        let mut start = offset;
+        let mut start_block = start / BLOCK_SZ;
+        let mut write_size = 0usize;
+        let direct_only = end <= INODE_DIRECT_COUNT * BLOCK_SZ;
+        let mut indirect1_cache = None;
+        loop {
+            let mut end_current_block = (start / BLOCK_SZ + 1) * BLOCK_SZ;
+            end_current_block = end_current_block.min(end);
+            let block_write_size = end_current_block - start;
+            let block_id = if direct_only {
+                self.direct_block_id(start_block as u32)
+            } else {
+                self.get_block_id_cached(start_block as u32, block_device, &mut indirect1_cache)
+            };
+            get_block_cache(block_id as usize, Arc::clone(block_device))
+                .lock()
+                .modify(0, |data_block: &mut DataBlock| {
+                    let src = &buf[write_size..write_size + block_write_size];
+                    let dst =
+                        &mut data_block[start % BLOCK_SZ..start % BLOCK_SZ + block_write_size];
+                    dst.copy_from_slice(src);
+                });
+            write_size += block_write_size;
+            if end_current_block == end {
+                break;
+            }
+            start_block += 1;
+            start = end_current_block;
+        }
+        write_size
+    }
+}
+
+type IndirectBlock = [u32; BLOCK_SZ / 4];
+pub type DataBlock = [u8; BLOCK_SZ];
+
+#[repr(C)]
+pub struct DirEntry {
+    name: [u8; NAME_LENGTH_LIMIT + 1],
+    inode_number: u32,
+}
+
+pub const DIRENT_SZ: usize = 32;
+
+impl DirEntry {
+    pub fn empty() -> Self {
+        Self {
+            name: [0u8; NAME_LENGTH_LIMIT + 1],
+            inode_number: 0,
+        }
+    }
+
+    pub fn new(name: &str, inode_number: u32) -> Self {
+        let mut bytes = [0u8; NAME_LENGTH_LIMIT + 1];
+        let name_bytes = name.as_bytes();
+        let len = name_bytes.len().min(NAME_LENGTH_LIMIT);
+        bytes[..len].copy_from_slice(&name_bytes[..len]);
+        Self {
+            name: bytes,
+            inode_number,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self as *const _ as usize as *const u8, DIRENT_SZ) }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self as *mut _ as usize as *mut u8, DIRENT_SZ) }
+    }
+
+    /// This entry's name, or `None` if the on-disk bytes are corrupt: no
+    /// NUL terminator within the name field, or not valid UTF-8.
+    pub fn name(&self) -> Option<&str> {
+        let len = self.name.iter().position(|&b| b == 0)?;
+        core::str::from_utf8(&self.name[..len]).ok()
+    }
+
+    pub fn inode_number(&self) -> u32 {
+        self.inode_number
+    }
+}
@@ -0,0 +1,25 @@
+#![no_std]
+//! An easy file system isolated from the kernel
+extern crate alloc;
+
+mod bitmap;
+mod block_cache;
+mod block_dev;
+mod efs;
+mod error;
+mod layout;
+mod trace_dev;
+mod vfs;
+
+pub const BLOCK_SZ: usize = 512;
+pub use bitmap::Bitmap;
+pub use block_cache::{
+    block_cache_sync_all, flush_block_range, get_block_cache, pin_block, tick, writeback_stale,
+    BlockCache,
+};
+pub use block_dev::BlockDevice;
+pub use efs::{EasyFileSystem, FsEvent, FsEventKind};
+pub use error::FsError;
+pub use layout::*;
+pub use trace_dev::{BlockOp, TracingBlockDevice};
+pub use vfs::Inode;
@@ -0,0 +1,55 @@
+use super::BlockDevice;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// A single recorded I/O against a `TracingBlockDevice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockOp {
+    Read,
+    Write,
+}
+
+/// A `BlockDevice` wrapper that delegates every read/write to `inner` while
+/// appending each `(op, block_id)` to a shared log, for diagnosing exactly
+/// what I/O a filesystem operation issues.
+pub struct TracingBlockDevice {
+    inner: Arc<dyn BlockDevice>,
+    log: Mutex<Vec<(BlockOp, usize)>>,
+}
+
+impl TracingBlockDevice {
+    pub fn new(inner: Arc<dyn BlockDevice>) -> Self {
+        Self {
+            inner,
+            log: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The `(op, block_id)` sequence recorded so far, oldest first.
+    pub fn log(&self) -> Vec<(BlockOp, usize)> {
+        self.log.lock().clone()
+    }
+
+    /// Discard the recorded log without affecting `inner`.
+    pub fn clear_log(&self) {
+        self.log.lock().clear();
+    }
+}
+
+impl BlockDevice for TracingBlockDevice {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.log.lock().push((BlockOp::Read, block_id));
+        self.inner.read_block(block_id, buf);
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.log.lock().push((BlockOp::Write, block_id));
+        self.inner.write_block(block_id, buf);
+    }
+
+    fn write_blocks(&self, start_block_id: usize, buf: &[u8]) {
+        self.log.lock().push((BlockOp::Write, start_block_id));
+        self.inner.write_blocks(start_block_id, buf);
+    }
+}
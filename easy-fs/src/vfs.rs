@@ -0,0 +1,883 @@
+use super::{
+    block_cache_sync_all, flush_block_range, get_block_cache, BlockDevice, DataBlock, DirEntry,
+    DiskInode, DiskInodeType, EasyFileSystem, FsError, FsEvent, FsEventKind, BLOCK_SZ, DIRENT_SZ,
+    NAME_LENGTH_LIMIT,
+};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use spin::{Mutex, MutexGuard};
+
+/// Ceiling on `Inode::walk`'s recursion depth. There's no real directory
+/// nesting in this filesystem yet (`create`/`create_fifo` only ever add
+/// entries to the root directory) and no symlinks to form a cycle with,
+/// but `walk` tracks visited inode ids and stops here regardless, so it's
+/// already safe against both once either lands.
+const WALK_MAX_DEPTH: usize = 64;
+
+/// A small sequential write pending flush to the block cache, used to
+/// combine a run of tiny appends into a single disk write.
+struct AppendBuffer {
+    offset: usize,
+    data: Vec<u8>,
+}
+
+/// Above this many buffered bytes, a write is flushed immediately rather
+/// than combined further.
+const WRITE_COMBINE_LIMIT: usize = 256;
+
+/// In-memory `name -> (inode id, byte offset of the DirEntry)` index for a
+/// directory's entries, built lazily on first lookup so that repeated
+/// `find`s don't re-scan every `DirEntry` on disk.
+type DirIndex = BTreeMap<String, (u32, usize)>;
+
+/// A handle to an on-disk inode, shared between the kernel's open-file
+/// table and the directory that named it.
+pub struct Inode {
+    inode_id: u32,
+    block_id: usize,
+    block_offset: usize,
+    fs: Arc<Mutex<EasyFileSystem>>,
+    block_device: Arc<dyn BlockDevice>,
+    write_buf: Mutex<Option<AppendBuffer>>,
+    dir_index: Mutex<Option<DirIndex>>,
+    /// Whether `size` or a block pointer has changed since the inode's
+    /// metadata block was last flushed. Lets `fdatasync` skip rewriting it
+    /// when only non-essential metadata (e.g. a future timestamp) is dirty.
+    meta_dirty: Mutex<bool>,
+    /// Set when a buffered write combined by `write_buf` later failed to
+    /// actually reach disk (e.g. the disk filled up between the write
+    /// returning "success" and the deferred flush running). `write_at`
+    /// can't retroactively un-succeed that earlier call, so it sticks here
+    /// until the next `write_at` call notices it and reports a short write
+    /// instead of silently losing the data.
+    write_buf_error: Mutex<bool>,
+    /// The directory this inode was last reached through via `find_path`,
+    /// if any. Since directory entries don't store `..` on disk, this is
+    /// the only way `..` can be resolved, and only along a path that was
+    /// actually descended.
+    parent: Mutex<Option<Weak<Inode>>>,
+}
+
+impl Inode {
+    pub fn new(
+        inode_id: u32,
+        block_id: u32,
+        block_offset: usize,
+        fs: Arc<Mutex<EasyFileSystem>>,
+        block_device: Arc<dyn BlockDevice>,
+    ) -> Self {
+        Self {
+            inode_id,
+            block_id: block_id as usize,
+            block_offset,
+            fs,
+            block_device,
+            write_buf: Mutex::new(None),
+            dir_index: Mutex::new(None),
+            meta_dirty: Mutex::new(false),
+            write_buf_error: Mutex::new(false),
+            parent: Mutex::new(None),
+        }
+    }
+
+    /// Record `parent` as the directory this inode was reached through, for
+    /// `find_path` to resolve a later `..` component against.
+    fn set_parent(&self, parent: &Arc<Inode>) {
+        *self.parent.lock() = Some(Arc::downgrade(parent));
+    }
+
+    /// The directory this inode was last reached through via `find_path`,
+    /// if that directory is still alive.
+    pub fn parent(&self) -> Option<Arc<Inode>> {
+        self.parent.lock().as_ref().and_then(Weak::upgrade)
+    }
+
+    /// Resolve a `/`-separated path relative to `dir`, recording the parent
+    /// directory of each inode descended into so that a later `..`
+    /// component can be resolved from the in-memory cache rather than an
+    /// on-disk entry. An inode with no recorded parent (because it wasn't
+    /// reached via this function) treats `..` as a no-op.
+    pub fn find_path(dir: &Arc<Inode>, path: &str) -> Option<Arc<Inode>> {
+        let mut current = dir.clone();
+        for component in path.split('/').filter(|s| !s.is_empty()) {
+            current = match component {
+                "." => current,
+                ".." => current.parent().unwrap_or_else(|| current.clone()),
+                name => {
+                    let child = current.find(name)?;
+                    child.set_parent(&current);
+                    child
+                }
+            };
+        }
+        Some(current)
+    }
+
+    fn read_disk_inode<V>(&self, f: impl FnOnce(&DiskInode) -> V) -> V {
+        get_block_cache(self.block_id, Arc::clone(&self.block_device))
+            .lock()
+            .read(self.block_offset, f)
+    }
+
+    fn modify_disk_inode<V>(&self, f: impl FnOnce(&mut DiskInode) -> V) -> V {
+        get_block_cache(self.block_id, Arc::clone(&self.block_device))
+            .lock()
+            .modify(self.block_offset, f)
+    }
+
+    /// Looks up `name` by exact match first; if that misses and
+    /// `disk_inode.case_insensitive` is set (see `set_case_insensitive`),
+    /// falls back to an ASCII-case-insensitive scan of every entry. The
+    /// exact match stays first (and stays O(log n) via `dir_index`) so
+    /// case-insensitive mode costs nothing extra for the common case of a
+    /// caller that already has the on-disk casing right.
+    fn find_inode_id(&self, name: &str, disk_inode: &DiskInode) -> Option<u32> {
+        assert!(disk_inode.is_dir());
+        let mut dir_index = self.dir_index.lock();
+        if dir_index.is_none() {
+            *dir_index = Some(self.build_dir_index(disk_inode));
+        }
+        let index = dir_index.as_ref().unwrap();
+        if let Some((inode_id, _offset)) = index.get(name) {
+            return Some(*inode_id);
+        }
+        if !disk_inode.case_insensitive {
+            return None;
+        }
+        let lower = name.to_ascii_lowercase();
+        index
+            .iter()
+            .find(|(entry_name, _)| entry_name.to_ascii_lowercase() == lower)
+            .map(|(_name, (inode_id, _offset))| *inode_id)
+    }
+
+    /// Scan every `DirEntry` once to build the `name -> (inode id, offset)`
+    /// index cached in `dir_index`.
+    fn build_dir_index(&self, disk_inode: &DiskInode) -> DirIndex {
+        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let mut dirent = DirEntry::empty();
+        let mut index = DirIndex::new();
+        for i in 0..file_count {
+            let offset = DIRENT_SZ * i;
+            assert_eq!(
+                disk_inode.read_at(offset, dirent.as_bytes_mut(), &self.block_device),
+                DIRENT_SZ,
+            );
+            if let Some(name) = dirent.name() {
+                index.insert(name.to_string(), (dirent.inode_number(), offset));
+            }
+        }
+        index
+    }
+
+    /// Drop the cached name index; the next lookup rebuilds it from disk.
+    /// Must be called after any change to this directory's entries.
+    fn invalidate_dir_index(&self) {
+        *self.dir_index.lock() = None;
+    }
+
+    /// Whether this inode refers to a directory rather than a regular file.
+    pub fn is_dir(&self) -> bool {
+        self.read_disk_inode(DiskInode::is_dir)
+    }
+
+    /// Whether this inode refers to a device special file.
+    pub fn is_device(&self) -> bool {
+        self.read_disk_inode(DiskInode::is_device)
+    }
+
+    /// `(major, minor)` for a device special file; meaningless otherwise.
+    pub fn device(&self) -> (u32, u32) {
+        self.read_disk_inode(|disk_inode| disk_inode.device)
+    }
+
+    /// Whether this inode refers to a FIFO (named pipe).
+    pub fn is_fifo(&self) -> bool {
+        self.read_disk_inode(DiskInode::is_fifo)
+    }
+
+    /// Whether this inode's filesystem was mounted via `EasyFileSystem::open_read_only`.
+    pub fn is_read_only(&self) -> bool {
+        self.fs.lock().is_read_only()
+    }
+
+    /// This inode's synthetic quota-tracking owner id, set via
+    /// `set_owner`. Defaults to 0 for every inode that hasn't had an
+    /// owner assigned.
+    pub fn owner(&self) -> u32 {
+        self.read_disk_inode(|disk_inode| disk_inode.owner)
+    }
+
+    /// Reassign this inode's quota-tracking owner id, used together with
+    /// `EasyFileSystem::set_quota`'s per-owner block accounting. Doesn't
+    /// retroactively move this inode's already-allocated blocks from the
+    /// old owner's usage count to the new one's — only blocks allocated
+    /// after the change are charged to `owner`.
+    pub fn set_owner(&self, owner: u32) {
+        if self.is_read_only() {
+            return;
+        }
+        self.modify_disk_inode(|disk_inode| disk_inode.owner = owner);
+        *self.meta_dirty.lock() = true;
+    }
+
+    /// Whether `find`/`create`/`create_fifo` match this directory's
+    /// entries case-insensitively, set via `set_case_insensitive`.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.read_disk_inode(|disk_inode| disk_inode.case_insensitive)
+    }
+
+    /// Turn case-insensitive lookups on or off for this directory. Only
+    /// affects how a later `find_inode_id` matches a name against
+    /// entries already here (and any added afterward) — it doesn't touch
+    /// any entry's stored casing, and an exact match is still always
+    /// preferred over a case-insensitive one.
+    pub fn set_case_insensitive(&self, enabled: bool) {
+        if self.is_read_only() {
+            return;
+        }
+        self.modify_disk_inode(|disk_inode| disk_inode.case_insensitive = enabled);
+        *self.meta_dirty.lock() = true;
+    }
+
+    /// Set `owner`'s block quota on this inode's filesystem — see
+    /// `EasyFileSystem::set_quota`. Exposed on `Inode` so callers that only
+    /// hold an `Arc<Inode>` (like `ROOT_INODE`) don't need their own handle
+    /// to the underlying `EasyFileSystem`.
+    pub fn set_quota(&self, owner: u32, blocks: u32) {
+        self.fs.lock().set_quota(owner, blocks);
+    }
+
+    /// This inode's logical inode number, the same `inode_id` used by
+    /// `EasyFileSystem::get_disk_inode_pos`. Suitable for `fstat`'s `ino`
+    /// field and stable across a remount, unlike its on-disk block position.
+    pub fn inode_id(&self) -> u32 {
+        self.inode_id
+    }
+
+    /// The `dev_id` this inode's filesystem was created/opened with,
+    /// suitable for `fstat`'s `dev` field.
+    pub fn dev_id(&self) -> u64 {
+        self.fs.lock().dev_id()
+    }
+
+    /// Number of directory entries referring to this inode on disk, i.e.
+    /// `DiskInode::nlink`.
+    pub fn hardlink_count(&self) -> u32 {
+        self.read_disk_inode(|disk_inode| disk_inode.nlink)
+    }
+
+    /// Number of live in-memory handles to this inode, i.e. how many open
+    /// files (and other `Arc<Inode>` holders, like a cached `parent`) are
+    /// currently pointing at it. Backed by `Arc::strong_count`, which this
+    /// reflects exactly because `EasyFileSystem::open_inodes` only ever
+    /// holds a `Weak` reference, never a strong one.
+    pub fn open_count(self: &Arc<Self>) -> usize {
+        Arc::strong_count(self)
+    }
+
+    /// Whether this inode is safe to reclaim: unreachable from any
+    /// directory entry (`hardlink_count() == 0`) and not currently open by
+    /// anyone but the caller (`open_count() <= 1`, the one being the
+    /// caller's own reference).
+    pub fn can_reclaim(self: &Arc<Self>) -> bool {
+        self.hardlink_count() == 0 && self.open_count() <= 1
+    }
+
+    /// Number of on-disk blocks actually allocated to this file, including
+    /// its indirect index blocks, suitable for `fstat`'s `blocks` field.
+    /// Less than `DiskInode::total_blocks` of the file's logical size once
+    /// `punch_hole` has freed some of it.
+    pub fn blocks_used(&self) -> u32 {
+        self.read_disk_inode(|disk_inode| disk_inode.blocks_used(&self.block_device))
+    }
+
+    /// Free every data block fully covered by `[offset, offset + len)`,
+    /// returning the freed space to the data bitmap. `size` is left
+    /// unchanged; a read anywhere in the punched range comes back as
+    /// zeros, same as a hole in any other sparse file. A block only
+    /// partially covered by the range keeps its allocation, with just its
+    /// covered bytes zeroed in place.
+    pub fn punch_hole(&self, offset: u32, len: u32) {
+        if self.is_read_only() {
+            return;
+        }
+        self.flush_write_buf();
+        let owner = self.owner();
+        let mut fs = self.fs.lock();
+        let freed = self
+            .modify_disk_inode(|disk_inode| disk_inode.punch_hole(offset, len, &self.block_device));
+        for block_id in freed {
+            fs.dealloc_data(owner, block_id);
+        }
+        *self.meta_dirty.lock() = true;
+        block_cache_sync_all();
+    }
+
+    /// Detect a corrupted block chain: any direct/indirect1/indirect2
+    /// pointer landing outside the filesystem's data area, or any block id
+    /// referenced more than once by this inode's own metadata. Intended
+    /// for an fsck-style consistency check rather than the ordinary read
+    /// path — `open` doesn't call this itself, since a corrupted chain is
+    /// the exception rather than something worth paying to rule out on
+    /// every open.
+    pub fn verify_chain(&self) -> bool {
+        let fs = self.fs.lock();
+        let (start, end) = fs.data_block_range();
+        self.read_disk_inode(|disk_inode| {
+            disk_inode.verify_chain(&self.block_device, |id| id >= start && id < end)
+        })
+    }
+
+    /// Look up a file by name in this directory.
+    pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
+        let inode_id = {
+            let _fs = self.fs.lock();
+            self.read_disk_inode(|disk_inode| self.find_inode_id(name, disk_inode))
+        };
+        inode_id.map(|inode_id| {
+            EasyFileSystem::get_inode(&self.fs, inode_id, self.block_device.clone())
+        })
+    }
+
+    /// Grow `disk_inode` to `new_size`, allocating data blocks as needed.
+    /// Returns `false` without growing the inode if the disk runs out of
+    /// free blocks partway through.
+    fn increase_size(
+        &self,
+        new_size: u32,
+        disk_inode: &mut DiskInode,
+        fs: &mut MutexGuard<EasyFileSystem>,
+    ) -> bool {
+        if new_size < disk_inode.size {
+            return true;
+        }
+        let owner = disk_inode.owner;
+        let blocks_needed = disk_inode.blocks_num_needed(new_size);
+        let mut hint = (disk_inode.data_blocks() > 0)
+            .then(|| disk_inode.get_block_id(disk_inode.data_blocks() - 1, &self.block_device));
+        let mut v: Vec<u32> = Vec::new();
+        for _ in 0..blocks_needed {
+            match fs.alloc_data(owner, hint) {
+                Some(block_id) => {
+                    hint = Some(block_id);
+                    v.push(block_id);
+                }
+                None => {
+                    for block_id in v {
+                        fs.dealloc_data(owner, block_id);
+                    }
+                    return false;
+                }
+            }
+        }
+        if !disk_inode.increase_size(new_size, v, &self.block_device) {
+            return false;
+        }
+        *self.meta_dirty.lock() = true;
+        true
+    }
+
+    /// Create a new regular file in this directory. Fails with
+    /// `FsError::Exists` if a file by that name already exists,
+    /// `FsError::NoSpace` if the disk has no room left for a new inode or
+    /// directory entry, or `FsError::TooLong` if `name` won't fit in a
+    /// directory entry.
+    pub fn create(&self, name: &str) -> Result<Arc<Inode>, FsError> {
+        self.create_typed(name, DiskInodeType::File)
+    }
+
+    /// Like `create`, but for a FIFO (named pipe) entry.
+    pub fn create_fifo(&self, name: &str) -> Result<Arc<Inode>, FsError> {
+        self.create_typed(name, DiskInodeType::Fifo)
+    }
+
+    fn create_typed(&self, name: &str, type_: DiskInodeType) -> Result<Arc<Inode>, FsError> {
+        if name.len() > NAME_LENGTH_LIMIT {
+            return Err(FsError::TooLong);
+        }
+        let mut fs = self.fs.lock();
+        let op = |root_inode: &DiskInode| {
+            assert!(root_inode.is_dir());
+            self.find_inode_id(name, root_inode)
+        };
+        if self.read_disk_inode(op).is_some() {
+            return Err(FsError::Exists);
+        }
+        let new_inode_id = fs.alloc_inode().ok_or(FsError::NoSpace)?;
+        let (new_inode_block_id, new_inode_block_offset) = fs.get_disk_inode_pos(new_inode_id);
+        get_block_cache(new_inode_block_id as usize, Arc::clone(&self.block_device))
+            .lock()
+            .modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
+                new_inode.initialize(type_);
+            });
+        let grown = self.modify_disk_inode(|root_inode| {
+            let file_count = (root_inode.size as usize) / DIRENT_SZ;
+            let new_size = (file_count + 1) * DIRENT_SZ;
+            if !self.increase_size(new_size as u32, root_inode, &mut fs) {
+                return false;
+            }
+            let dirent = DirEntry::new(name, new_inode_id);
+            root_inode.write_at(
+                file_count * DIRENT_SZ,
+                dirent.as_bytes(),
+                &self.block_device,
+            );
+            true
+        });
+        if !grown {
+            fs.dealloc_inode(new_inode_id);
+            return Err(FsError::NoSpace);
+        }
+        self.invalidate_dir_index();
+        drop(fs);
+        block_cache_sync_all();
+        self.fs.lock().notify(FsEvent {
+            inode_id: new_inode_id,
+            kind: FsEventKind::Create,
+        });
+        Ok(EasyFileSystem::get_inode(
+            &self.fs,
+            new_inode_id,
+            self.block_device.clone(),
+        ))
+    }
+
+    /// List the names of every entry in this directory.
+    pub fn ls(&self) -> Vec<String> {
+        self.ls_with_kind()
+            .into_iter()
+            .map(|(name, _is_dir)| name)
+            .collect()
+    }
+
+    /// Like `ls`, but paired with whether each entry is itself a
+    /// directory, for `OSInode::next_dirents`' type filter. `DirEntry`
+    /// doesn't carry a type byte of its own, so this costs one extra inode
+    /// lookup per entry (by the inode number already in hand, not a
+    /// name-based `find`) beyond what `ls` does.
+    pub fn ls_with_kind(&self) -> Vec<(String, bool)> {
+        let entries = {
+            let _fs = self.fs.lock();
+            self.read_disk_inode(|disk_inode| {
+                let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+                let mut v = Vec::new();
+                for i in 0..file_count {
+                    let mut dirent = DirEntry::empty();
+                    assert_eq!(
+                        disk_inode.read_at(
+                            i * DIRENT_SZ,
+                            dirent.as_bytes_mut(),
+                            &self.block_device,
+                        ),
+                        DIRENT_SZ,
+                    );
+                    if let Some(name) = dirent.name() {
+                        v.push((String::from(name), dirent.inode_number()));
+                    }
+                }
+                v
+            })
+        };
+        entries
+            .into_iter()
+            .map(|(name, inode_number)| {
+                let is_dir =
+                    EasyFileSystem::get_inode(&self.fs, inode_number, self.block_device.clone())
+                        .is_dir();
+                (name, is_dir)
+            })
+            .collect()
+    }
+
+    /// Recursively visit every entry reachable from this directory,
+    /// calling `f(path, inode)` for each one — files and subdirectories
+    /// alike — with its path relative to this directory, joined with
+    /// `/`. Skips `.`/`..` defensively, though nothing in this
+    /// filesystem's `create`/`create_fifo` ever writes them. Safe against
+    /// a cycle (there's no real symlink support yet to form one) by
+    /// tracking visited inode ids, and against runaway depth via
+    /// `WALK_MAX_DEPTH`; either one silently stops descending rather than
+    /// visiting twice or overflowing the stack. A no-op if `self` isn't a
+    /// directory.
+    pub fn walk(&self, f: &mut impl FnMut(&str, &Arc<Inode>)) {
+        if !self.is_dir() {
+            return;
+        }
+        let mut visited = BTreeSet::new();
+        visited.insert(self.inode_id);
+        self.walk_inner("", &mut visited, 0, f);
+    }
+
+    fn walk_inner(
+        &self,
+        prefix: &str,
+        visited: &mut BTreeSet<u32>,
+        depth: usize,
+        f: &mut impl FnMut(&str, &Arc<Inode>),
+    ) {
+        if depth >= WALK_MAX_DEPTH {
+            return;
+        }
+        for (name, is_dir) in self.ls_with_kind() {
+            if name == "." || name == ".." {
+                continue;
+            }
+            let Some(child) = self.find(&name) else {
+                continue;
+            };
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            f(&path, &child);
+            if is_dir && visited.insert(child.inode_id) {
+                child.walk_inner(&path, visited, depth + 1, f);
+            }
+        }
+    }
+
+    pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        self.flush_write_buf();
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.read_at(offset, buf, &self.block_device))
+    }
+
+    /// Read this file's entire contents into a freshly allocated `Vec`.
+    /// Meant for a caller that needs the whole thing at once (e.g. loading
+    /// an ELF to exec), not as a general-purpose read path.
+    pub fn read_all(&self) -> Vec<u8> {
+        let mut offset = 0;
+        let mut buffer = [0u8; 512];
+        let mut v = Vec::new();
+        loop {
+            let len = self.read_at(offset, &mut buffer);
+            if len == 0 {
+                break;
+            }
+            offset += len;
+            v.extend_from_slice(&buffer[..len]);
+        }
+        v
+    }
+
+    /// Write `buf` at `offset`, growing the file if needed. Returns the
+    /// number of bytes actually written, which is less than `buf.len()` if
+    /// the disk ran out of space while growing the file.
+    ///
+    /// Small sequential writes are combined in `write_buf` rather than
+    /// immediately dirtying a block, so that e.g. a line-at-a-time append
+    /// doesn't sync a block per call; the buffer is flushed once it grows
+    /// past `WRITE_COMBINE_LIMIT` or a non-sequential access occurs.
+    ///
+    /// If an earlier call's buffered data failed to reach disk on a later
+    /// deferred flush (see `write_buf_error`), that failure is reported
+    /// here, as a short write of 0, before this call's own data is touched.
+    pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
+        if self.is_read_only() {
+            return 0;
+        }
+        if core::mem::take(&mut *self.write_buf_error.lock()) {
+            return 0;
+        }
+        let written = self.write_at_inner(offset, buf);
+        if written > 0 {
+            self.fs.lock().notify(FsEvent {
+                inode_id: self.inode_id,
+                kind: FsEventKind::Write,
+            });
+        }
+        written
+    }
+
+    fn write_at_inner(&self, offset: usize, buf: &[u8]) -> usize {
+        let mut write_buf = self.write_buf.lock();
+        if let Some(pending) = write_buf.as_mut() {
+            if pending.offset + pending.data.len() == offset
+                && pending.data.len() + buf.len() <= WRITE_COMBINE_LIMIT
+            {
+                pending.data.extend_from_slice(buf);
+                return buf.len();
+            }
+        }
+        let flushed = write_buf.take();
+        if buf.len() <= WRITE_COMBINE_LIMIT {
+            *write_buf = Some(AppendBuffer {
+                offset,
+                data: Vec::from(buf),
+            });
+            drop(write_buf);
+            self.flush_pending(flushed);
+            buf.len()
+        } else {
+            drop(write_buf);
+            self.flush_pending(flushed);
+            self.write_at_immediate(offset, buf)
+        }
+    }
+
+    /// Flush a buffer evicted from `write_buf`, marking `write_buf_error`
+    /// sticky if it doesn't fully reach disk — its caller already reported
+    /// it written, so there's no one left to hand a short count to.
+    fn flush_pending(&self, pending: Option<AppendBuffer>) {
+        if let Some(pending) = pending {
+            let written = self.write_at_immediate(pending.offset, &pending.data);
+            if written < pending.data.len() {
+                *self.write_buf_error.lock() = true;
+            }
+        }
+    }
+
+    /// Flush any data buffered by `write_at`'s write-combining to disk.
+    fn flush_write_buf(&self) {
+        let pending = self.write_buf.lock().take();
+        self.flush_pending(pending);
+    }
+
+    fn write_at_immediate(&self, offset: usize, buf: &[u8]) -> usize {
+        let mut fs = self.fs.lock();
+        let size = self.modify_disk_inode(|disk_inode| {
+            if !self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs) {
+                return 0;
+            }
+            self.materialize_holes(disk_inode, &mut fs, offset, buf.len());
+            disk_inode.write_at(offset, buf, &self.block_device)
+        });
+        block_cache_sync_all();
+        size
+    }
+
+    /// Replace any hole `punch_hole` left in `[offset, offset + len)` with
+    /// a freshly allocated, already-zeroed block before writing into it, so
+    /// `DiskInode::write_at` never mistakes a hole's 0 pointer for a real
+    /// block id (block 0 is the superblock).
+    fn materialize_holes(
+        &self,
+        disk_inode: &mut DiskInode,
+        fs: &mut MutexGuard<EasyFileSystem>,
+        offset: usize,
+        len: usize,
+    ) {
+        if disk_inode.is_inline() || len == 0 {
+            return;
+        }
+        let end = (offset + len).min(disk_inode.size as usize);
+        if offset >= end {
+            return;
+        }
+        let first_block = (offset / BLOCK_SZ) as u32;
+        let last_block = ((end - 1) / BLOCK_SZ) as u32;
+        for inner_id in first_block..=last_block {
+            if disk_inode.get_block_id(inner_id, &self.block_device) == 0 {
+                let new_block = fs
+                    .alloc_data(disk_inode.owner, None)
+                    .expect("no space left to fill in a punched hole");
+                disk_inode.set_block_id(inner_id, new_block, &self.block_device);
+            }
+        }
+    }
+
+    /// Copy `len` bytes starting at `src_offset` in `self` to `dst_offset`
+    /// in `dst`, growing `dst` as needed. Used to split a file in two
+    /// without routing the data back through userspace. Returns the number
+    /// of bytes actually copied, which is less than `len` if either file
+    /// ran out of space to read from or grow into.
+    pub fn clone_range(
+        &self,
+        src_offset: usize,
+        dst: &Inode,
+        dst_offset: usize,
+        len: usize,
+    ) -> usize {
+        let mut buf = [0u8; 512];
+        let mut copied = 0;
+        while copied < len {
+            let chunk = (len - copied).min(buf.len());
+            let read = self.read_at(src_offset + copied, &mut buf[..chunk]);
+            if read == 0 {
+                break;
+            }
+            let written = dst.write_at(dst_offset + copied, &buf[..read]);
+            copied += written;
+            if written < read {
+                break;
+            }
+        }
+        copied
+    }
+
+    /// Flush only the cached blocks backing `[offset, offset + len)`,
+    /// rather than every dirty block in the cache. Lets a caller checkpoint
+    /// part of a large file cheaply.
+    pub fn sync_range(&self, offset: usize, len: usize) {
+        self.flush_write_buf();
+        let _fs = self.fs.lock();
+        if len == 0 {
+            return;
+        }
+        let start_block = offset / BLOCK_SZ;
+        let end_block = (offset + len - 1) / BLOCK_SZ;
+        let block_ids: Vec<usize> = self.read_disk_inode(|disk_inode| {
+            (start_block..=end_block)
+                .map(|inner_id| {
+                    disk_inode.get_block_id(inner_id as u32, &self.block_device) as usize
+                })
+                .collect()
+        });
+        flush_block_range(&block_ids);
+    }
+
+    pub fn clear(&self) {
+        if self.is_read_only() {
+            return;
+        }
+        self.flush_write_buf();
+        let owner = self.owner();
+        let mut fs = self.fs.lock();
+        self.modify_disk_inode(|disk_inode| {
+            let was_inline = disk_inode.is_inline();
+            let expected = if was_inline {
+                0
+            } else {
+                disk_inode.blocks_used(&self.block_device) as usize
+            };
+            let data_blocks_dealloc = disk_inode.clear_size(&self.block_device);
+            assert!(data_blocks_dealloc.len() == expected);
+            for data_block in data_blocks_dealloc.into_iter() {
+                fs.dealloc_data(owner, data_block);
+            }
+        });
+        *self.meta_dirty.lock() = true;
+        block_cache_sync_all();
+    }
+
+    /// Block ids of every data block currently in use, in file order,
+    /// excluding any hole `punch_hole` has left in the range (there's
+    /// nothing there to flush).
+    fn data_block_ids(&self, disk_inode: &DiskInode) -> Vec<usize> {
+        if disk_inode.is_inline() {
+            return Vec::new();
+        }
+        let data_blocks = (disk_inode.size as usize).div_ceil(BLOCK_SZ) as u32;
+        (0..data_blocks)
+            .map(|inner_id| disk_inode.get_block_id(inner_id, &self.block_device) as usize)
+            .filter(|&id| id != 0)
+            .collect()
+    }
+
+    /// Flush this file's data and inode metadata to disk. Returns `false`
+    /// if a write combined earlier by `write_buf` (this call's own flush or
+    /// an already-pending one) never actually made it to disk.
+    pub fn fsync(&self) -> bool {
+        self.flush_write_buf();
+        let _fs = self.fs.lock();
+        let block_ids = self.read_disk_inode(|disk_inode| self.data_block_ids(disk_inode));
+        flush_block_range(&block_ids);
+        flush_block_range(&[self.block_id]);
+        *self.meta_dirty.lock() = false;
+        !core::mem::take(&mut *self.write_buf_error.lock())
+    }
+
+    /// Like `fsync`, but skips rewriting the inode's metadata block unless
+    /// `size` or a block pointer actually changed since it was last
+    /// flushed — cheaper when only non-essential metadata is dirty. Returns
+    /// `false` under the same condition as `fsync`.
+    pub fn fdatasync(&self) -> bool {
+        self.flush_write_buf();
+        let _fs = self.fs.lock();
+        let block_ids = self.read_disk_inode(|disk_inode| self.data_block_ids(disk_inode));
+        flush_block_range(&block_ids);
+        let mut meta_dirty = self.meta_dirty.lock();
+        if *meta_dirty {
+            flush_block_range(&[self.block_id]);
+            *meta_dirty = false;
+        }
+        drop(meta_dirty);
+        !core::mem::take(&mut *self.write_buf_error.lock())
+    }
+
+    /// Flush only this inode's own metadata block, leaving its data blocks
+    /// untouched, so a caller can control the order metadata and data
+    /// durability land in relative to each other — e.g. flush data first,
+    /// confirm it's durable, then call this to make the metadata pointing
+    /// at it durable too, rather than the two racing each other. Unlike
+    /// `fsync`/`fdatasync`, this deliberately does not flush the pending
+    /// write-combine buffer first, since doing so writes data; a caller
+    /// with a buffered write still pending should flush it themselves
+    /// (e.g. via `fsync`/`fdatasync`, or `read_at`) before relying on this
+    /// for metadata alone.
+    pub fn sync_metadata(&self) {
+        let _fs = self.fs.lock();
+        flush_block_range(&[self.block_id]);
+        *self.meta_dirty.lock() = false;
+    }
+
+    /// Relocate this file's data blocks into one contiguous run, improving
+    /// sequential read performance for a file that's fragmented from being
+    /// freed and reallocated piecemeal over its lifetime. Returns `false`
+    /// without changing anything if there's no free run long enough, the
+    /// file is inline/empty/already contiguous, or it has a hole
+    /// `punch_hole` left in it (nothing to relocate a hole into, and
+    /// keeping track of which leaf entries must stay 0 isn't worth it for
+    /// what's meant to be an occasional offline cleanup).
+    ///
+    /// Every block is copied into its new home and every pointer is
+    /// repointed at it — and only then are the old blocks freed — so a
+    /// crash partway through leaves some blocks moved and some not, but
+    /// never loses data or aliases a block between the old and new layout.
+    pub fn defragment(&self) -> bool {
+        if self.is_read_only() {
+            return false;
+        }
+        self.flush_write_buf();
+        let owner = self.owner();
+        let mut fs = self.fs.lock();
+        let old_blocks: Vec<u32> = self.read_disk_inode(|disk_inode| {
+            (0..disk_inode.data_blocks())
+                .map(|inner_id| disk_inode.get_block_id(inner_id, &self.block_device))
+                .collect()
+        });
+        if old_blocks.is_empty() || old_blocks.contains(&0) {
+            return false;
+        }
+        let already_contiguous = old_blocks.windows(2).all(|w| w[1] == w[0] + 1);
+        if already_contiguous {
+            return false;
+        }
+        let Some(new_blocks) = fs.alloc_contiguous(owner, old_blocks.len() as u32) else {
+            return false;
+        };
+        for (&old_id, &new_id) in old_blocks.iter().zip(new_blocks.iter()) {
+            let data = get_block_cache(old_id as usize, Arc::clone(&self.block_device))
+                .lock()
+                .read(0, |block: &DataBlock| *block);
+            get_block_cache(new_id as usize, Arc::clone(&self.block_device))
+                .lock()
+                .modify(0, |block: &mut DataBlock| *block = data);
+        }
+        block_cache_sync_all();
+        self.modify_disk_inode(|disk_inode| {
+            for (inner_id, &new_id) in new_blocks.iter().enumerate() {
+                disk_inode.set_block_id(inner_id as u32, new_id, &self.block_device);
+            }
+        });
+        *self.meta_dirty.lock() = true;
+        block_cache_sync_all();
+        for old_id in old_blocks {
+            fs.dealloc_data(owner, old_id);
+        }
+        block_cache_sync_all();
+        true
+    }
+}
+
+impl Drop for Inode {
+    fn drop(&mut self) {
+        self.flush_write_buf();
+        self.fs.lock().forget_inode(self.inode_id);
+    }
+}
@@ -0,0 +1,54 @@
+//! Constants describing the memory layout and scheduling quanta.
+
+pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// Ceiling on how far a user stack may grow downward past its initial
+/// `USER_STACK_SIZE` via `MemorySet::handle_lazy_page_fault`'s demand
+/// paging, so a runaway recursion faults fatally instead of eating
+/// address space (and physical frames) without bound.
+pub const USER_STACK_MAX_SIZE: usize = 4096 * 16;
+pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
+pub const MEMORY_END: usize = 0x88000000;
+pub const PAGE_SIZE: usize = 0x1000;
+pub const PAGE_SIZE_BITS: usize = 0xc;
+pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
+pub const TRAP_CONTEXT_BASE: usize = TRAMPOLINE - PAGE_SIZE;
+pub const MMIO: &[(usize, usize)] = &[(0x10001000, 0x1000)];
+
+/// Number of CPUs this kernel schedules across. Currently always 1 (this
+/// is a single-hart build); `sys_sched_setaffinity`'s validation is
+/// written against this rather than hardcoding 1 so it doesn't need a
+/// second look if SMP support ever raises it.
+pub const NUM_CPUS: usize = 1;
+
+pub const CLOCK_FREQ: usize = 12500000;
+/// Length of one scheduling tick, in timer ticks.
+pub const BIG_STRIDE: usize = 100_000;
+
+/// Ceiling on the number of processes alive at once, enforced by
+/// `pid_alloc`. Keeps a fork bomb from growing `PidAllocator::current`
+/// (and the kernel stacks/address spaces that go with each pid) without
+/// bound until the kernel runs out of memory; `sys_fork`/`sys_clone`
+/// return -1 once it's reached instead of letting the allocation that
+/// would exceed it run.
+pub const MAX_PROCESS_COUNT: usize = 256;
+
+/// Ceiling on the number of fds a single task's `fd_table` may grow to,
+/// enforced by `TaskControlBlockInner::alloc_fd`. `sys_open`/`sys_pipe`/
+/// `sys_dup`/etc. return -1 once it's reached, or (if the task has set
+/// `ProcFlags::STRICT_RLIMIT` via `sys_prctl`) kill the task instead.
+pub const MAX_FD_COUNT: usize = 256;
+
+/// How often, in timer interrupts, the kernel checks for dirty blocks old
+/// enough to write back. See `easy_fs::writeback_stale`.
+pub const BLOCK_CACHE_WRITEBACK_INTERVAL_TICKS: usize = 100;
+/// How many easy-fs ticks (per `easy_fs::tick`, bumped once per timer
+/// interrupt) a block may sit dirty before the periodic writeback flushes
+/// it regardless of whether anything has explicitly synced it.
+pub const BLOCK_CACHE_WRITEBACK_MAX_AGE_TICKS: usize = 200;
+
+pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
+    let top = TRAMPOLINE - app_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let bottom = top - KERNEL_STACK_SIZE;
+    (bottom, top)
+}
@@ -0,0 +1,87 @@
+use crate::mm::{
+    frame_alloc, frame_dealloc, kernel_token, FrameTracker, PageTable, PhysAddr, PhysPageNum,
+    StepByOne, VirtAddr,
+};
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use easy_fs::BlockDevice;
+use lazy_static::*;
+use virtio_drivers::{VirtIOBlk, VirtIOHeader};
+
+#[allow(dead_code)]
+const VIRTIO0: usize = 0x10001000;
+
+pub struct VirtIOBlock(UPSafeCell<VirtIOBlk<'static>>);
+
+// `VirtIOBlk` only exposes single-block `read_block`/`write_block`, with no
+// batched multi-block request to hand a `read_blocks`/`write_blocks`
+// override down to, so `BlockCacheManager`'s cluster reads still cost one
+// virtio request per block here — clustering only saves the cache lookups
+// that would otherwise follow, not the device I/O itself. `FileBlockDevice`
+// (`easy-fs-fuse`) is the backend that actually batches the I/O.
+
+lazy_static! {
+    static ref QUEUE_FRAMES: UPSafeCell<Vec<FrameTracker>> = unsafe { UPSafeCell::new(Vec::new()) };
+}
+
+impl BlockDevice for VirtIOBlock {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        self.0
+            .exclusive_access()
+            .read_block(block_id, buf)
+            .expect("Error when reading VirtIOBlk");
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        self.0
+            .exclusive_access()
+            .write_block(block_id, buf)
+            .expect("Error when writing VirtIOBlk");
+    }
+}
+
+impl VirtIOBlock {
+    pub fn new() -> Self {
+        unsafe {
+            Self(UPSafeCell::new(
+                VirtIOBlk::new(&mut *(VIRTIO0 as *mut VirtIOHeader)).unwrap(),
+            ))
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn virtio_dma_alloc(pages: usize) -> PhysAddr {
+    let mut ppn_base = PhysPageNum(0);
+    for i in 0..pages {
+        let frame = frame_alloc().unwrap();
+        if i == 0 {
+            ppn_base = frame.ppn;
+        }
+        assert_eq!(frame.ppn.0, ppn_base.0 + i);
+        QUEUE_FRAMES.exclusive_access().push(frame);
+    }
+    ppn_base.into()
+}
+
+#[no_mangle]
+pub extern "C" fn virtio_dma_dealloc(pa: PhysAddr, pages: usize) -> i32 {
+    let mut ppn_base: PhysPageNum = pa.into();
+    for _ in 0..pages {
+        frame_dealloc(ppn_base);
+        ppn_base.step();
+    }
+    0
+}
+
+#[no_mangle]
+pub extern "C" fn virtio_phys_to_virt(paddr: PhysAddr) -> usize {
+    paddr.0
+}
+
+#[no_mangle]
+pub extern "C" fn virtio_virt_to_phys(vaddr: usize) -> PhysAddr {
+    PageTable::from_token(kernel_token())
+        .translate_va(VirtAddr::from(vaddr))
+        .unwrap()
+}
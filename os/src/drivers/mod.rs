@@ -0,0 +1,3 @@
+pub mod block;
+
+pub use block::BLOCK_DEVICE;
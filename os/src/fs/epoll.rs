@@ -0,0 +1,109 @@
+use super::File;
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+
+/// Register `fd` with `events` as a new interest. Fails (returns `false`
+/// from `EpollInstance::ctl`) if `fd` is already registered.
+pub const EPOLL_CTL_ADD: usize = 1;
+/// Change the interest events already registered for `fd`.
+pub const EPOLL_CTL_MOD: usize = 2;
+/// Drop `fd`'s registration.
+pub const EPOLL_CTL_DEL: usize = 3;
+
+/// There's data to read on a registered fd, per `File::poll_readable`.
+/// Shares its value with `POLLIN` since both describe the same condition.
+pub const EPOLLIN: i32 = 0x0001;
+
+/// One ready fd, filled in by `sys_epoll_wait`. Modeled on POSIX
+/// `epoll_event`, with `data` carrying back whatever the caller passed to
+/// `sys_epoll_ctl` when it registered `fd` (typically the fd itself).
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EpollEvent {
+    pub events: i32,
+    pub data: u64,
+}
+
+/// An epoll instance: a registration table of `(fd, events, data)` interest
+/// entries, installed into its owning task's `fd_table` like any other fd so
+/// `sys_epoll_ctl`/`sys_epoll_wait` can reach it through the same
+/// `fd_table.get(epfd)` lookup every other fd-taking syscall uses.
+///
+/// `sys_epoll_wait` still has to ask every registered fd's
+/// `File::poll_readable()` each time around its loop — this kernel has no
+/// wakeup-queue mechanism that could push readiness into the instance
+/// instead, so the scalability win over `sys_poll` is the registration step
+/// being paid once via `sys_epoll_ctl` rather than every call, not a move
+/// away from polling itself.
+pub struct EpollInstance {
+    registered: UPSafeCell<Vec<(i32, i32, u64)>>,
+}
+
+impl EpollInstance {
+    pub fn new() -> Self {
+        Self {
+            registered: unsafe { UPSafeCell::new(Vec::new()) },
+        }
+    }
+
+    /// Apply `op` (`EPOLL_CTL_ADD`/`MOD`/`DEL`) for `fd`. Returns whether it
+    /// took effect: `ADD` fails if `fd` is already registered, `MOD`/`DEL`
+    /// fail if it isn't.
+    pub fn ctl(&self, op: usize, fd: i32, events: i32, data: u64) -> bool {
+        let mut registered = self.registered.exclusive_access();
+        match op {
+            EPOLL_CTL_ADD => {
+                if registered.iter().any(|(f, _, _)| *f == fd) {
+                    return false;
+                }
+                registered.push((fd, events, data));
+                true
+            }
+            EPOLL_CTL_MOD => {
+                if let Some(entry) = registered.iter_mut().find(|(f, _, _)| *f == fd) {
+                    entry.1 = events;
+                    entry.2 = data;
+                    true
+                } else {
+                    false
+                }
+            }
+            EPOLL_CTL_DEL => {
+                let before = registered.len();
+                registered.retain(|(f, _, _)| *f != fd);
+                registered.len() != before
+            }
+            _ => false,
+        }
+    }
+}
+
+impl File for EpollInstance {
+    fn readable(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    /// An epoll instance isn't meant to be read via `sys_read` at all
+    /// (`sys_epoll_wait` is how its events are consumed); the 0 here is a
+    /// harmless no-op return, not a claim of EOF.
+    fn read(&self, _buf: UserBuffer) -> usize {
+        0
+    }
+
+    fn write(&self, _buf: UserBuffer) -> usize {
+        0
+    }
+
+    fn epoll_ctl(&self, op: usize, fd: i32, events: i32, data: u64) -> Option<bool> {
+        Some(self.ctl(op, fd, events, data))
+    }
+
+    fn epoll_registered(&self) -> Option<Vec<(i32, i32, u64)>> {
+        Some(self.registered.exclusive_access().clone())
+    }
+}
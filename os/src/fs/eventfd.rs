@@ -0,0 +1,80 @@
+use super::File;
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use crate::task::suspend_current_and_run_next;
+
+/// A lightweight cross-task signaling fd backed by a `u64` counter, modeled
+/// on Linux's `eventfd`. `write` adds an 8-byte little-endian value to the
+/// counter; `read` blocks until the counter is nonzero, then returns (and
+/// resets) it the same way. Readable exactly when the counter is nonzero,
+/// which is what `poll_readable` reports.
+pub struct EventFd {
+    counter: UPSafeCell<u64>,
+}
+
+impl EventFd {
+    pub fn new(initval: u64) -> Self {
+        Self {
+            counter: unsafe { UPSafeCell::new(initval) },
+        }
+    }
+}
+
+impl File for EventFd {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    /// Blocks until the counter is nonzero; there's no EOF condition on an
+    /// eventfd, so this only returns 0 for a zero-length `buf`.
+    fn read(&self, buf: UserBuffer) -> usize {
+        loop {
+            let mut counter = self.counter.exclusive_access();
+            if *counter == 0 {
+                drop(counter);
+                suspend_current_and_run_next();
+                continue;
+            }
+            let value = *counter;
+            *counter = 0;
+            drop(counter);
+            let bytes = value.to_le_bytes();
+            let mut written = 0;
+            let mut buf_iter = buf.into_iter();
+            for byte in bytes {
+                match buf_iter.next() {
+                    Some(dst) => {
+                        unsafe {
+                            *dst = byte;
+                        }
+                        written += 1;
+                    }
+                    None => break,
+                }
+            }
+            return written;
+        }
+    }
+
+    fn write(&self, buf: UserBuffer) -> usize {
+        let mut bytes = [0u8; 8];
+        let mut buf_iter = buf.into_iter();
+        for byte in bytes.iter_mut() {
+            match buf_iter.next() {
+                Some(src) => *byte = unsafe { *src },
+                None => break,
+            }
+        }
+        let add = u64::from_le_bytes(bytes);
+        *self.counter.exclusive_access() += add;
+        8
+    }
+
+    fn poll_readable(&self) -> bool {
+        *self.counter.exclusive_access() != 0
+    }
+}
@@ -0,0 +1,232 @@
+use super::File;
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use crate::task::suspend_current_and_run_next;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use lazy_static::lazy_static;
+
+const RING_BUFFER_SIZE: usize = 32;
+
+#[derive(Copy, Clone, PartialEq)]
+enum RingBufferStatus {
+    Full,
+    Empty,
+    Normal,
+}
+
+/// Like `PipeRingBuffer`, but the open ends aren't wired up at creation:
+/// any number of processes can `open_fifo_read`/`open_fifo_write` the same
+/// inode over time, so "is the other side closed" is a live count rather
+/// than a pair of `Weak` handles fixed at construction.
+struct FifoRingBuffer {
+    arr: [u8; RING_BUFFER_SIZE],
+    head: usize,
+    tail: usize,
+    status: RingBufferStatus,
+    readers: usize,
+    writers: usize,
+}
+
+impl FifoRingBuffer {
+    fn new() -> Self {
+        Self {
+            arr: [0; RING_BUFFER_SIZE],
+            head: 0,
+            tail: 0,
+            status: RingBufferStatus::Empty,
+            readers: 0,
+            writers: 0,
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.status = RingBufferStatus::Normal;
+        self.arr[self.tail] = byte;
+        self.tail = (self.tail + 1) % RING_BUFFER_SIZE;
+        if self.tail == self.head {
+            self.status = RingBufferStatus::Full;
+        }
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        self.status = RingBufferStatus::Normal;
+        let c = self.arr[self.head];
+        self.head = (self.head + 1) % RING_BUFFER_SIZE;
+        if self.head == self.tail {
+            self.status = RingBufferStatus::Empty;
+        }
+        c
+    }
+
+    fn available_read(&self) -> usize {
+        if self.status == RingBufferStatus::Empty {
+            0
+        } else if self.tail > self.head {
+            self.tail - self.head
+        } else {
+            self.tail + RING_BUFFER_SIZE - self.head
+        }
+    }
+
+    fn available_write(&self) -> usize {
+        if self.status == RingBufferStatus::Full {
+            0
+        } else {
+            RING_BUFFER_SIZE - self.available_read()
+        }
+    }
+}
+
+lazy_static! {
+    /// FIFO ring buffers by backing inode id, shared by every process that
+    /// opens the same FIFO path. Created on first open, and kept around
+    /// afterwards (bytes written while nobody's reading aren't lost, and a
+    /// fresh open shouldn't see a stale reader/writer count of zero reset
+    /// to a brand new buffer).
+    static ref FIFOS: UPSafeCell<BTreeMap<u32, Arc<UPSafeCell<FifoRingBuffer>>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+fn fifo_buffer(inode_id: u32) -> Arc<UPSafeCell<FifoRingBuffer>> {
+    FIFOS
+        .exclusive_access()
+        .entry(inode_id)
+        .or_insert_with(|| Arc::new(unsafe { UPSafeCell::new(FifoRingBuffer::new()) }))
+        .clone()
+}
+
+pub struct Fifo {
+    readable: bool,
+    writable: bool,
+    buffer: Arc<UPSafeCell<FifoRingBuffer>>,
+}
+
+impl Drop for Fifo {
+    fn drop(&mut self) {
+        let mut buffer = self.buffer.exclusive_access();
+        if self.readable {
+            buffer.readers -= 1;
+        }
+        if self.writable {
+            buffer.writers -= 1;
+        }
+    }
+}
+
+/// Open `inode_id`'s FIFO for reading. Blocks until a writer has opened it
+/// too (rendezvous semantics), so the first byte a reader sees was written
+/// after it started waiting rather than stale data from long ago.
+pub fn open_fifo_read(inode_id: u32) -> Arc<Fifo> {
+    let buffer = fifo_buffer(inode_id);
+    buffer.exclusive_access().readers += 1;
+    loop {
+        if buffer.exclusive_access().writers > 0 {
+            break;
+        }
+        suspend_current_and_run_next();
+    }
+    Arc::new(Fifo {
+        readable: true,
+        writable: false,
+        buffer,
+    })
+}
+
+/// Like `open_fifo_read`, but for writing; blocks until a reader has
+/// opened the same FIFO.
+pub fn open_fifo_write(inode_id: u32) -> Arc<Fifo> {
+    let buffer = fifo_buffer(inode_id);
+    buffer.exclusive_access().writers += 1;
+    loop {
+        if buffer.exclusive_access().readers > 0 {
+            break;
+        }
+        suspend_current_and_run_next();
+    }
+    Arc::new(Fifo {
+        readable: false,
+        writable: true,
+        buffer,
+    })
+}
+
+impl File for Fifo {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Same EOF contract as `Pipe::read`: blocks on an empty buffer while
+    /// any writer is still open, and returns 0 only once the buffer is
+    /// drained with `writers == 0`.
+    fn read(&self, buf: UserBuffer) -> usize {
+        assert!(self.readable);
+        let mut buf_iter = buf.into_iter();
+        let mut read_size = 0usize;
+        loop {
+            let mut buffer = self.buffer.exclusive_access();
+            let loop_read = buffer.available_read();
+            if loop_read == 0 {
+                if buffer.writers == 0 {
+                    return read_size;
+                }
+                drop(buffer);
+                suspend_current_and_run_next();
+                continue;
+            }
+            for _ in 0..loop_read {
+                if let Some(byte_ref) = buf_iter.next() {
+                    unsafe {
+                        *byte_ref = buffer.read_byte();
+                    }
+                    read_size += 1;
+                } else {
+                    return read_size;
+                }
+            }
+            return read_size;
+        }
+    }
+
+    /// Returns the number of bytes written, or -1 (EPIPE) if every reader
+    /// had closed its end before all of `buf` could be written.
+    fn write(&self, buf: UserBuffer) -> usize {
+        assert!(self.writable);
+        let mut buf_iter = buf.into_iter();
+        let mut write_size = 0usize;
+        loop {
+            let mut buffer = self.buffer.exclusive_access();
+            if buffer.readers == 0 {
+                return if write_size == 0 {
+                    (-1isize) as usize
+                } else {
+                    write_size
+                };
+            }
+            let loop_write = buffer.available_write();
+            if loop_write == 0 {
+                drop(buffer);
+                suspend_current_and_run_next();
+                continue;
+            }
+            for _ in 0..loop_write {
+                if let Some(byte_ref) = buf_iter.next() {
+                    buffer.write_byte(unsafe { *byte_ref });
+                    write_size += 1;
+                } else {
+                    return write_size;
+                }
+            }
+            return write_size;
+        }
+    }
+
+    fn poll_readable(&self) -> bool {
+        let buffer = self.buffer.exclusive_access();
+        buffer.available_read() > 0 || buffer.writers == 0
+    }
+}
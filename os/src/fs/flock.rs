@@ -0,0 +1,108 @@
+//! Advisory `flock(2)`-style locking, keyed by inode id rather than fd, so
+//! every fd a process has open on the same file contends for the same
+//! lock. Purely cooperative: nothing stops a task from reading or writing
+//! a locked file without going through `sys_flock` first, only callers
+//! that check in with `sys_flock` coordinate with each other.
+
+use crate::sync::UPSafeCell;
+use crate::task::suspend_current_and_run_next;
+use alloc::collections::{BTreeMap, BTreeSet};
+use lazy_static::*;
+
+pub const LOCK_SH: u32 = 1;
+pub const LOCK_EX: u32 = 2;
+pub const LOCK_UN: u32 = 8;
+pub const LOCK_NB: u32 = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+struct FlockState {
+    mode: LockMode,
+    /// pids currently holding this lock — more than one only under
+    /// `Shared`; `Exclusive` always has exactly one.
+    holders: BTreeSet<usize>,
+}
+
+lazy_static! {
+    static ref LOCKS: UPSafeCell<BTreeMap<u32, FlockState>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// Try to grant `mode` on `inode_id` to `pid`. A process already the sole
+/// holder may freely change its own mode (covers re-locking and
+/// upgrade/downgrade); otherwise `Shared` only succeeds against another
+/// `Shared` holder, and `Exclusive` never succeeds against any holder.
+fn try_acquire(inode_id: u32, pid: usize, mode: LockMode) -> bool {
+    let mut locks = LOCKS.exclusive_access();
+    match locks.get_mut(&inode_id) {
+        None => {
+            let mut holders = BTreeSet::new();
+            holders.insert(pid);
+            locks.insert(inode_id, FlockState { mode, holders });
+            true
+        }
+        Some(state) if state.holders.len() == 1 && state.holders.contains(&pid) => {
+            state.mode = mode;
+            true
+        }
+        Some(state) if mode == LockMode::Shared && state.mode == LockMode::Shared => {
+            state.holders.insert(pid);
+            true
+        }
+        Some(_) => false,
+    }
+}
+
+/// Drop `pid`'s hold on `inode_id`'s lock, if it has one. A no-op if `pid`
+/// isn't currently a holder.
+pub fn release(inode_id: u32, pid: usize) {
+    let mut locks = LOCKS.exclusive_access();
+    if let Some(state) = locks.get_mut(&inode_id) {
+        state.holders.remove(&pid);
+        if state.holders.is_empty() {
+            locks.remove(&inode_id);
+        }
+    }
+}
+
+/// Drop every lock `pid` holds, anywhere. Called when a task exits, since
+/// its fd table (and with it, every implicit lock release `sys_close`
+/// would otherwise have triggered) is about to be torn down at once.
+pub fn release_all_for_pid(pid: usize) {
+    let mut locks = LOCKS.exclusive_access();
+    locks.retain(|_, state| {
+        state.holders.remove(&pid);
+        !state.holders.is_empty()
+    });
+}
+
+/// `sys_flock`'s implementation. `op` is `LOCK_SH`/`LOCK_EX`/`LOCK_UN`,
+/// optionally `| LOCK_NB`. Blocks (unless `LOCK_NB`) until the lock can be
+/// granted. Returns 0 on success, -1 if `op` is invalid or (with
+/// `LOCK_NB`) the lock is already held incompatibly.
+pub fn flock(inode_id: u32, pid: usize, op: u32) -> isize {
+    if op & LOCK_UN != 0 {
+        release(inode_id, pid);
+        return 0;
+    }
+    let mode = if op & LOCK_EX != 0 {
+        LockMode::Exclusive
+    } else if op & LOCK_SH != 0 {
+        LockMode::Shared
+    } else {
+        return -1;
+    };
+    loop {
+        if try_acquire(inode_id, pid, mode) {
+            return 0;
+        }
+        if op & LOCK_NB != 0 {
+            return -1;
+        }
+        suspend_current_and_run_next();
+    }
+}
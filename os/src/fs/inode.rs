@@ -0,0 +1,302 @@
+use super::{DirentFilter, FdStats, File, Stat, StatMode};
+use crate::drivers::BLOCK_DEVICE;
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use easy_fs::{EasyFileSystem, Inode};
+use lazy_static::*;
+
+/// A kernel-side handle to an open file, tracking its own read/write
+/// cursor and permissions on top of the shared `easy-fs` `Inode`.
+pub struct OSInode {
+    readable: bool,
+    writable: bool,
+    inner: UPSafeCell<OSInodeInner>,
+}
+
+pub struct OSInodeInner {
+    offset: usize,
+    inode: Arc<Inode>,
+    /// This fd's own listing of a directory's entries, snapshotted the
+    /// first time `next_dirents` is called on it, plus a cursor into it.
+    /// Snapshotting once rather than re-listing the directory's live
+    /// entries on every call means a rename or unlink elsewhere during
+    /// iteration can't skip or duplicate an entry this fd hasn't reached
+    /// yet: names already returned stay returned, and names not yet
+    /// reached stay pending, regardless of what happens to the directory
+    /// in between. Paired with whether each entry is itself a directory,
+    /// for `next_dirents`' type filter. `None` until the first
+    /// `next_dirents` call.
+    dir_snapshot: Option<Vec<(String, bool)>>,
+    dir_cursor: usize,
+    /// Cumulative bytes moved through `read`/`write` on this fd, for
+    /// `sys_fd_stats`.
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl OSInode {
+    pub fn new(readable: bool, writable: bool, inode: Arc<Inode>) -> Self {
+        Self {
+            readable,
+            writable,
+            inner: unsafe {
+                UPSafeCell::new(OSInodeInner {
+                    offset: 0,
+                    inode,
+                    dir_snapshot: None,
+                    dir_cursor: 0,
+                    bytes_read: 0,
+                    bytes_written: 0,
+                })
+            },
+        }
+    }
+
+    pub fn read_all(&self) -> Vec<u8> {
+        self.inner.exclusive_access().inode.read_all()
+    }
+}
+
+lazy_static! {
+    pub static ref ROOT_INODE: Arc<Inode> = {
+        let efs = EasyFileSystem::open(BLOCK_DEVICE.clone(), 0);
+        EasyFileSystem::root_inode(&efs)
+    };
+}
+
+pub fn list_apps() {
+    println!("/**** APPS ****");
+    for app in ROOT_INODE.ls() {
+        println!("{}", app);
+    }
+    println!("**************/");
+}
+
+bitflags! {
+    pub struct OpenFlags: u32 {
+        const RDONLY = 0;
+        const WRONLY = 1 << 0;
+        const RDWR = 1 << 1;
+        const CREATE = 1 << 9;
+        const TRUNC = 1 << 10;
+        /// Fail if the resolved path exists and is not a directory.
+        const DIRECTORY = 1 << 11;
+        /// Combined with `CREATE`, fail instead of opening if the path already exists.
+        const EXCL = 1 << 12;
+        /// Open a handle to the path itself rather than its contents: the
+        /// resulting fd is neither readable nor writable, so `read`/`write`
+        /// always fail, but `fstat` still works off it (`sys_linkat` is not
+        /// implemented and always returns -1, so it does not). Useful
+        /// for tools that need to reference a path safely without being
+        /// able to touch what's in it.
+        const O_PATH = 1 << 13;
+        /// Close this fd automatically on `exec`, so a program that didn't
+        /// ask for it doesn't inherit a handle it has no business
+        /// holding. Enforced by `TaskControlBlock::exec`'s close-on-exec
+        /// sweep, not by anything here.
+        const CLOEXEC = 1 << 14;
+    }
+}
+
+impl OpenFlags {
+    /// Translate the flag bits into the (read, write) permissions the
+    /// resulting `OSInode` should be opened with.
+    pub(crate) fn read_write(&self) -> (bool, bool) {
+        if self.contains(Self::O_PATH) {
+            (false, false)
+        } else if self.is_empty() {
+            (true, false)
+        } else if self.contains(Self::WRONLY) {
+            (false, true)
+        } else {
+            (true, true)
+        }
+    }
+
+    /// Whether this is a sensible access mode: `WRONLY` and `RDWR` are
+    /// mutually exclusive, since each already implies write access and
+    /// together they'd just be a confusing way to ask for the same thing.
+    pub(crate) fn is_valid_access_mode(&self) -> bool {
+        !(self.contains(Self::WRONLY) && self.contains(Self::RDWR))
+    }
+}
+
+pub fn open_file(name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+    open_file_at(&ROOT_INODE, name, flags)
+}
+
+/// Like `open_file`, but resolves a relative `name` against `base` instead
+/// of always against `ROOT_INODE`. `sys_openat` uses this with the
+/// directory its `dirfd` refers to; `open_file` is just this with `base`
+/// fixed to the root.
+pub fn open_file_at(base: &Arc<Inode>, name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+    let (readable, writable) = flags.read_write();
+    if flags.contains(OpenFlags::CREATE) {
+        if let Some(inode) = Inode::find_path(base, name) {
+            if flags.contains(OpenFlags::EXCL) {
+                return None;
+            }
+            inode.clear();
+            Some(Arc::new(OSInode::new(readable, writable, inode)))
+        } else {
+            base.create(name)
+                .ok()
+                .map(|inode| Arc::new(OSInode::new(readable, writable, inode)))
+        }
+    } else {
+        Inode::find_path(base, name).and_then(|inode| {
+            if flags.contains(OpenFlags::DIRECTORY) && !inode.is_dir() {
+                return None;
+            }
+            if flags.contains(OpenFlags::TRUNC) {
+                inode.clear();
+            }
+            Some(Arc::new(OSInode::new(readable, writable, inode)))
+        })
+    }
+}
+
+impl File for OSInode {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Returns 0 only once `offset` has actually reached the file's end —
+    /// `Inode::read_at` returns 0 exactly there and nowhere else, so there's
+    /// no separate EOF check needed here. A read spanning the last few
+    /// bytes still returns the partial count it got (not 0); only the next
+    /// read, with `offset` past the end, sees the 0.
+    fn read(&self, mut buf: UserBuffer) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let mut total_read_size = 0usize;
+        for slice in buf.buffers.iter_mut() {
+            let read_size = inner.inode.read_at(inner.offset, slice);
+            if read_size == 0 {
+                break;
+            }
+            inner.offset += read_size;
+            total_read_size += read_size;
+        }
+        inner.bytes_read += total_read_size as u64;
+        total_read_size
+    }
+
+    /// Writes each of `buf`'s slices in turn, stopping at the first one
+    /// `Inode::write_at` doesn't fully satisfy (e.g. `FsError::NoSpace`
+    /// mid-write) rather than asserting it always does, and reports
+    /// whatever was actually written so far as a short write instead of
+    /// panicking the kernel over a full disk.
+    fn write(&self, buf: UserBuffer) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let mut total_write_size = 0usize;
+        for slice in buf.buffers.iter() {
+            let write_size = inner.inode.write_at(inner.offset, slice);
+            inner.offset += write_size;
+            total_write_size += write_size;
+            if write_size < slice.len() {
+                break;
+            }
+        }
+        inner.bytes_written += total_write_size as u64;
+        total_write_size
+    }
+
+    fn inode(&self) -> Option<Arc<Inode>> {
+        Some(self.inner.exclusive_access().inode.clone())
+    }
+
+    fn stat(&self) -> Stat {
+        read_stat(&self.inner.exclusive_access().inode)
+    }
+
+    fn next_dirents(&self, max_bytes: usize, filter: DirentFilter) -> Option<Vec<String>> {
+        let mut inner = self.inner.exclusive_access();
+        if !inner.inode.is_dir() {
+            return None;
+        }
+        if inner.dir_snapshot.is_none() {
+            inner.dir_snapshot = Some(inner.inode.ls_with_kind());
+        }
+        let snapshot = inner.dir_snapshot.as_ref().unwrap();
+        let mut cursor = inner.dir_cursor;
+        let mut used = 0usize;
+        let mut names = Vec::new();
+        while cursor < snapshot.len() {
+            let (name, is_dir) = &snapshot[cursor];
+            let matches = match filter {
+                DirentFilter::All => true,
+                DirentFilter::DirsOnly => *is_dir,
+                DirentFilter::FilesOnly => !is_dir,
+            };
+            if matches {
+                let needed = name.len() + 1;
+                if used + needed > max_bytes {
+                    break;
+                }
+                used += needed;
+                names.push(name.clone());
+            }
+            cursor += 1;
+        }
+        inner.dir_cursor = cursor;
+        Some(names)
+    }
+
+    fn io_stats(&self) -> FdStats {
+        let inner = self.inner.exclusive_access();
+        FdStats {
+            bytes_read: inner.bytes_read,
+            bytes_written: inner.bytes_written,
+        }
+    }
+
+    fn set_owner(&self, owner: u32) -> bool {
+        self.inner.exclusive_access().inode.set_owner(owner);
+        true
+    }
+}
+
+fn read_stat(inode: &Inode) -> Stat {
+    let mode = if inode.is_dir() {
+        StatMode::DIR
+    } else if inode.is_device() {
+        StatMode::CHAR
+    } else if inode.is_fifo() {
+        StatMode::FIFO
+    } else {
+        StatMode::FILE
+    };
+    Stat::new(
+        inode.dev_id(),
+        inode.inode_id() as u64,
+        mode,
+        1,
+        inode.blocks_used() as u64,
+        easy_fs::BLOCK_SZ as u32,
+    )
+}
+
+/// Resolve `path` to its backing `Inode`, if it exists.
+pub fn find_inode(path: &str) -> Option<Arc<Inode>> {
+    Inode::find_path(&ROOT_INODE, path)
+}
+
+/// Stat a file by path, without opening it (and so without an fd to close
+/// afterwards). Returns `None` if no such path exists.
+pub fn stat_path(path: &str) -> Option<Stat> {
+    find_inode(path).map(|inode| read_stat(&inode))
+}
+
+/// Create a FIFO (named pipe) at `path`. Returns `false` if an entry by
+/// that name already exists or the disk has no space left for it.
+pub fn mknod_fifo(path: &str) -> bool {
+    ROOT_INODE.create_fifo(path).is_ok()
+}
@@ -0,0 +1,8 @@
+/// One scatter-gather segment, passed to `sys_preadv`/`sys_pwritev`.
+/// Modeled on POSIX `struct iovec`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IoVec {
+    pub base: *const u8,
+    pub len: usize,
+}
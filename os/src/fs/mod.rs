@@ -0,0 +1,144 @@
+mod epoll;
+mod eventfd;
+mod fifo;
+mod flock;
+mod inode;
+mod iovec;
+mod pipe;
+mod poll;
+mod ramfile;
+mod stat;
+mod stdio;
+
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use alloc::sync::Arc;
+
+/// Which entries `File::next_dirents` should return, so a caller like a
+/// shell doing tab-completion on directory names only can skip stat-ing
+/// (or even transferring) entries it doesn't care about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirentFilter {
+    /// Every entry, regardless of kind — `sys_getdents`' existing
+    /// behavior.
+    All,
+    /// Only subdirectories.
+    DirsOnly,
+    /// Only non-directories (regular files, FIFOs, devices).
+    FilesOnly,
+}
+
+pub trait File: Send + Sync {
+    fn readable(&self) -> bool;
+    fn writable(&self) -> bool;
+    /// Read up to `buf.len()` bytes, returning how many were actually
+    /// read. `sys_read` passes this straight through as its return value,
+    /// so every impl needs to agree on what 0 means: a real end-of-stream
+    /// condition the caller can rely on, never a spurious placeholder for
+    /// "nothing right now" (that's what blocking and EAGAIN-style retries
+    /// are for). Concretely: `OSInode` returns 0 only once `Inode::read_at`
+    /// itself reaches the file's true end; `Pipe`/`Fifo` return 0 only once
+    /// their ring buffer is drained *and* every write end has closed,
+    /// otherwise they block. A zero-length `buf` is not handled uniformly:
+    /// `OSInode` and `RamFile` return 0 for it immediately ("0 bytes
+    /// requested," not an EOF signal), `Pipe`/`Fifo`/`EventFd` still block
+    /// until they have data (or, for pipes, every writer has closed), and
+    /// `Stdin` only accepts a one-byte `buf`.
+    fn read(&self, buf: UserBuffer) -> usize;
+    fn write(&self, buf: UserBuffer) -> usize;
+    /// The on-disk inode backing this file, if any. Used by `sys_mmap` to
+    /// set up a file-backed mapping; `Stdin`/`Stdout`/pipes have none.
+    fn inode(&self) -> Option<Arc<easy_fs::Inode>> {
+        None
+    }
+    /// Report this file's `fstat` information. Defaults to a device-less,
+    /// type-less stat for files that don't otherwise override it.
+    fn stat(&self) -> Stat {
+        Stat::new(0, 0, StatMode::NULL, 0, 0, 0)
+    }
+    /// Whether a `read` would return data (or EOF) right now, for
+    /// `sys_poll`. Defaults to true, since regular files and devices never
+    /// block on `read`; `Stdin` also keeps this default, although its
+    /// `read` blocks until a character arrives. Pipes and FIFOs override this to
+    /// check whether their ring buffer actually has bytes (or every
+    /// writer has gone away).
+    fn poll_readable(&self) -> bool {
+        true
+    }
+    /// This file's pipe ring buffer, if it's one end of a pipe. Lets
+    /// `sys_tee` peek at buffered bytes and duplicate them into another
+    /// pipe without going through a `read`/`write` pair that would consume
+    /// them. Defaults to `None` for every other kind of file.
+    fn pipe_buffer(&self) -> Option<Arc<UPSafeCell<PipeRingBuffer>>> {
+        None
+    }
+    /// Set (or, with `None`, clear) how long a blocked `write` will wait
+    /// for room before giving up, for `sys_set_pipe_write_timeout`.
+    /// Defaults to a no-op for every kind of file that doesn't block on
+    /// write in the first place; `Pipe` is the only override.
+    fn set_write_timeout_ms(&self, _timeout_ms: Option<usize>) {}
+    /// Up to the next names (matching `filter`) in this fd's directory
+    /// listing that fit (each counting its NUL terminator) within
+    /// `max_bytes`, advancing this fd's own cursor past every entry seen
+    /// — matching or not — so a later call with a different filter
+    /// doesn't re-see an entry this one skipped over. Defaults to `None`
+    /// for anything that isn't an open directory fd; `OSInode` is the
+    /// only override.
+    fn next_dirents(
+        &self,
+        _max_bytes: usize,
+        _filter: DirentFilter,
+    ) -> Option<alloc::vec::Vec<alloc::string::String>> {
+        None
+    }
+    /// If this file is an epoll instance, add/modify/remove `fd`'s
+    /// registration per `op` (`EPOLL_CTL_ADD`/`MOD`/`DEL`), returning
+    /// whether it took effect. Defaults to `None` for every other kind of
+    /// file; `EpollInstance` is the only override.
+    fn epoll_ctl(&self, _op: usize, _fd: i32, _events: i32, _data: u64) -> Option<bool> {
+        None
+    }
+    /// If this file is an epoll instance, its current `(fd, events, data)`
+    /// registrations, for `sys_epoll_wait` to poll. Defaults to `None`;
+    /// `EpollInstance` is the only override.
+    fn epoll_registered(&self) -> Option<alloc::vec::Vec<(i32, i32, u64)>> {
+        None
+    }
+    /// Cumulative bytes moved through `read`/`write` on this fd so far, for
+    /// `sys_fd_stats`. Defaults to all zeros for files that don't track it
+    /// (`Stdin`, `Stdout`, `Fifo`, `EventFd`, `EpollInstance`); `OSInode`,
+    /// `Pipe` and `RamFile` are the ones that actually count.
+    fn io_stats(&self) -> FdStats {
+        FdStats::default()
+    }
+    /// Resize this file to exactly `len` bytes, zero-filling any new
+    /// space, for `sys_ftruncate`. Defaults to unsupported (`false`);
+    /// `RamFile` is currently the only override.
+    fn ftruncate(&self, _len: usize) -> bool {
+        false
+    }
+    /// Reassign the `easy_fs::Inode` quota-tracking owner id backing this
+    /// file, for `sys_set_owner`. Defaults to unsupported (`false`) for
+    /// anything with no backing `Inode` to reassign; `OSInode` is
+    /// currently the only override.
+    fn set_owner(&self, _owner: u32) -> bool {
+        false
+    }
+}
+
+pub use epoll::{EpollEvent, EpollInstance, EPOLLIN, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD};
+pub use eventfd::EventFd;
+pub use fifo::{open_fifo_read, open_fifo_write, Fifo};
+pub use flock::{
+    flock, release_all_for_pid as release_flocks_for_pid, LOCK_EX, LOCK_NB, LOCK_SH, LOCK_UN,
+};
+pub use inode::{
+    find_inode, list_apps, mknod_fifo, open_file, open_file_at, stat_path, OSInode, OpenFlags,
+    ROOT_INODE,
+};
+pub use iovec::IoVec;
+pub use pipe::{make_pipe, Pipe, PipeRingBuffer};
+pub use poll::{PollFd, POLLIN};
+pub use ramfile::RamFile;
+pub use stat::{FdStats, Stat, StatMode};
+pub use stdio::{Stdin, Stdout};
@@ -0,0 +1,295 @@
+use super::{FdStats, File};
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use crate::task::suspend_current_and_run_next;
+use crate::timer::get_time_ms;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+/// Capacity `make_pipe` uses when the caller doesn't ask for a specific
+/// size.
+const RING_BUFFER_SIZE: usize = 32;
+
+#[derive(Copy, Clone, PartialEq)]
+enum RingBufferStatus {
+    Full,
+    Empty,
+    Normal,
+}
+
+pub struct PipeRingBuffer {
+    /// Backing storage; `arr.len()` is this ring buffer's capacity. All of
+    /// the wraparound arithmetic below is in terms of `arr.len()` rather
+    /// than a compile-time constant, so it's correct for any capacity
+    /// (including 1, and non-power-of-two sizes).
+    arr: Vec<u8>,
+    head: usize,
+    tail: usize,
+    status: RingBufferStatus,
+    write_end: Option<Weak<Pipe>>,
+    read_end: Option<Weak<Pipe>>,
+}
+
+impl PipeRingBuffer {
+    pub fn new() -> Self {
+        Self::with_capacity(RING_BUFFER_SIZE)
+    }
+
+    /// Like `new`, but with an explicit capacity rather than the default.
+    /// `capacity` must be at least 1.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            arr: alloc::vec![0; capacity],
+            head: 0,
+            tail: 0,
+            status: RingBufferStatus::Empty,
+            write_end: None,
+            read_end: None,
+        }
+    }
+
+    pub fn set_write_end(&mut self, write_end: &Arc<Pipe>) {
+        self.write_end = Some(Arc::downgrade(write_end));
+    }
+
+    pub fn set_read_end(&mut self, read_end: &Arc<Pipe>) {
+        self.read_end = Some(Arc::downgrade(read_end));
+    }
+
+    pub fn write_byte(&mut self, byte: u8) {
+        self.status = RingBufferStatus::Normal;
+        self.arr[self.tail] = byte;
+        self.tail = (self.tail + 1) % self.arr.len();
+        if self.tail == self.head {
+            self.status = RingBufferStatus::Full;
+        }
+    }
+
+    pub fn read_byte(&mut self) -> u8 {
+        self.status = RingBufferStatus::Normal;
+        let c = self.arr[self.head];
+        self.head = (self.head + 1) % self.arr.len();
+        if self.head == self.tail {
+            self.status = RingBufferStatus::Empty;
+        }
+        c
+    }
+
+    pub fn available_read(&self) -> usize {
+        if self.status == RingBufferStatus::Empty {
+            0
+        } else if self.tail > self.head {
+            self.tail - self.head
+        } else {
+            self.tail + self.arr.len() - self.head
+        }
+    }
+
+    /// This ring buffer's total capacity, regardless of how much is
+    /// currently buffered. Lets `sys_tee` size its peek buffer without
+    /// over-allocating for a caller-supplied `len` far larger than a pipe
+    /// could ever hold.
+    pub fn capacity(&self) -> usize {
+        self.arr.len()
+    }
+
+    pub fn available_write(&self) -> usize {
+        if self.status == RingBufferStatus::Full {
+            0
+        } else {
+            self.arr.len() - self.available_read()
+        }
+    }
+
+    pub fn all_write_ends_closed(&self) -> bool {
+        self.write_end.as_ref().unwrap().upgrade().is_none()
+    }
+
+    pub fn all_read_ends_closed(&self) -> bool {
+        self.read_end.as_ref().unwrap().upgrade().is_none()
+    }
+
+    /// Copy up to `out.len()` currently-buffered bytes into `out`, oldest
+    /// first, without consuming them — unlike `read_byte`, `head` doesn't
+    /// move. Used by `sys_tee` to duplicate data into a second pipe while
+    /// leaving it in place for this pipe's own reader.
+    pub fn peek(&self, out: &mut [u8]) -> usize {
+        let n = self.available_read().min(out.len());
+        for (i, byte) in out.iter_mut().enumerate().take(n) {
+            *byte = self.arr[(self.head + i) % self.arr.len()];
+        }
+        n
+    }
+}
+
+pub struct Pipe {
+    readable: bool,
+    writable: bool,
+    buffer: Arc<UPSafeCell<PipeRingBuffer>>,
+    /// How long, in ms, a `write` blocked on a full buffer will wait before
+    /// giving up and returning whatever it's written so far, if set via
+    /// `set_write_timeout_ms`. `None` (the default) blocks forever, as
+    /// before this was added.
+    write_timeout_ms: UPSafeCell<Option<usize>>,
+    /// Cumulative bytes moved through `read`/`write` on this end, for
+    /// `sys_fd_stats`.
+    stats: UPSafeCell<FdStats>,
+}
+
+impl Pipe {
+    pub fn read_end_with_buffer(buffer: Arc<UPSafeCell<PipeRingBuffer>>) -> Self {
+        Self {
+            readable: true,
+            writable: false,
+            buffer,
+            write_timeout_ms: unsafe { UPSafeCell::new(None) },
+            stats: unsafe { UPSafeCell::new(FdStats::default()) },
+        }
+    }
+
+    pub fn write_end_with_buffer(buffer: Arc<UPSafeCell<PipeRingBuffer>>) -> Self {
+        Self {
+            readable: false,
+            writable: true,
+            buffer,
+            write_timeout_ms: unsafe { UPSafeCell::new(None) },
+            stats: unsafe { UPSafeCell::new(FdStats::default()) },
+        }
+    }
+
+    /// Set (or, with `None`, clear) this pipe's write timeout. Only
+    /// meaningful on the write end; harmless but unobserved on the read
+    /// end, since nothing ever blocks there on buffer space.
+    pub fn set_write_timeout_ms(&self, timeout_ms: Option<usize>) {
+        *self.write_timeout_ms.exclusive_access() = timeout_ms;
+    }
+}
+
+/// Create a new pipe, returning (read end, write end).
+pub fn make_pipe() -> (Arc<Pipe>, Arc<Pipe>) {
+    let buffer = Arc::new(unsafe { UPSafeCell::new(PipeRingBuffer::new()) });
+    let read_end = Arc::new(Pipe::read_end_with_buffer(buffer.clone()));
+    let write_end = Arc::new(Pipe::write_end_with_buffer(buffer.clone()));
+    buffer.exclusive_access().set_write_end(&write_end);
+    buffer.exclusive_access().set_read_end(&read_end);
+    (read_end, write_end)
+}
+
+impl File for Pipe {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Blocks whenever the ring buffer is empty and some write end is still
+    /// open, so 0 is never returned for "nothing to read right now" — only
+    /// once the buffer is drained *and* `all_write_ends_closed`, the one
+    /// real EOF condition a pipe has.
+    fn read(&self, buf: UserBuffer) -> usize {
+        assert!(self.readable);
+        let mut buf_iter = buf.into_iter();
+        let mut read_size = 0usize;
+        loop {
+            let mut ring_buffer = self.buffer.exclusive_access();
+            let loop_read = ring_buffer.available_read();
+            if loop_read == 0 {
+                if ring_buffer.all_write_ends_closed() {
+                    return read_size;
+                }
+                drop(ring_buffer);
+                suspend_current_and_run_next();
+                continue;
+            }
+            for _ in 0..loop_read {
+                if let Some(byte_ref) = buf_iter.next() {
+                    unsafe {
+                        *byte_ref = ring_buffer.read_byte();
+                    }
+                    read_size += 1;
+                } else {
+                    self.stats.exclusive_access().bytes_read += read_size as u64;
+                    return read_size;
+                }
+            }
+            self.stats.exclusive_access().bytes_read += read_size as u64;
+            return read_size;
+        }
+    }
+
+    /// Returns the number of bytes written, or -1 (EPIPE or a timed-out
+    /// write that made no progress at all) if nothing could be written.
+    ///
+    /// If a write timeout is set (`set_write_timeout_ms`), a write that's
+    /// blocked on a full buffer past its deadline returns whatever it's
+    /// written so far instead of continuing to wait on a reader that may
+    /// never come. There's no wakeup/timer race to reconcile here: unlike
+    /// `MutexBlocking`'s wait queue, this loop doesn't block off-queue and
+    /// wait to be woken — it's a plain busy-poll that re-checks both the
+    /// buffer and the deadline every time it's scheduled back in.
+    fn write(&self, buf: UserBuffer) -> usize {
+        assert!(self.writable);
+        let deadline_ms = self
+            .write_timeout_ms
+            .exclusive_access()
+            .map(|timeout_ms| get_time_ms() + timeout_ms);
+        let mut buf_iter = buf.into_iter();
+        let mut write_size = 0usize;
+        loop {
+            let mut ring_buffer = self.buffer.exclusive_access();
+            if ring_buffer.all_read_ends_closed() {
+                self.stats.exclusive_access().bytes_written += write_size as u64;
+                return if write_size == 0 {
+                    (-1isize) as usize
+                } else {
+                    write_size
+                };
+            }
+            let loop_write = ring_buffer.available_write();
+            if loop_write == 0 {
+                drop(ring_buffer);
+                if deadline_ms.is_some_and(|deadline| get_time_ms() >= deadline) {
+                    self.stats.exclusive_access().bytes_written += write_size as u64;
+                    return if write_size == 0 {
+                        (-1isize) as usize
+                    } else {
+                        write_size
+                    };
+                }
+                suspend_current_and_run_next();
+                continue;
+            }
+            for _ in 0..loop_write {
+                if let Some(byte_ref) = buf_iter.next() {
+                    ring_buffer.write_byte(unsafe { *byte_ref });
+                    write_size += 1;
+                } else {
+                    self.stats.exclusive_access().bytes_written += write_size as u64;
+                    return write_size;
+                }
+            }
+            self.stats.exclusive_access().bytes_written += write_size as u64;
+            return write_size;
+        }
+    }
+
+    fn poll_readable(&self) -> bool {
+        let ring_buffer = self.buffer.exclusive_access();
+        ring_buffer.available_read() > 0 || ring_buffer.all_write_ends_closed()
+    }
+
+    fn pipe_buffer(&self) -> Option<Arc<UPSafeCell<PipeRingBuffer>>> {
+        Some(self.buffer.clone())
+    }
+
+    fn set_write_timeout_ms(&self, timeout_ms: Option<usize>) {
+        Pipe::set_write_timeout_ms(self, timeout_ms);
+    }
+
+    fn io_stats(&self) -> FdStats {
+        *self.stats.exclusive_access()
+    }
+}
@@ -0,0 +1,12 @@
+/// A file descriptor to watch, passed to `sys_poll`. Modeled on POSIX
+/// `pollfd`, though this kernel only ever sets `POLLIN` in `revents`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+
+/// There's data to read (or the fd has hit EOF/closed), per `File::poll_readable`.
+pub const POLLIN: i16 = 0x0001;
@@ -0,0 +1,104 @@
+use super::{FdStats, File, Stat, StatMode};
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+
+/// A file backed by RAM rather than `easy-fs`, for `sys_memfd_create`. It's
+/// unnamed in the directory tree — there's no `Inode` and nothing to
+/// unlink — and reclaimed as soon as the last fd referencing it (tracked
+/// the usual way, via its `Arc` refcount) closes, so it never touches a
+/// disk block. `read`/`write` share a single growable buffer and cursor
+/// the same way `OSInode` does; `write` past the current end grows the
+/// buffer instead of leaving a hole, since there's no sparse-file
+/// machinery here worth building for it.
+pub struct RamFile {
+    inner: UPSafeCell<RamFileInner>,
+}
+
+struct RamFileInner {
+    data: Vec<u8>,
+    offset: usize,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl RamFile {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(RamFileInner {
+                    data: Vec::new(),
+                    offset: 0,
+                    bytes_read: 0,
+                    bytes_written: 0,
+                })
+            },
+        }
+    }
+}
+
+impl File for RamFile {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    /// Same EOF contract as `OSInode::read`: 0 only once `offset` reaches
+    /// `data.len()`, never as a placeholder for "nothing buffered" — there's
+    /// no such state here, `data` always holds everything written so far.
+    fn read(&self, mut buf: UserBuffer) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let mut total_read_size = 0usize;
+        for slice in buf.buffers.iter_mut() {
+            let available = inner.data.len().saturating_sub(inner.offset);
+            let read_size = available.min(slice.len());
+            if read_size == 0 {
+                break;
+            }
+            slice[..read_size].copy_from_slice(&inner.data[inner.offset..inner.offset + read_size]);
+            inner.offset += read_size;
+            total_read_size += read_size;
+        }
+        inner.bytes_read += total_read_size as u64;
+        total_read_size
+    }
+
+    fn write(&self, buf: UserBuffer) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        let mut total_write_size = 0usize;
+        for slice in buf.buffers.iter() {
+            let end = inner.offset + slice.len();
+            if end > inner.data.len() {
+                inner.data.resize(end, 0);
+            }
+            inner.data[inner.offset..end].copy_from_slice(slice);
+            inner.offset = end;
+            total_write_size += slice.len();
+        }
+        inner.bytes_written += total_write_size as u64;
+        total_write_size
+    }
+
+    fn stat(&self) -> Stat {
+        // No backing disk, so no blocks are ever allocated for it,
+        // regardless of how much has been written — that's exactly what
+        // distinguishes a memfd from a regular file on `fstat`.
+        Stat::new(0, 0, StatMode::FILE, 1, 0, 0)
+    }
+
+    fn ftruncate(&self, len: usize) -> bool {
+        self.inner.exclusive_access().data.resize(len, 0);
+        true
+    }
+
+    fn io_stats(&self) -> FdStats {
+        let inner = self.inner.exclusive_access();
+        FdStats {
+            bytes_read: inner.bytes_read,
+            bytes_written: inner.bytes_written,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use bitflags::bitflags;
+
+/// The subset of `fstat(2)`'s `struct stat` this kernel reports.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Stat {
+    pub dev: u64,
+    pub ino: u64,
+    pub mode: StatMode,
+    pub nlink: u32,
+    /// Number of 512-byte blocks actually allocated to this file, as
+    /// `du` wants rather than the logical `size` a sparse file would
+    /// report.
+    pub blocks: u64,
+    /// The owning filesystem's block size in bytes.
+    pub blksize: u32,
+    pad: [u64; 5],
+}
+
+impl Stat {
+    pub fn new(dev: u64, ino: u64, mode: StatMode, nlink: u32, blocks: u64, blksize: u32) -> Self {
+        Self {
+            dev,
+            ino,
+            mode,
+            nlink,
+            blocks,
+            blksize,
+            pad: [0; 5],
+        }
+    }
+}
+
+/// Cumulative bytes moved through an fd's `read`/`write`, reported by
+/// `sys_fd_stats` so an I/O monitor can attribute bandwidth to specific
+/// files instead of just the syscall counts it already sees.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FdStats {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+bitflags! {
+    pub struct StatMode: u32 {
+        const NULL  = 0;
+        const DIR   = 0o040000;
+        const FILE  = 0o100000;
+        /// Character device, e.g. `Stdin`/`Stdout`.
+        const CHAR  = 0o020000;
+        /// Block device.
+        const BLOCK = 0o060000;
+        /// FIFO (named pipe).
+        const FIFO  = 0o010000;
+    }
+}
@@ -0,0 +1,17 @@
+use crate::sbi::shutdown;
+use core::panic::PanicInfo;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    if let Some(location) = info.location() {
+        println!(
+            "[kernel] Panicked at {}:{} {}",
+            location.file(),
+            location.line(),
+            info.message()
+        );
+    } else {
+        println!("[kernel] Panicked: {}", info.message());
+    }
+    shutdown()
+}
@@ -0,0 +1,538 @@
+use super::{
+    frame_alloc, FrameTracker, PTEFlags, PageTable, PageTableEntry, PhysAddr, PhysPageNum,
+    StepByOne, VirtAddr, VirtPageNum,
+};
+use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE};
+use crate::mm::address::VPNRange;
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use lazy_static::*;
+use riscv::register::satp;
+
+extern "C" {
+    fn stext();
+    fn etext();
+    fn srodata();
+    fn erodata();
+    fn sdata();
+    fn edata();
+    fn sbss_with_stack();
+    fn ebss();
+    fn ekernel();
+    fn strampoline();
+}
+
+lazy_static! {
+    pub static ref KERNEL_SPACE: Arc<UPSafeCell<MemorySet>> =
+        Arc::new(unsafe { UPSafeCell::new(MemorySet::new_kernel()) });
+}
+
+bitflags! {
+    pub struct MapPermission: u8 {
+        const R = 1 << 1;
+        const W = 1 << 2;
+        const X = 1 << 3;
+        const U = 1 << 4;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MapType {
+    Identical,
+    Framed,
+}
+
+/// How a lazily-mapped area's pages should be filled in when first
+/// touched, set by `sys_mmap`'s flags argument.
+#[derive(Clone)]
+pub enum MmapBacking {
+    /// Pages are zero-filled on first access.
+    Anonymous,
+    /// Pages are copied from this file, starting at the given byte offset
+    /// into the mapping, on first access.
+    File(Arc<easy_fs::Inode>, usize),
+}
+
+pub struct MapArea {
+    vpn_range: VPNRange,
+    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    map_type: MapType,
+    pub map_perm: MapPermission,
+    /// `Some` for an mmap area whose pages are mapped lazily on page
+    /// fault rather than eagerly when the area is created.
+    backing: Option<MmapBacking>,
+    /// `Some(floor)` for a user stack area that's allowed to grow
+    /// downward on a page fault just below its current bottom, down to
+    /// (but not below) `floor`. `None` for every other area.
+    growable_stack_floor: Option<VirtPageNum>,
+}
+
+impl MapArea {
+    pub fn new(
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        map_type: MapType,
+        map_perm: MapPermission,
+    ) -> Self {
+        let start_vpn = start_va.floor();
+        let end_vpn = end_va.ceil();
+        Self {
+            vpn_range: VPNRange::new(start_vpn, end_vpn),
+            data_frames: BTreeMap::new(),
+            map_type,
+            map_perm,
+            backing: None,
+            growable_stack_floor: None,
+        }
+    }
+
+    pub fn new_lazy(
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        map_perm: MapPermission,
+        backing: MmapBacking,
+    ) -> Self {
+        let start_vpn = start_va.floor();
+        let end_vpn = end_va.ceil();
+        Self {
+            vpn_range: VPNRange::new(start_vpn, end_vpn),
+            data_frames: BTreeMap::new(),
+            map_type: MapType::Framed,
+            map_perm,
+            backing: Some(backing),
+            growable_stack_floor: None,
+        }
+    }
+
+    pub fn from_another(another: &MapArea) -> Self {
+        Self {
+            vpn_range: VPNRange::new(another.vpn_range.get_start(), another.vpn_range.get_end()),
+            data_frames: BTreeMap::new(),
+            map_type: another.map_type,
+            map_perm: another.map_perm,
+            backing: another.backing.clone(),
+            growable_stack_floor: another.growable_stack_floor,
+        }
+    }
+
+    pub fn contains(&self, vpn: VirtPageNum) -> bool {
+        self.vpn_range.get_start().0 <= vpn.0 && vpn.0 < self.vpn_range.get_end().0
+    }
+
+    /// Map and fill in a single page of a lazily-backed area on first
+    /// access. Panics if this area has no backing or `vpn` is already
+    /// mapped.
+    pub fn fault_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        self.map_one(page_table, vpn);
+        if let Some(MmapBacking::File(inode, base_offset)) = &self.backing {
+            let page_offset = base_offset + (vpn.0 - self.vpn_range.get_start().0) * PAGE_SIZE;
+            let buf = page_table.translate(vpn).unwrap().ppn().get_bytes_array();
+            inode.read_at(page_offset, buf);
+        }
+    }
+
+    /// Mark this area as a growable user stack, allowed to extend
+    /// downward on demand as far as `floor` (inclusive).
+    pub fn set_growable_stack_floor(&mut self, floor: VirtPageNum) {
+        self.growable_stack_floor = Some(floor);
+    }
+
+    /// Extend this area downward by one page and map it, for a growable
+    /// stack's demand paging. Returns `false` without changing anything
+    /// if the new bottom page is already mapped — by another area, or
+    /// (defensively) by this one — rather than letting `map_one` panic
+    /// through `PageTable::map`'s own assertion.
+    pub fn extend_down_one(&mut self, page_table: &mut PageTable) -> bool {
+        let new_start = VirtPageNum(self.vpn_range.get_start().0 - 1);
+        if page_table
+            .translate(new_start)
+            .is_some_and(|pte| pte.is_valid())
+        {
+            return false;
+        }
+        self.vpn_range = VPNRange::new(new_start, self.vpn_range.get_end());
+        self.map_one(page_table, new_start);
+        true
+    }
+
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        let ppn: PhysPageNum;
+        match self.map_type {
+            MapType::Identical => {
+                ppn = PhysPageNum(vpn.0);
+            }
+            MapType::Framed => {
+                let frame = frame_alloc().unwrap();
+                ppn = frame.ppn;
+                self.data_frames.insert(vpn, frame);
+            }
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits()).unwrap();
+        page_table.map(vpn, ppn, pte_flags);
+    }
+
+    pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        if self.map_type == MapType::Framed {
+            let had_frame = self.data_frames.remove(&vpn).is_some();
+            // A lazily-backed page that was never faulted in has no frame
+            // and was never entered into the page table; skip it.
+            if self.backing.is_some() && !had_frame {
+                return;
+            }
+        }
+        page_table.unmap(vpn);
+    }
+
+    pub fn map(&mut self, page_table: &mut PageTable) {
+        for vpn in self.vpn_range {
+            self.map_one(page_table, vpn);
+        }
+    }
+
+    pub fn unmap(&mut self, page_table: &mut PageTable) {
+        for vpn in self.vpn_range {
+            self.unmap_one(page_table, vpn);
+        }
+    }
+
+    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8]) {
+        assert_eq!(self.map_type, MapType::Framed);
+        let mut start: usize = 0;
+        let mut current_vpn = self.vpn_range.get_start();
+        let len = data.len();
+        loop {
+            let src = &data[start..len.min(start + PAGE_SIZE)];
+            let dst = &mut page_table
+                .translate(current_vpn)
+                .unwrap()
+                .ppn()
+                .get_bytes_array()[..src.len()];
+            dst.copy_from_slice(src);
+            start += PAGE_SIZE;
+            if start >= len {
+                break;
+            }
+            current_vpn.step();
+        }
+    }
+}
+
+pub struct MemorySet {
+    page_table: PageTable,
+    areas: Vec<MapArea>,
+}
+
+impl MemorySet {
+    pub fn new_bare() -> Self {
+        Self {
+            page_table: PageTable::new(),
+            areas: Vec::new(),
+        }
+    }
+
+    pub fn token(&self) -> usize {
+        self.page_table.token()
+    }
+
+    /// Resident set size, in pages: the number of pages across every area
+    /// that actually have a physical frame backing them right now.
+    /// `MapArea::data_frames` already only holds an entry for a page once
+    /// it's been mapped — eagerly for non-lazy `Framed` areas,
+    /// lazily on first fault for an mmap area via `handle_lazy_page_fault`
+    /// — and loses it the moment `unmap_one` runs, so this is a live sum
+    /// rather than a separately maintained counter that could drift from
+    /// the page table. Does not include the kernel's own `Identical`
+    /// mapping, which bypasses `data_frames` entirely (see `map_one`).
+    pub fn rss_pages(&self) -> usize {
+        self.areas.iter().map(|area| area.data_frames.len()).sum()
+    }
+
+    /// Virtual size, in pages: the number of pages reserved across every
+    /// area, whether or not each has actually been faulted in yet. For a
+    /// lazy mmap area this counts the whole reservation, not just the
+    /// pages `rss_pages` would count as resident.
+    pub fn vsize_pages(&self) -> usize {
+        self.areas
+            .iter()
+            .map(|area| area.vpn_range.get_end().0 - area.vpn_range.get_start().0)
+            .sum()
+    }
+
+    pub fn insert_framed_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) {
+        self.push(
+            MapArea::new(start_va, end_va, MapType::Framed, permission),
+            None,
+        );
+    }
+
+    /// Reserve `[start_va, end_va)` for an mmap mapping. If `eager` is
+    /// `false` (the default), no frames are allocated up front; pages are
+    /// mapped and filled in lazily by `handle_lazy_page_fault` the first
+    /// time each is touched. If `eager` is `true` (set via
+    /// `sys_prctl(PR_SET_PROC_FLAGS, ProcFlags::MMAP_EAGER)`), every page
+    /// is mapped and filled in immediately instead.
+    pub fn insert_mmap_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+        backing: MmapBacking,
+        eager: bool,
+    ) {
+        let area = MapArea::new_lazy(start_va, end_va, permission, backing);
+        let vpn_range = area.vpn_range;
+        self.areas.push(area);
+        if eager {
+            let idx = self.areas.len() - 1;
+            for vpn in vpn_range {
+                self.areas[idx].fault_one(&mut self.page_table, vpn);
+            }
+        }
+    }
+
+    /// If `va` falls inside a lazily-backed area that hasn't been faulted
+    /// in yet, map and fill its page now and return `true`. If `va` is one
+    /// page below a growable stack's current bottom and still within its
+    /// floor, grow the stack by that page and return whether that worked.
+    /// Returns `false` otherwise, in which case the fault is a genuine
+    /// error.
+    pub fn handle_lazy_page_fault(&mut self, va: VirtAddr) -> bool {
+        let vpn = va.floor();
+        if self
+            .page_table
+            .translate(vpn)
+            .is_some_and(|pte| pte.is_valid())
+        {
+            return false;
+        }
+        if let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.backing.is_some() && area.contains(vpn))
+        {
+            area.fault_one(&mut self.page_table, vpn);
+            return true;
+        }
+        // A fault one page below a growable stack's current bottom, as
+        // long as that's still within its floor, grows the stack by one
+        // page rather than killing the task outright.
+        if let Some(area) = self.areas.iter_mut().find(|area| {
+            area.growable_stack_floor.is_some() && vpn.0 + 1 == area.vpn_range.get_start().0
+        }) {
+            if vpn.0 >= area.growable_stack_floor.unwrap().0 {
+                return area.extend_down_one(&mut self.page_table);
+            }
+        }
+        false
+    }
+
+    pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
+        if let Some((idx, area)) = self
+            .areas
+            .iter_mut()
+            .enumerate()
+            .find(|(_, area)| area.vpn_range.get_start() == start_vpn)
+        {
+            area.unmap(&mut self.page_table);
+            self.areas.remove(idx);
+        }
+    }
+
+    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
+        map_area.map(&mut self.page_table);
+        if let Some(data) = data {
+            map_area.copy_data(&mut self.page_table, data);
+        }
+        self.areas.push(map_area);
+    }
+
+    fn map_trampoline(&mut self) {
+        self.page_table.map(
+            VirtAddr::from(TRAMPOLINE).into(),
+            PhysAddr::from(strampoline as usize).into(),
+            PTEFlags::R | PTEFlags::X,
+        );
+    }
+
+    /// Map the kernel's own identity-mapped address space.
+    pub fn new_kernel() -> Self {
+        let mut memory_set = Self::new_bare();
+        memory_set.map_trampoline();
+        memory_set.push(
+            MapArea::new(
+                (stext as usize).into(),
+                (etext as usize).into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::X,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (srodata as usize).into(),
+                (erodata as usize).into(),
+                MapType::Identical,
+                MapPermission::R,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (sdata as usize).into(),
+                (edata as usize).into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (sbss_with_stack as usize).into(),
+                (ebss as usize).into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (ekernel as usize).into(),
+                MEMORY_END.into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        memory_set
+    }
+
+    /// Build an address space from an ELF image, returning it along with
+    /// the user stack top and entry point.
+    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
+        let mut memory_set = Self::new_bare();
+        memory_set.map_trampoline();
+        let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
+        let elf_header = elf.header;
+        let magic = elf_header.pt1.magic;
+        assert_eq!(magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf!");
+        let ph_count = elf_header.pt2.ph_count();
+        let mut max_end_vpn = VirtPageNum(0);
+        for i in 0..ph_count {
+            let ph = elf.program_header(i).unwrap();
+            if ph.get_type().unwrap() == xmas_elf::program::Type::Load {
+                let start_va: VirtAddr = (ph.virtual_addr() as usize).into();
+                let end_va: VirtAddr = ((ph.virtual_addr() + ph.mem_size()) as usize).into();
+                let mut map_perm = MapPermission::U;
+                let ph_flags = ph.flags();
+                if ph_flags.is_read() {
+                    map_perm |= MapPermission::R;
+                }
+                if ph_flags.is_write() {
+                    map_perm |= MapPermission::W;
+                }
+                if ph_flags.is_execute() {
+                    map_perm |= MapPermission::X;
+                }
+                let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
+                max_end_vpn = map_area.vpn_range.get_end();
+                memory_set.push(
+                    map_area,
+                    Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
+                );
+            }
+        }
+        let max_end_va: VirtAddr = max_end_vpn.into();
+        let mut user_stack_bottom: usize = max_end_va.into();
+        // Guard page, then room for the stack to grow all the way down to
+        // `USER_STACK_MAX_SIZE` without ever reaching back into the ELF
+        // segments above — the growable region below `user_stack_bottom`
+        // has to actually be free, not just arithmetically assumed to be.
+        user_stack_bottom += PAGE_SIZE;
+        user_stack_bottom += crate::config::USER_STACK_MAX_SIZE - crate::config::USER_STACK_SIZE;
+        let user_stack_top = user_stack_bottom + crate::config::USER_STACK_SIZE;
+        let user_stack_floor_va: VirtAddr =
+            (user_stack_top - crate::config::USER_STACK_MAX_SIZE).into();
+        let mut user_stack_area = MapArea::new(
+            user_stack_bottom.into(),
+            user_stack_top.into(),
+            MapType::Framed,
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        );
+        user_stack_area.set_growable_stack_floor(user_stack_floor_va.floor());
+        memory_set.push(user_stack_area, None);
+        memory_set.push(
+            MapArea::new(
+                user_stack_top.into(),
+                (user_stack_top + PAGE_SIZE).into(),
+                MapType::Framed,
+                MapPermission::R | MapPermission::W | MapPermission::U,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                crate::config::TRAP_CONTEXT_BASE.into(),
+                crate::config::TRAMPOLINE.into(),
+                MapType::Framed,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        (
+            memory_set,
+            user_stack_top,
+            elf.header.pt2.entry_point() as usize,
+        )
+    }
+
+    pub fn from_existing_user(user_space: &MemorySet) -> Self {
+        let mut memory_set = Self::new_bare();
+        memory_set.map_trampoline();
+        for area in user_space.areas.iter() {
+            let new_area = MapArea::from_another(area);
+            let lazy = area.backing.is_some();
+            if lazy {
+                // Copy only the pages the parent has actually faulted in;
+                // the rest stay unmapped and will fault for the child too.
+                memory_set.areas.push(new_area);
+            } else {
+                memory_set.push(new_area, None);
+            }
+            for vpn in area.vpn_range {
+                if lazy && !user_space.translate(vpn).is_some_and(|pte| pte.is_valid()) {
+                    continue;
+                }
+                if lazy {
+                    let area = memory_set.areas.last_mut().unwrap();
+                    area.map_one(&mut memory_set.page_table, vpn);
+                }
+                let src_ppn = user_space.translate(vpn).unwrap().ppn();
+                let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
+                dst_ppn
+                    .get_bytes_array()
+                    .copy_from_slice(src_ppn.get_bytes_array());
+            }
+        }
+        memory_set
+    }
+
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
+        self.page_table.translate(vpn)
+    }
+
+    pub fn activate(&self) {
+        let satp = self.page_table.token();
+        unsafe {
+            satp::write(satp);
+            core::arch::asm!("sfence.vma");
+        }
+    }
+}
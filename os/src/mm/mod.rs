@@ -0,0 +1,23 @@
+mod address;
+mod frame_allocator;
+mod heap_allocator;
+mod memory_set;
+mod page_table;
+
+pub use address::{PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+pub use frame_allocator::{frame_alloc, frame_dealloc, FrameTracker};
+pub use memory_set::{MapPermission, MemorySet, MmapBacking, KERNEL_SPACE};
+pub use page_table::{
+    read_user, translated_byte_buffer, translated_ref, translated_refmut, translated_str,
+    try_translated_byte_buffer, write_user, PTEFlags, PageTable, PageTableEntry, UserBuffer,
+};
+
+pub fn init() {
+    heap_allocator::init_heap();
+    frame_allocator::init_frame_allocator();
+    KERNEL_SPACE.exclusive_access().activate();
+}
+
+pub fn kernel_token() -> usize {
+    KERNEL_SPACE.exclusive_access().token()
+}
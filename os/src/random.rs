@@ -0,0 +1,43 @@
+//! A kernel-internal PRNG backing `sys_getrandom`. Seeded from the timer
+//! at first use; **not** cryptographically secure — there's no entropy
+//! source behind it beyond the boot time, so it's only fit for hash seeds
+//! and test data, never keys or tokens.
+
+use crate::sync::UPSafeCell;
+use crate::timer::get_time_ns;
+use lazy_static::*;
+
+/// xorshift64* — minimal state, decent statistical quality, no need to
+/// pull in the `rand` crate for something this kernel only uses for
+/// non-adversarial randomness.
+struct Xorshift64 {
+    state: u64,
+}
+
+impl Xorshift64 {
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+lazy_static! {
+    /// A zero seed would make every `next_u64` call return 0 forever, so
+    /// `| 1` guards against the (practically impossible, but free to rule
+    /// out) case of booting at timer value 0.
+    static ref RNG: UPSafeCell<Xorshift64> =
+        unsafe { UPSafeCell::new(Xorshift64 { state: get_time_ns() | 1 }) };
+}
+
+/// Fill `buf` with pseudorandom bytes from the shared kernel PRNG.
+pub fn fill_random(buf: &mut [u8]) {
+    let mut rng = RNG.exclusive_access();
+    for chunk in buf.chunks_mut(8) {
+        let bytes = rng.next_u64().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Mutex, UPSafeCell};
+use crate::task::{add_task, block_current_and_run_next, current_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+/// A shared flag another task can set to abort a
+/// [`Condvar::wait_cancellable`] wait without signaling the condvar itself.
+pub struct CancelToken {
+    cancelled: UPSafeCell<bool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self {
+            cancelled: unsafe { UPSafeCell::new(false) },
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.exclusive_access()
+    }
+
+    fn mark_cancelled(&self) {
+        *self.cancelled.exclusive_access() = true;
+    }
+}
+
+struct WaitEntry {
+    task: Arc<TaskControlBlock>,
+    token: Option<Arc<CancelToken>>,
+}
+
+pub struct Condvar {
+    inner: UPSafeCell<CondvarInner>,
+}
+
+struct CondvarInner {
+    wait_queue: VecDeque<WaitEntry>,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(CondvarInner {
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    pub fn signal(&self) {
+        let mut inner = self.inner.exclusive_access();
+        if let Some(entry) = inner.wait_queue.pop_front() {
+            add_task(entry.task);
+        }
+    }
+
+    /// Atomically release `mutex` and block until `signal` is called,
+    /// then re-acquire `mutex` before returning.
+    pub fn wait(&self, mutex: Arc<dyn Mutex>) {
+        mutex.unlock();
+        let mut inner = self.inner.exclusive_access();
+        inner.wait_queue.push_back(WaitEntry {
+            task: current_task().unwrap(),
+            token: None,
+        });
+        drop(inner);
+        block_current_and_run_next();
+        mutex.lock();
+    }
+
+    /// Like `wait`, but also wakes up if `token` is cancelled by another
+    /// task via `cancel` before `signal` reaches this waiter. Returns
+    /// `true` if the wait ended in a cancellation, `false` if it was
+    /// signaled normally. `mutex` is re-acquired before returning either way.
+    pub fn wait_cancellable(&self, mutex: Arc<dyn Mutex>, token: Arc<CancelToken>) -> bool {
+        mutex.unlock();
+        let mut inner = self.inner.exclusive_access();
+        inner.wait_queue.push_back(WaitEntry {
+            task: current_task().unwrap(),
+            token: Some(token.clone()),
+        });
+        drop(inner);
+        block_current_and_run_next();
+        mutex.lock();
+        token.is_cancelled()
+    }
+
+    /// Abort the wait associated with `token`, if it's still queued,
+    /// waking its task immediately. Does nothing if `token` has already
+    /// been signaled or isn't waiting on this condvar.
+    pub fn cancel(&self, token: &Arc<CancelToken>) {
+        let mut inner = self.inner.exclusive_access();
+        let pos = inner
+            .wait_queue
+            .iter()
+            .position(|entry| entry.token.as_ref().is_some_and(|t| Arc::ptr_eq(t, token)));
+        let Some(pos) = pos else {
+            return;
+        };
+        let entry = inner.wait_queue.remove(pos).unwrap();
+        drop(inner);
+        token.mark_cancelled();
+        add_task(entry.task);
+    }
+}
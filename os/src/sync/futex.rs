@@ -0,0 +1,53 @@
+use super::UPSafeCell;
+use crate::task::{add_task, TaskControlBlock};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use lazy_static::*;
+
+/// Global futex wait queues, keyed by the **physical** address of the user
+/// word being waited on (not the virtual address `sys_futex_wait`/
+/// `sys_futex_wake` are called with), so two tasks sharing the same
+/// underlying page through different mappings still rendezvous on the same
+/// queue.
+pub struct FutexTable {
+    queues: BTreeMap<usize, VecDeque<Arc<TaskControlBlock>>>,
+}
+
+impl FutexTable {
+    pub fn new() -> Self {
+        Self {
+            queues: BTreeMap::new(),
+        }
+    }
+
+    /// Queue `task` on `key`'s wait list. The caller is responsible for
+    /// blocking it afterwards; this only records it as a waiter.
+    pub fn wait(&mut self, key: usize, task: Arc<TaskControlBlock>) {
+        self.queues.entry(key).or_default().push_back(task);
+    }
+
+    /// Wake up to `n` waiters on `key`, returning how many were actually
+    /// woken. Does nothing (and returns 0) if nobody is waiting on `key`.
+    pub fn wake(&mut self, key: usize, n: usize) -> usize {
+        let Some(queue) = self.queues.get_mut(&key) else {
+            return 0;
+        };
+        let mut woken = 0;
+        while woken < n {
+            let Some(task) = queue.pop_front() else {
+                break;
+            };
+            add_task(task);
+            woken += 1;
+        }
+        if queue.is_empty() {
+            self.queues.remove(&key);
+        }
+        woken
+    }
+}
+
+lazy_static! {
+    pub static ref FUTEX_TABLE: UPSafeCell<FutexTable> =
+        unsafe { UPSafeCell::new(FutexTable::new()) };
+}
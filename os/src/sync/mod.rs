@@ -0,0 +1,11 @@
+mod condvar;
+mod futex;
+mod mutex;
+mod semaphore;
+mod up;
+
+pub use condvar::{CancelToken, Condvar};
+pub use futex::FUTEX_TABLE;
+pub use mutex::{Mutex, MutexBlocking, MutexSpin};
+pub use semaphore::Semaphore;
+pub use up::UPSafeCell;
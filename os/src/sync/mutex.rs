@@ -0,0 +1,116 @@
+use super::UPSafeCell;
+use crate::task::{
+    block_current_and_run_next, current_task, suspend_current_and_run_next, wakeup_task_directed,
+    TaskControlBlock,
+};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+pub trait Mutex: Sync + Send {
+    fn lock(&self);
+    fn unlock(&self);
+    /// Acquire without blocking. Returns `true` if the mutex was free and is
+    /// now held, `false` if it was already locked (and this task is not
+    /// queued to wait for it).
+    fn try_lock(&self) -> bool;
+}
+
+/// A spinlock, mostly useful for uncontended short critical sections.
+pub struct MutexSpin {
+    locked: UPSafeCell<bool>,
+}
+
+impl MutexSpin {
+    pub fn new() -> Self {
+        Self {
+            locked: unsafe { UPSafeCell::new(false) },
+        }
+    }
+}
+
+impl Mutex for MutexSpin {
+    fn lock(&self) {
+        loop {
+            let mut locked = self.locked.exclusive_access();
+            if *locked {
+                drop(locked);
+                suspend_current_and_run_next();
+                continue;
+            } else {
+                *locked = true;
+                return;
+            }
+        }
+    }
+
+    fn unlock(&self) {
+        let mut locked = self.locked.exclusive_access();
+        *locked = false;
+    }
+
+    fn try_lock(&self) -> bool {
+        let mut locked = self.locked.exclusive_access();
+        if *locked {
+            false
+        } else {
+            *locked = true;
+            true
+        }
+    }
+}
+
+/// A mutex that parks waiters on a wait queue instead of spinning.
+pub struct MutexBlocking {
+    inner: UPSafeCell<MutexBlockingInner>,
+}
+
+struct MutexBlockingInner {
+    locked: bool,
+    wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl MutexBlocking {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(MutexBlockingInner {
+                    locked: false,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+}
+
+impl Mutex for MutexBlocking {
+    fn lock(&self) {
+        let mut inner = self.inner.exclusive_access();
+        if inner.locked {
+            inner.wait_queue.push_back(current_task().unwrap());
+            drop(inner);
+            block_current_and_run_next();
+        } else {
+            inner.locked = true;
+        }
+    }
+
+    fn unlock(&self) {
+        let mut inner = self.inner.exclusive_access();
+        assert!(inner.locked);
+        if let Some(waking_task) = inner.wait_queue.pop_front() {
+            wakeup_task_directed(waking_task);
+        } else {
+            inner.locked = false;
+        }
+    }
+
+    fn try_lock(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.locked {
+            false
+        } else {
+            inner.locked = true;
+            true
+        }
+    }
+}
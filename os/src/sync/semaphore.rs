@@ -0,0 +1,73 @@
+use super::UPSafeCell;
+use crate::task::{add_task, block_current_and_run_next, current_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+pub struct Semaphore {
+    pub inner: UPSafeCell<SemaphoreInner>,
+}
+
+pub struct SemaphoreInner {
+    pub count: isize,
+    pub wait_queue: VecDeque<Arc<TaskControlBlock>>,
+    destroyed: bool,
+}
+
+impl Semaphore {
+    pub fn new(res_count: usize) -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(SemaphoreInner {
+                    count: res_count as isize,
+                    wait_queue: VecDeque::new(),
+                    destroyed: false,
+                })
+            },
+        }
+    }
+
+    pub fn up(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.count += 1;
+        if inner.count <= 0 {
+            if let Some(task) = inner.wait_queue.pop_front() {
+                add_task(task);
+            }
+        }
+    }
+
+    /// Block until a resource is available. Returns `false` without ever
+    /// acquiring one if `destroy` tears this semaphore down — either
+    /// before this call decrements `count` at all, or while it's sitting
+    /// in `wait_queue` — `true` otherwise.
+    pub fn down(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.destroyed {
+            return false;
+        }
+        inner.count -= 1;
+        if inner.count < 0 {
+            inner.wait_queue.push_back(current_task().unwrap());
+            drop(inner);
+            block_current_and_run_next();
+            return !self.inner.exclusive_access().destroyed;
+        }
+        true
+    }
+
+    /// Wake every task currently blocked in `wait_queue` so its `down`
+    /// returns `false` instead of hanging forever, and mark this
+    /// semaphore destroyed so any `down` still racing this call also
+    /// fails rather than blocking. The caller owns freeing this
+    /// semaphore's slot in `semaphore_list`; `destroy` only tears down
+    /// the wait queue.
+    pub fn destroy(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.destroyed = true;
+        let waiters: VecDeque<_> = inner.wait_queue.drain(..).collect();
+        drop(inner);
+        for task in waiters {
+            add_task(task);
+        }
+    }
+}
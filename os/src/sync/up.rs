@@ -0,0 +1,34 @@
+use core::cell::{RefCell, RefMut};
+
+/// Wraps a value so it can be shared as a `static` and mutated, relying on
+/// the fact that this kernel is single-hart and never preempts itself
+/// outside of explicit trap/switch points.
+pub struct UPSafeCell<T> {
+    inner: RefCell<T>,
+}
+
+unsafe impl<T> Sync for UPSafeCell<T> {}
+
+impl<T> UPSafeCell<T> {
+    /// # Safety
+    /// The caller must guarantee this is used in an environment with only
+    /// one hart and no interleaved access.
+    pub unsafe fn new(value: T) -> Self {
+        Self {
+            inner: RefCell::new(value),
+        }
+    }
+
+    pub fn exclusive_access(&self) -> RefMut<'_, T> {
+        self.inner
+            .try_borrow_mut()
+            .expect("UPSafeCell already borrowed: a double-borrow bug, not concurrent access")
+    }
+
+    /// Like `exclusive_access`, but returns `None` instead of panicking if
+    /// the cell is already borrowed, for call sites that can back off
+    /// instead of treating a double-borrow as fatal.
+    pub fn try_exclusive_access(&self) -> Option<RefMut<'_, T>> {
+        self.inner.try_borrow_mut().ok()
+    }
+}
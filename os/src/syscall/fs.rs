@@ -0,0 +1,1045 @@
+use crate::fs::{
+    find_inode, flock, make_pipe, mknod_fifo, open_fifo_read, open_fifo_write, open_file,
+    open_file_at, stat_path, DirentFilter, EpollEvent, EpollInstance, EventFd, FdStats, File,
+    IoVec, OpenFlags, PipeRingBuffer, PollFd, RamFile, Stat, EPOLLIN, EPOLL_CTL_DEL, LOCK_UN,
+    POLLIN, ROOT_INODE,
+};
+use crate::mm::{
+    translated_byte_buffer, translated_ref, translated_refmut, translated_str, write_user,
+    UserBuffer,
+};
+use crate::sync::UPSafeCell;
+use crate::task::{
+    current_task, current_user_token, exit_current_and_run_next, suspend_current_and_run_next,
+    ProcFlags, TaskControlBlock, STRICT_RLIMIT_EXIT_CODE,
+};
+use crate::timer::get_time_us;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use easy_fs::Inode;
+
+/// Called wherever `TaskControlBlockInner::alloc_fd` returns `None`. Under
+/// `ProcFlags::STRICT_RLIMIT` this kills the task instead of letting the
+/// caller fall back to returning -1.
+fn fd_limit_hit(task: &Arc<TaskControlBlock>) -> isize {
+    let strict = task
+        .inner_exclusive_access()
+        .proc_flags
+        .contains(ProcFlags::STRICT_RLIMIT);
+    if strict {
+        exit_current_and_run_next(STRICT_RLIMIT_EXIT_CODE);
+    }
+    -1
+}
+
+pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    if !file.writable() || file.inode().is_some_and(|inode| inode.is_dir()) {
+        return -1;
+    }
+    let file = file.clone();
+    drop(fd_table);
+    drop(inner);
+    file.write(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+}
+
+/// -1 only for an invalid/unreadable/directory fd, checked before ever
+/// touching `File::read`. Past that point this always returns whatever
+/// `read` returns, never substituting -1 for it — see `File::read`'s doc
+/// comment for what 0 does and doesn't mean from there.
+pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    if !file.readable() || file.inode().is_some_and(|inode| inode.is_dir()) {
+        return -1;
+    }
+    let file = file.clone();
+    drop(fd_table);
+    drop(inner);
+    file.read(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+}
+
+pub fn sys_open(path: *const u8, flags: u32) -> isize {
+    let task = current_task().unwrap();
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    let Some(flags) = OpenFlags::from_bits(flags).filter(OpenFlags::is_valid_access_mode) else {
+        return -1;
+    };
+    if let Some(inode) = find_inode(path.as_str()) {
+        if inode.is_fifo() {
+            let (readable, writable) = flags.read_write();
+            let file: Arc<dyn File + Send + Sync> = if writable {
+                open_fifo_write(inode.inode_id())
+            } else if readable {
+                open_fifo_read(inode.inode_id())
+            } else {
+                return -1;
+            };
+            let mut inner = task.inner_exclusive_access();
+            let Some(fd) = inner.alloc_fd() else {
+                drop(inner);
+                return fd_limit_hit(&task);
+            };
+            inner.fd_table.exclusive_access()[fd] = Some(file);
+            inner.close_on_exec.exclusive_access()[fd] = flags.contains(OpenFlags::CLOEXEC);
+            return fd as isize;
+        }
+    }
+    if let Some(inode) = open_file(path.as_str(), flags) {
+        let mut inner = task.inner_exclusive_access();
+        let Some(fd) = inner.alloc_fd() else {
+            drop(inner);
+            return fd_limit_hit(&task);
+        };
+        inner.fd_table.exclusive_access()[fd] = Some(inode);
+        inner.close_on_exec.exclusive_access()[fd] = flags.contains(OpenFlags::CLOEXEC);
+        fd as isize
+    } else {
+        -1
+    }
+}
+
+/// Passed as `dirfd` to resolve `path` against the current working
+/// directory instead of an open directory fd. This kernel has no `chdir`,
+/// so "the cwd" is always the filesystem root.
+pub const AT_FDCWD: isize = -100;
+
+/// Like `sys_open`, but `path` (if relative) is resolved against the
+/// directory `dirfd` refers to instead of always against the root —
+/// `AT_FDCWD` keeps the old root-relative behavior. Returns -1 if `dirfd`
+/// isn't an open fd, or doesn't name a directory.
+pub fn sys_openat(dirfd: isize, path: *const u8, flags: u32) -> isize {
+    let task = current_task().unwrap();
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    let Some(flags) = OpenFlags::from_bits(flags).filter(OpenFlags::is_valid_access_mode) else {
+        return -1;
+    };
+    let base = if dirfd == AT_FDCWD {
+        ROOT_INODE.clone()
+    } else {
+        let inner = task.inner_exclusive_access();
+        let fd_table = inner.fd_table.exclusive_access();
+        let Some(Some(dir)) = fd_table.get(dirfd as usize) else {
+            return -1;
+        };
+        let Some(inode) = dir.inode() else {
+            return -1;
+        };
+        drop(fd_table);
+        drop(inner);
+        if !inode.is_dir() {
+            return -1;
+        }
+        inode
+    };
+    if let Some(inode) = open_file_at(&base, path.as_str(), flags) {
+        let mut inner = task.inner_exclusive_access();
+        let Some(fd) = inner.alloc_fd() else {
+            drop(inner);
+            return fd_limit_hit(&task);
+        };
+        inner.fd_table.exclusive_access()[fd] = Some(inode);
+        inner.close_on_exec.exclusive_access()[fd] = flags.contains(OpenFlags::CLOEXEC);
+        fd as isize
+    } else {
+        -1
+    }
+}
+
+/// The only `mknod` kind this kernel supports: a FIFO (named pipe).
+const MKNOD_FIFO: u32 = 1;
+
+/// Create a filesystem entry at `path` without opening it. `kind` must be
+/// `MKNOD_FIFO`; any other value fails like an unsupported device type
+/// would on a real `mknod(2)`. Returns -1 on failure.
+pub fn sys_mknod(path: *const u8, kind: u32) -> isize {
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    if kind != MKNOD_FIFO {
+        return -1;
+    }
+    if mknod_fifo(path.as_str()) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_close(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut fd_table = inner.fd_table.exclusive_access();
+    if fd >= fd_table.len() {
+        return -1;
+    }
+    let Some(file) = fd_table[fd].take() else {
+        return -1;
+    };
+    inner.close_on_exec.exclusive_access()[fd] = false;
+    if let Some(inode) = file.inode() {
+        let inode_id = inode.inode_id();
+        let still_open = fd_table
+            .iter()
+            .flatten()
+            .any(|f| f.inode().is_some_and(|i| i.inode_id() == inode_id));
+        if !still_open {
+            drop(fd_table);
+            drop(inner);
+            flock(inode_id, task.getpid(), LOCK_UN);
+        }
+    }
+    0
+}
+
+bitflags! {
+    /// Flags for `sys_close_range`.
+    pub struct CloseRangeFlags: u32 {
+        /// Set close-on-exec on every fd in the range instead of closing
+        /// it.
+        const CLOEXEC = 1 << 0;
+    }
+}
+
+/// Close every open fd in `[first, last]` (inclusive) — or, with
+/// `CloseRangeFlags::CLOEXEC` set, leave each open but set its
+/// close-on-exec flag instead of closing it. Tolerates an already-closed
+/// slot or a range that runs past the end of the fd table; only returns
+/// -1 if `first > last` or `flags` has a bit outside `CloseRangeFlags`.
+/// A convenience over looping `sys_close` one fd at a time, most useful
+/// right before `exec` to drop every fd above the standard three in one
+/// call.
+pub fn sys_close_range(first: usize, last: usize, flags: u32) -> isize {
+    let Some(flags) = CloseRangeFlags::from_bits(flags) else {
+        return -1;
+    };
+    if first > last {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut fd_table = inner.fd_table.exclusive_access();
+    if first >= fd_table.len() {
+        return 0;
+    }
+    let last = last.min(fd_table.len() - 1);
+    if flags.contains(CloseRangeFlags::CLOEXEC) {
+        let mut close_on_exec = inner.close_on_exec.exclusive_access();
+        for fd in first..=last {
+            close_on_exec[fd] = fd_table[fd].is_some();
+        }
+        return 0;
+    }
+    let mut closed_inodes = Vec::new();
+    for fd in first..=last {
+        if let Some(file) = fd_table[fd].take() {
+            inner.close_on_exec.exclusive_access()[fd] = false;
+            if let Some(inode) = file.inode() {
+                closed_inodes.push(inode.inode_id());
+            }
+        }
+    }
+    let mut to_unlock = Vec::new();
+    for inode_id in closed_inodes {
+        let still_open = fd_table
+            .iter()
+            .flatten()
+            .any(|f| f.inode().is_some_and(|i| i.inode_id() == inode_id));
+        if !still_open && !to_unlock.contains(&inode_id) {
+            to_unlock.push(inode_id);
+        }
+    }
+    drop(fd_table);
+    drop(inner);
+    for inode_id in to_unlock {
+        flock(inode_id, task.getpid(), LOCK_UN);
+    }
+    0
+}
+
+pub fn sys_pipe(pipe: *mut usize) -> isize {
+    let task = current_task().unwrap();
+    let token = current_user_token();
+    let mut inner = task.inner_exclusive_access();
+    let (pipe_read, pipe_write) = make_pipe();
+    let Some(read_fd) = inner.alloc_fd() else {
+        drop(inner);
+        return fd_limit_hit(&task);
+    };
+    inner.fd_table.exclusive_access()[read_fd] = Some(pipe_read);
+    let Some(write_fd) = inner.alloc_fd() else {
+        drop(inner);
+        return fd_limit_hit(&task);
+    };
+    inner.fd_table.exclusive_access()[write_fd] = Some(pipe_write);
+    *translated_refmut(token, pipe) = read_fd;
+    *translated_refmut(token, unsafe { pipe.add(1) }) = write_fd;
+    0
+}
+
+pub fn sys_dup(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    if fd >= fd_table.len() || fd_table[fd].is_none() {
+        return -1;
+    }
+    let duped = fd_table[fd].as_ref().unwrap().clone();
+    drop(fd_table);
+    let Some(new_fd) = inner.alloc_fd() else {
+        drop(inner);
+        return fd_limit_hit(&task);
+    };
+    inner.fd_table.exclusive_access()[new_fd] = Some(duped);
+    new_fd as isize
+}
+
+pub fn sys_fstat(fd: usize, st: *mut u8) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let stat = file.stat();
+    drop(fd_table);
+    drop(inner);
+    write_user(token, st as *mut Stat, stat);
+    0
+}
+
+/// Report cumulative read/write bandwidth through `fd`, for an I/O monitor
+/// to attribute bytes moved to specific files rather than just syscall
+/// counts. Returns -1 if `fd` isn't open.
+pub fn sys_fd_stats(fd: usize, buf: *mut u8) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let stats = file.io_stats();
+    drop(fd_table);
+    drop(inner);
+    write_user(token, buf as *mut FdStats, stats);
+    0
+}
+
+/// Stat a file by path, without the open/fstat/close dance `sys_fstat`
+/// requires. Returns -1 if no such path exists.
+pub fn sys_stat(path: *const u8, st: *mut u8) -> isize {
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    let Some(stat) = stat_path(path.as_str()) else {
+        return -1;
+    };
+    write_user(token, st as *mut Stat, stat);
+    0
+}
+
+pub fn sys_linkat(_old_path: *const u8, _new_path: *const u8) -> isize {
+    -1
+}
+
+pub fn sys_unlinkat(_path: *const u8) -> isize {
+    -1
+}
+
+/// Flush only the cached blocks backing `[offset, offset + len)` of `fd`'s
+/// file, rather than a whole-file fsync. Returns -1 if `fd` has no backing
+/// inode (a pipe, stdio, or eventfd).
+pub fn sys_sync_file_range(fd: usize, offset: usize, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    inode.sync_range(offset, len);
+    0
+}
+
+/// Free `[offset, offset + len)` of `fd`'s file, deallocating every data
+/// block fully covered by the range (a later read there comes back as
+/// zeros) without changing the file's size. Returns -1 if `fd` has no
+/// backing inode.
+pub fn sys_fpunch_hole(fd: usize, offset: usize, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    inode.punch_hole(offset as u32, len as u32);
+    0
+}
+
+/// List a directory fd's entries into `buf` as NUL-terminated names, one
+/// after another, stopping before any name that wouldn't fit. Picks up
+/// where the previous call on this same fd left off rather than
+/// re-listing from the start, via `File::next_dirents`'s per-fd cursor
+/// over a snapshot taken the first time this fd is read — so a rename or
+/// unlink elsewhere in the directory between calls can't cause an entry
+/// this fd hasn't reached yet to be skipped or duplicated. Returns the
+/// number of bytes written, or -1 if `fd` isn't an open directory.
+pub fn sys_getdents(fd: usize, buf: *mut u8, len: usize) -> isize {
+    getdents_impl(fd, buf, len, DirentFilter::All)
+}
+
+/// `dirent_type`s accepted by `sys_getdents_filtered`. Matches neither
+/// `DT_DIR` nor `DT_REG`'s actual glibc values, since this kernel's
+/// `getdents` never reported a type byte to begin with; these are its own
+/// small, self-contained convention.
+pub const GETDENTS_FILTER_ALL: i32 = 0;
+pub const GETDENTS_FILTER_DIRS_ONLY: i32 = 1;
+pub const GETDENTS_FILTER_FILES_ONLY: i32 = 2;
+
+/// Like `sys_getdents`, but only returns entries matching `filter`
+/// (`GETDENTS_FILTER_*`), so a caller that only wants directory names (or
+/// only non-directory names) doesn't have to `fstat` every entry itself
+/// to throw away the ones it doesn't care about. Shares `sys_getdents`'s
+/// fd cursor: entries skipped by the filter still advance it, so
+/// switching filters mid-listing on the same fd doesn't replay or skip
+/// anything relative to where this call leaves off. Returns -1 if `fd`
+/// isn't an open directory or `filter` isn't a recognized value.
+pub fn sys_getdents_filtered(fd: usize, buf: *mut u8, len: usize, filter: i32) -> isize {
+    let filter = match filter {
+        GETDENTS_FILTER_ALL => DirentFilter::All,
+        GETDENTS_FILTER_DIRS_ONLY => DirentFilter::DirsOnly,
+        GETDENTS_FILTER_FILES_ONLY => DirentFilter::FilesOnly,
+        _ => return -1,
+    };
+    getdents_impl(fd, buf, len, filter)
+}
+
+/// List a directory fd's entries into `buf` as NUL-terminated names, one
+/// after another, stopping before any name that wouldn't fit. Picks up
+/// where the previous call on this same fd left off rather than
+/// re-listing from the start, via `File::next_dirents`'s per-fd cursor
+/// over a snapshot taken the first time this fd is read — so a rename or
+/// unlink elsewhere in the directory between calls can't cause an entry
+/// this fd hasn't reached yet to be skipped or duplicated. Returns the
+/// number of bytes written, or -1 if `fd` isn't an open directory.
+fn getdents_impl(fd: usize, buf: *mut u8, len: usize, filter: DirentFilter) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let file = file.clone();
+    drop(fd_table);
+    drop(inner);
+    let Some(names) = file.next_dirents(len, filter) else {
+        return -1;
+    };
+    let mut buf_iter = UserBuffer::new(translated_byte_buffer(token, buf, len)).into_iter();
+    let mut written = 0usize;
+    for name in names {
+        for &byte in name.as_bytes().iter().chain(&[0u8]) {
+            match buf_iter.next() {
+                Some(dst) => unsafe {
+                    *dst = byte;
+                },
+                None => return written as isize,
+            }
+            written += 1;
+        }
+    }
+    written as isize
+}
+
+/// Flush `fd`'s data and inode metadata to disk. Returns -1 if `fd` has no
+/// backing inode (a pipe, stdio, or eventfd), or if a write combined
+/// earlier by the inode's write-combining never actually reached disk (the
+/// disk filled up between that write returning "success" and its deferred
+/// flush running).
+pub fn sys_fsync(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    if inode.fsync() {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Like `sys_fsync`, but skips rewriting `fd`'s inode metadata block unless
+/// its size or block pointers changed, so a pure timestamp update doesn't
+/// force an extra write. Returns -1 under the same conditions as
+/// `sys_fsync`.
+pub fn sys_fdatasync(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    if inode.fdatasync() {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Flush every buffered filesystem block to disk, across every open file,
+/// not just one `fd` the way `sys_fsync`/`sys_fdatasync` do. This kernel's
+/// block device (`VirtIOBlock`) is synchronous — `write_block` only
+/// returns once the write has actually landed — so there's no separate
+/// completion to wait for beyond the flush call itself; unlike a
+/// real-world `sync(2)` built over an async/interrupt-driven device queue,
+/// nothing here can be "submitted but not yet acknowledged" by the time
+/// `block_cache_sync_all` returns. Always succeeds.
+pub fn sys_sync() -> isize {
+    easy_fs::block_cache_sync_all();
+    0
+}
+
+/// Read `iovcnt` scatter-gather segments from `fd` starting at `offset`,
+/// filling them in order without moving `fd`'s own read cursor. Returns the
+/// total bytes read, which is less than the sum of `iov` lengths at EOF, or
+/// -1 if `fd` has no backing inode (a pipe, stdio, or eventfd).
+pub fn sys_preadv(fd: usize, iov: *const IoVec, iovcnt: usize, offset: usize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    let mut cursor = offset;
+    let mut total = 0usize;
+    'segments: for i in 0..iovcnt {
+        let segment = *translated_ref(token, unsafe { iov.add(i) });
+        let mut buf = UserBuffer::new(translated_byte_buffer(token, segment.base, segment.len));
+        for slice in buf.buffers.iter_mut() {
+            let read_size = inode.read_at(cursor, slice);
+            cursor += read_size;
+            total += read_size;
+            if read_size < slice.len() {
+                break 'segments;
+            }
+        }
+    }
+    total as isize
+}
+
+/// Write `iovcnt` scatter-gather segments to `fd` starting at `offset`, in
+/// order, without moving `fd`'s own write cursor. Returns the total bytes
+/// written, or -1 if `fd` has no backing inode (a pipe, stdio, or eventfd).
+pub fn sys_pwritev(fd: usize, iov: *const IoVec, iovcnt: usize, offset: usize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    let mut cursor = offset;
+    let mut total = 0usize;
+    for i in 0..iovcnt {
+        let segment = *translated_ref(token, unsafe { iov.add(i) });
+        let buf = UserBuffer::new(translated_byte_buffer(token, segment.base, segment.len));
+        for slice in buf.buffers.iter() {
+            let write_size = inode.write_at(cursor, slice);
+            assert_eq!(write_size, slice.len());
+            cursor += write_size;
+            total += write_size;
+        }
+    }
+    total as isize
+}
+
+/// Advisory `flock(2)`-style lock/unlock on `fd`'s inode. `op` is one of
+/// `LOCK_SH`/`LOCK_EX`/`LOCK_UN`, optionally `| LOCK_NB` to fail instead of
+/// blocking. The lock is shared by every fd this process has open on the
+/// same inode, and is released automatically once none of them are left
+/// open (as well as explicitly via `LOCK_UN`). Returns -1 if `fd` has no
+/// backing inode, `op` is invalid, or (with `LOCK_NB`) the lock is already
+/// held incompatibly by another process.
+pub fn sys_flock(fd: usize, op: u32) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    let inode_id = inode.inode_id();
+    drop(fd_table);
+    drop(inner);
+    flock(inode_id, task.getpid(), op)
+}
+
+/// Set (or, with a negative `timeout_ms`, clear) how long a `write` to
+/// `fd` will wait for buffer space before giving up and returning whatever
+/// it's written so far, instead of blocking on a slow or stuck reader
+/// forever. Only meaningful for a pipe write end; a no-op (but not an
+/// error) on any other kind of file. Returns -1 if `fd` isn't open.
+pub fn sys_set_pipe_write_timeout(fd: usize, timeout_ms: isize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    file.set_write_timeout_ms((timeout_ms >= 0).then(|| timeout_ms as usize));
+    0
+}
+
+/// `sys_poll` returns this instead of a ready count when the wait was cut
+/// short by a `sys_kill`-delivered signal, rather than any fd becoming
+/// ready or the timeout elapsing.
+const EINTR: isize = -2;
+
+/// Wait until at least one of `fds[..nfds]` is ready per `File::poll_readable`
+/// (only `POLLIN` is supported), `timeout_ms` milliseconds have passed
+/// (a negative `timeout_ms` waits forever), or a signal becomes pending
+/// on this task. Returns the number of ready fds with `revents` filled
+/// in, 0 on timeout, `EINTR` if interrupted, or -1 if `nfds` names a
+/// closed fd.
+///
+/// The deadline is computed once up front with `get_time_us`, not
+/// `get_time_ms`, and checked in microseconds every time around the loop.
+/// `suspend_current_and_run_next` gives up the remainder of this tick, but
+/// with no other runnable task it's handed straight back, so checking in
+/// ms would round a short timeout like 3ms up to the next whole
+/// millisecond tick (or down to 0) instead of returning once the
+/// requested microsecond actually elapses.
+pub fn sys_poll(fds: *mut PollFd, nfds: usize, timeout_ms: isize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let deadline_us = (timeout_ms >= 0).then(|| get_time_us() + timeout_ms as usize * 1000);
+    loop {
+        let mut inner = task.inner_exclusive_access();
+        if core::mem::take(&mut inner.pending_signal) {
+            return EINTR;
+        }
+        let fd_table = inner.fd_table.clone();
+        drop(inner);
+        let mut ready = 0usize;
+        for i in 0..nfds {
+            let pollfd = translated_refmut(token, unsafe { fds.add(i) });
+            let table = fd_table.exclusive_access();
+            let Some(Some(file)) = table.get(pollfd.fd as usize) else {
+                return -1;
+            };
+            pollfd.revents = 0;
+            if pollfd.events & POLLIN != 0 && file.poll_readable() {
+                pollfd.revents |= POLLIN;
+            }
+            if pollfd.revents != 0 {
+                ready += 1;
+            }
+        }
+        if ready > 0 {
+            return ready as isize;
+        }
+        if deadline_us.is_some_and(|deadline| get_time_us() >= deadline) {
+            return 0;
+        }
+        suspend_current_and_run_next();
+    }
+}
+
+/// Create a new epoll instance, installed into the current task's own
+/// `fd_table` the same way `sys_pipe`/`sys_eventfd` install theirs. Returns
+/// its fd.
+pub fn sys_epoll_create() -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let Some(fd) = inner.alloc_fd() else {
+        drop(inner);
+        return fd_limit_hit(&task);
+    };
+    inner.fd_table.exclusive_access()[fd] = Some(Arc::new(EpollInstance::new()));
+    fd as isize
+}
+
+/// Add/modify/remove `fd`'s registration on the epoll instance `epfd`, per
+/// `op` (`EPOLL_CTL_ADD`/`MOD`/`DEL`). `event` is read for `ADD`/`MOD` (its
+/// `events`/`data` become the registration); ignored for `DEL`, which may
+/// pass a null pointer. Returns -1 if `epfd` isn't an open epoll instance,
+/// or if `op` doesn't apply (`ADD` on an already-registered `fd`, `MOD`/`DEL`
+/// on one that isn't registered).
+pub fn sys_epoll_ctl(epfd: usize, op: usize, fd: usize, event: *const EpollEvent) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(epoll)) = fd_table.get(epfd) else {
+        return -1;
+    };
+    let epoll = epoll.clone();
+    drop(fd_table);
+    drop(inner);
+    let (events, data) = if op == EPOLL_CTL_DEL {
+        (0, 0)
+    } else {
+        let event = translated_ref(token, event);
+        (event.events, event.data)
+    };
+    match epoll.epoll_ctl(op, fd as i32, events, data) {
+        Some(true) => 0,
+        _ => -1,
+    }
+}
+
+/// Wait until at least one fd registered on the epoll instance `epfd`
+/// becomes ready per `File::poll_readable()`, `timeout_ms` milliseconds
+/// have passed (a negative `timeout_ms` waits forever), or a signal becomes
+/// pending on this task. Writes up to `maxevents` ready fds into `events`
+/// and returns how many, 0 on timeout, `EINTR` if interrupted, or -1 if
+/// `epfd` isn't an open epoll instance.
+///
+/// Still re-checks every registered fd each time around the loop — see
+/// `EpollInstance`'s doc comment — so this scales with the number of fds
+/// registered on `epfd`, not the number open in the whole task, which is
+/// `sys_poll`'s actual limitation for a server juggling many idle fds.
+///
+/// Like `sys_poll`, the deadline is tracked in microseconds (`get_time_us`)
+/// rather than milliseconds, so a short timeout isn't rounded up to the
+/// next whole millisecond tick.
+pub fn sys_epoll_wait(
+    epfd: usize,
+    events: *mut EpollEvent,
+    maxevents: usize,
+    timeout_ms: isize,
+) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let deadline_us = (timeout_ms >= 0).then(|| get_time_us() + timeout_ms as usize * 1000);
+    loop {
+        let mut inner = task.inner_exclusive_access();
+        if core::mem::take(&mut inner.pending_signal) {
+            return EINTR;
+        }
+        let fd_table = inner.fd_table.clone();
+        drop(inner);
+        let table = fd_table.exclusive_access();
+        let Some(Some(epoll)) = table.get(epfd) else {
+            return -1;
+        };
+        let epoll = epoll.clone();
+        drop(table);
+        let Some(registered) = epoll.epoll_registered() else {
+            return -1;
+        };
+        let mut ready = 0usize;
+        for (fd, interest, data) in registered {
+            if ready >= maxevents {
+                break;
+            }
+            let table = fd_table.exclusive_access();
+            let Some(Some(file)) = table.get(fd as usize) else {
+                continue;
+            };
+            let file = file.clone();
+            drop(table);
+            if interest & EPOLLIN != 0 && file.poll_readable() {
+                *translated_refmut(token, unsafe { events.add(ready) }) = EpollEvent {
+                    events: EPOLLIN,
+                    data,
+                };
+                ready += 1;
+            }
+        }
+        if ready > 0 {
+            return ready as isize;
+        }
+        if deadline_us.is_some_and(|deadline| get_time_us() >= deadline) {
+            return 0;
+        }
+        suspend_current_and_run_next();
+    }
+}
+
+/// Copy up to `len` bytes currently buffered in `in_fd`'s pipe into
+/// `out_fd`'s pipe, without consuming them from `in_fd` — its reader still
+/// sees every byte. Blocks if `out_fd`'s pipe is full, the same as a
+/// regular `write` would. Returns the number of bytes copied (which can be
+/// less than `len` if fewer were buffered), or -1 if either fd isn't a pipe
+/// or `out_fd`'s read end has already been dropped.
+pub fn sys_tee(in_fd: usize, out_fd: usize, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(in_file)) = fd_table.get(in_fd) else {
+        return -1;
+    };
+    let Some(Some(out_file)) = fd_table.get(out_fd) else {
+        return -1;
+    };
+    let (Some(src), Some(dst)) = (in_file.pipe_buffer(), out_file.pipe_buffer()) else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    let mut peeked = alloc::vec![0u8; len.min(src.exclusive_access().capacity())];
+    let copied = src.exclusive_access().peek(&mut peeked);
+    let mut written = 0usize;
+    while written < copied {
+        let mut ring_buffer = dst.exclusive_access();
+        if ring_buffer.all_read_ends_closed() {
+            return if written == 0 { -1 } else { written as isize };
+        }
+        let loop_write = ring_buffer.available_write().min(copied - written);
+        if loop_write == 0 {
+            drop(ring_buffer);
+            suspend_current_and_run_next();
+            continue;
+        }
+        for _ in 0..loop_write {
+            ring_buffer.write_byte(peeked[written]);
+            written += 1;
+        }
+    }
+    written as isize
+}
+
+/// Move up to `len` bytes between a pipe and a file entirely in-kernel,
+/// without round-tripping through a userspace buffer like a `read`
+/// followed by a `write` would. Exactly one of `in_fd`/`out_fd` must be a
+/// pipe end and the other a regular file; `in_off`/`out_off` give the file
+/// side's starting offset and are ignored for whichever side is the pipe,
+/// which always moves through its own ring buffer cursor instead. Returns
+/// -1 if either fd doesn't exist, or the two aren't one pipe and one file.
+pub fn sys_splice(in_fd: usize, in_off: usize, out_fd: usize, out_off: usize, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(in_file)) = fd_table.get(in_fd) else {
+        return -1;
+    };
+    let Some(Some(out_file)) = fd_table.get(out_fd) else {
+        return -1;
+    };
+    let in_file = in_file.clone();
+    let out_file = out_file.clone();
+    drop(fd_table);
+    drop(inner);
+    if let (Some(in_pipe), Some(out_inode)) = (in_file.pipe_buffer(), out_file.inode()) {
+        splice_pipe_to_file(&in_pipe, &out_inode, out_off, len)
+    } else if let (Some(in_inode), Some(out_pipe)) = (in_file.inode(), out_file.pipe_buffer()) {
+        splice_file_to_pipe(&in_inode, in_off, &out_pipe, len)
+    } else {
+        -1
+    }
+}
+
+/// `sys_splice`'s file-to-pipe direction: read `len` bytes from `inode`
+/// starting at `offset` in chunks, writing each chunk into `pipe` as it's
+/// read. Stops early at EOF or once every read end of `pipe` has closed.
+fn splice_file_to_pipe(
+    inode: &Inode,
+    offset: usize,
+    pipe: &Arc<UPSafeCell<PipeRingBuffer>>,
+    len: usize,
+) -> isize {
+    let mut cursor = offset;
+    let mut total = 0usize;
+    let mut chunk = [0u8; 512];
+    while total < len {
+        let want = chunk.len().min(len - total);
+        let read = inode.read_at(cursor, &mut chunk[..want]);
+        if read == 0 {
+            break;
+        }
+        cursor += read;
+        let mut written = 0usize;
+        while written < read {
+            let mut ring_buffer = pipe.exclusive_access();
+            if ring_buffer.all_read_ends_closed() {
+                let done = total + written;
+                return if done == 0 { -1 } else { done as isize };
+            }
+            let loop_write = ring_buffer.available_write().min(read - written);
+            if loop_write == 0 {
+                drop(ring_buffer);
+                suspend_current_and_run_next();
+                continue;
+            }
+            for _ in 0..loop_write {
+                ring_buffer.write_byte(chunk[written]);
+                written += 1;
+            }
+        }
+        total += read;
+    }
+    total as isize
+}
+
+/// `sys_splice`'s pipe-to-file direction: drain up to `len` bytes out of
+/// `pipe`'s own cursor, writing each burst into `inode` starting at
+/// `offset` as it's read. Stops early once every write end of `pipe` has
+/// closed and its buffer has drained.
+fn splice_pipe_to_file(
+    pipe: &Arc<UPSafeCell<PipeRingBuffer>>,
+    inode: &Inode,
+    offset: usize,
+    len: usize,
+) -> isize {
+    let mut cursor = offset;
+    let mut total = 0usize;
+    let mut chunk = [0u8; 512];
+    while total < len {
+        let want = chunk.len().min(len - total);
+        let mut read = 0usize;
+        loop {
+            let mut ring_buffer = pipe.exclusive_access();
+            let avail = ring_buffer.available_read();
+            if avail == 0 {
+                if ring_buffer.all_write_ends_closed() {
+                    break;
+                }
+                drop(ring_buffer);
+                suspend_current_and_run_next();
+                continue;
+            }
+            let loop_read = avail.min(want - read);
+            for _ in 0..loop_read {
+                chunk[read] = ring_buffer.read_byte();
+                read += 1;
+            }
+            break;
+        }
+        if read == 0 {
+            break;
+        }
+        let written = inode.write_at(cursor, &chunk[..read]);
+        assert_eq!(written, read);
+        cursor += written;
+        total += written;
+    }
+    total as isize
+}
+
+/// Create an `EventFd` counter fd seeded at `initval` and install it in the
+/// calling task's fd table, returning the new fd.
+pub fn sys_eventfd(initval: u64) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let Some(fd) = inner.alloc_fd() else {
+        drop(inner);
+        return fd_limit_hit(&task);
+    };
+    inner.fd_table.exclusive_access()[fd] = Some(Arc::new(EventFd::new(initval)));
+    fd as isize
+}
+
+/// Create an anonymous, RAM-backed file and return a readable+writable fd
+/// to it, per `sys_eventfd`/`sys_epoll_create`'s usual fd-installation
+/// pattern. `name` is read (it must still be a valid NUL-terminated
+/// string) but otherwise unused: the fd is never entered into the
+/// directory tree, so there's nowhere to store or show a name, and
+/// nothing to unlink later. The file is reclaimed as soon as every fd
+/// referencing it is closed.
+pub fn sys_memfd_create(name: *const u8) -> isize {
+    let token = current_user_token();
+    let _name = translated_str(token, name);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let Some(fd) = inner.alloc_fd() else {
+        drop(inner);
+        return fd_limit_hit(&task);
+    };
+    inner.fd_table.exclusive_access()[fd] = Some(Arc::new(RamFile::new()));
+    fd as isize
+}
+
+/// Resize the file open at `fd` to exactly `len` bytes, per
+/// `File::ftruncate`. Returns -1 if `fd` isn't open or doesn't support
+/// being resized this way (currently only a `sys_memfd_create` fd does).
+pub fn sys_ftruncate(fd: usize, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    if file.ftruncate(len) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Set `owner`'s block quota, in data blocks, on the filesystem rooted at
+/// `ROOT_INODE` — see `easy_fs::EasyFileSystem::set_quota`. There's only
+/// ever one mounted filesystem in this kernel, so unlike `sys_ftruncate`
+/// there's no fd to route this through; it always applies to `ROOT_INODE`'s
+/// filesystem. Always succeeds.
+pub fn sys_setquota(owner: usize, blocks: usize) -> isize {
+    ROOT_INODE.set_quota(owner as u32, blocks as u32);
+    0
+}
+
+/// Reassign the quota-tracking owner id of the `easy-fs` inode backing
+/// `fd`, per `File::set_owner`. Returns -1 if `fd` isn't open or has no
+/// backing inode to reassign (a pipe, `Stdin`/`Stdout`, or a
+/// `sys_memfd_create` fd, none of which are subject to quotas).
+pub fn sys_set_owner(fd: usize, owner: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    if file.set_owner(owner as u32) {
+        0
+    } else {
+        -1
+    }
+}
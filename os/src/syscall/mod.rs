@@ -0,0 +1,207 @@
+const SYSCALL_DUP: usize = 24;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_READ: usize = 63;
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_SYNC: usize = 81;
+const SYSCALL_FSYNC: usize = 82;
+const SYSCALL_FDATASYNC: usize = 83;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_STAT: usize = 79;
+const SYSCALL_LINKAT: usize = 37;
+const SYSCALL_UNLINKAT: usize = 35;
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_PRCTL: usize = 167;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_MUTEX_CREATE: usize = 1010;
+const SYSCALL_MUTEX_LOCK: usize = 1011;
+const SYSCALL_MUTEX_UNLOCK: usize = 1012;
+const SYSCALL_MUTEX_TRY_LOCK: usize = 1013;
+const SYSCALL_SEMAPHORE_CREATE: usize = 1020;
+const SYSCALL_SEMAPHORE_UP: usize = 1021;
+const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
+const SYSCALL_SEMAPHORE_DESTROY: usize = 1023;
+const SYSCALL_CONDVAR_CREATE: usize = 1030;
+const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
+const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_EVENTFD: usize = 1040;
+const SYSCALL_MEMBARRIER: usize = 1041;
+const SYSCALL_GETDENTS: usize = 1042;
+const SYSCALL_SYNC_FILE_RANGE: usize = 1043;
+const SYSCALL_CANCEL_TOKEN_CREATE: usize = 1044;
+const SYSCALL_CANCEL_TOKEN_CANCEL: usize = 1045;
+const SYSCALL_CONDVAR_WAIT_CANCELLABLE: usize = 1046;
+const SYSCALL_CLOCK_GETTIME_NS: usize = 1050;
+const SYSCALL_MKNOD: usize = 1051;
+const SYSCALL_SCHED_SETSCHEDULER: usize = 1052;
+const SYSCALL_POLL: usize = 1053;
+const SYSCALL_TEE: usize = 1054;
+const SYSCALL_GETRANDOM: usize = 1055;
+const SYSCALL_FLOCK: usize = 1056;
+const SYSCALL_REBOOT: usize = 1057;
+const SYSCALL_PREADV: usize = 1058;
+const SYSCALL_PWRITEV: usize = 1059;
+const SYSCALL_SETPRIORITY: usize = 1060;
+const SYSCALL_GETPRIORITY: usize = 1061;
+const SYSCALL_MINCORE: usize = 1062;
+const SYSCALL_SIGACTION: usize = 1063;
+const SYSCALL_SIGRETURN: usize = 1064;
+const SYSCALL_OPENAT: usize = 1065;
+const SYSCALL_PEEK: usize = 1066;
+const SYSCALL_SPLICE: usize = 1067;
+const SYSCALL_SET_THREAD_NAME: usize = 1068;
+const SYSCALL_GET_THREAD_NAME: usize = 1069;
+const SYSCALL_CLOCK_NANOSLEEP: usize = 1070;
+const SYSCALL_YIELD_TO: usize = 1071;
+const SYSCALL_SET_PIPE_WRITE_TIMEOUT: usize = 1072;
+const SYSCALL_CLONE: usize = 1073;
+const SYSCALL_SCHED_STATS: usize = 1074;
+const SYSCALL_EPOLL_CREATE: usize = 1075;
+const SYSCALL_EPOLL_CTL: usize = 1076;
+const SYSCALL_EPOLL_WAIT: usize = 1077;
+const SYSCALL_FPUNCH_HOLE: usize = 1078;
+const SYSCALL_FUTEX_WAIT: usize = 1079;
+const SYSCALL_FUTEX_WAKE: usize = 1080;
+const SYSCALL_FD_STATS: usize = 1081;
+const SYSCALL_FEXECVE: usize = 1082;
+const SYSCALL_GETDENTS_FILTERED: usize = 1083;
+const SYSCALL_MEMFD_CREATE: usize = 1084;
+const SYSCALL_FTRUNCATE: usize = 1085;
+const SYSCALL_SCHED_SETAFFINITY: usize = 1086;
+const SYSCALL_SCHED_GETAFFINITY: usize = 1087;
+const SYSCALL_SETQUOTA: usize = 1088;
+const SYSCALL_SET_OWNER: usize = 1089;
+const SYSCALL_GETTID: usize = 1090;
+const SYSCALL_GETRUSAGE: usize = 1091;
+const SYSCALL_CLOSE_RANGE: usize = 1092;
+const SYSCALL_SETRLIMIT: usize = 1093;
+const SYSCALL_GETRLIMIT: usize = 1094;
+
+mod fs;
+mod process;
+mod sync;
+
+use crate::fs::{EpollEvent, IoVec, PollFd};
+use crate::task::SchedStats;
+use fs::*;
+use process::*;
+use sync::*;
+
+pub fn syscall(syscall_id: usize, args: [usize; 5]) -> isize {
+    match syscall_id {
+        SYSCALL_DUP => sys_dup(args[0]),
+        SYSCALL_OPEN => sys_open(args[0] as *const u8, args[1] as u32),
+        SYSCALL_OPENAT => sys_openat(args[0] as isize, args[1] as *const u8, args[2] as u32),
+        SYSCALL_CLOSE => sys_close(args[0]),
+        SYSCALL_PIPE => sys_pipe(args[0] as *mut usize),
+        SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_SYNC => sys_sync(),
+        SYSCALL_FSYNC => sys_fsync(args[0]),
+        SYSCALL_FDATASYNC => sys_fdatasync(args[0]),
+        SYSCALL_FSTAT => sys_fstat(args[0], args[1] as *mut u8),
+        SYSCALL_STAT => sys_stat(args[0] as *const u8, args[1] as *mut u8),
+        SYSCALL_LINKAT => sys_linkat(args[1] as *const u8, args[3] as *const u8),
+        SYSCALL_UNLINKAT => sys_unlinkat(args[1] as *const u8),
+        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_YIELD => sys_yield(),
+        SYSCALL_KILL => sys_kill(args[0], args[1] as u32),
+        SYSCALL_GET_TIME => sys_get_time(),
+        SYSCALL_TIMES => sys_times(args[0] as *mut TimeStat),
+        SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_SBRK => sys_sbrk(args[0] as i32),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_FORK => sys_fork(),
+        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2], args[3], args[4]),
+        SYSCALL_WAITPID => sys_waitpid(
+            args[0] as isize,
+            args[1] as *mut i32,
+            args[2] as *mut ChildRusage,
+        ),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_MUTEX_CREATE => sys_mutex_create(args[0] == 1),
+        SYSCALL_MUTEX_LOCK => sys_mutex_lock(args[0]),
+        SYSCALL_MUTEX_UNLOCK => sys_mutex_unlock(args[0]),
+        SYSCALL_MUTEX_TRY_LOCK => sys_mutex_try_lock(args[0]),
+        SYSCALL_SEMAPHORE_CREATE => sys_semaphore_create(args[0]),
+        SYSCALL_SEMAPHORE_UP => sys_semaphore_up(args[0]),
+        SYSCALL_SEMAPHORE_DOWN => sys_semaphore_down(args[0]),
+        SYSCALL_SEMAPHORE_DESTROY => sys_semaphore_destroy(args[0]),
+        SYSCALL_CONDVAR_CREATE => sys_condvar_create(),
+        SYSCALL_CONDVAR_SIGNAL => sys_condvar_signal(args[0]),
+        SYSCALL_CONDVAR_WAIT => sys_condvar_wait(args[0], args[1]),
+        SYSCALL_EVENTFD => sys_eventfd(args[0] as u64),
+        SYSCALL_MEMBARRIER => sys_membarrier(),
+        SYSCALL_GETDENTS => sys_getdents(args[0], args[1] as *mut u8, args[2]),
+        SYSCALL_SYNC_FILE_RANGE => sys_sync_file_range(args[0], args[1], args[2]),
+        SYSCALL_CANCEL_TOKEN_CREATE => sys_cancel_token_create(),
+        SYSCALL_CANCEL_TOKEN_CANCEL => sys_cancel_token_cancel(args[0], args[1]),
+        SYSCALL_CONDVAR_WAIT_CANCELLABLE => sys_condvar_wait_cancellable(args[0], args[1], args[2]),
+        SYSCALL_CLOCK_GETTIME_NS => sys_clock_gettime_ns(args[0], args[1] as *mut u64),
+        SYSCALL_MKNOD => sys_mknod(args[0] as *const u8, args[1] as u32),
+        SYSCALL_SCHED_SETSCHEDULER => sys_sched_setscheduler(args[0], args[1]),
+        SYSCALL_POLL => sys_poll(args[0] as *mut PollFd, args[1], args[2] as isize),
+        SYSCALL_TEE => sys_tee(args[0], args[1], args[2]),
+        SYSCALL_SPLICE => sys_splice(args[0], args[1], args[2], args[3], args[4]),
+        SYSCALL_GETRANDOM => sys_getrandom(args[0] as *const u8, args[1]),
+        SYSCALL_FLOCK => sys_flock(args[0], args[1] as u32),
+        SYSCALL_REBOOT => sys_reboot(args[0]),
+        SYSCALL_PREADV => sys_preadv(args[0], args[1] as *const IoVec, args[2], args[3]),
+        SYSCALL_PWRITEV => sys_pwritev(args[0], args[1] as *const IoVec, args[2], args[3]),
+        SYSCALL_SETPRIORITY => sys_setpriority(args[0], args[1] as isize),
+        SYSCALL_GETPRIORITY => sys_getpriority(args[0]),
+        SYSCALL_PEEK => sys_peek(args[0], args[1], args[2] as *mut u8, args[3]),
+        SYSCALL_SET_THREAD_NAME => sys_set_thread_name(args[0] as *const u8),
+        SYSCALL_GET_THREAD_NAME => sys_get_thread_name(args[0], args[1] as *mut u8),
+        SYSCALL_CLOCK_NANOSLEEP => sys_clock_nanosleep(args[0], args[1], args[2] as *const u64),
+        SYSCALL_YIELD_TO => sys_yield_to(args[0]),
+        SYSCALL_SET_PIPE_WRITE_TIMEOUT => sys_set_pipe_write_timeout(args[0], args[1] as isize),
+        SYSCALL_MINCORE => sys_mincore(args[0], args[1], args[2] as *mut u8),
+        SYSCALL_SIGACTION => sys_sigaction(args[0] as u32, args[1]),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        SYSCALL_PRCTL => sys_prctl(args[0], args[1]),
+        SYSCALL_CLONE => sys_clone(args[0], args[1]),
+        SYSCALL_SCHED_STATS => sys_sched_stats(args[0] as *mut SchedStats),
+        SYSCALL_EPOLL_CREATE => sys_epoll_create(),
+        SYSCALL_EPOLL_CTL => sys_epoll_ctl(args[0], args[1], args[2], args[3] as *const EpollEvent),
+        SYSCALL_EPOLL_WAIT => sys_epoll_wait(
+            args[0],
+            args[1] as *mut EpollEvent,
+            args[2],
+            args[3] as isize,
+        ),
+        SYSCALL_FPUNCH_HOLE => sys_fpunch_hole(args[0], args[1], args[2]),
+        SYSCALL_FUTEX_WAIT => sys_futex_wait(args[0], args[1] as u32),
+        SYSCALL_FUTEX_WAKE => sys_futex_wake(args[0], args[1]),
+        SYSCALL_FD_STATS => sys_fd_stats(args[0], args[1] as *mut u8),
+        SYSCALL_FEXECVE => sys_fexecve(args[0]),
+        SYSCALL_GETDENTS_FILTERED => {
+            sys_getdents_filtered(args[0], args[1] as *mut u8, args[2], args[3] as i32)
+        }
+        SYSCALL_MEMFD_CREATE => sys_memfd_create(args[0] as *const u8),
+        SYSCALL_FTRUNCATE => sys_ftruncate(args[0], args[1]),
+        SYSCALL_SCHED_SETAFFINITY => sys_sched_setaffinity(args[0], args[1]),
+        SYSCALL_SCHED_GETAFFINITY => sys_sched_getaffinity(args[0]),
+        SYSCALL_SETQUOTA => sys_setquota(args[0], args[1]),
+        SYSCALL_SET_OWNER => sys_set_owner(args[0], args[1]),
+        SYSCALL_GETTID => sys_gettid(),
+        SYSCALL_GETRUSAGE => sys_getrusage(args[0] as *mut MemStat),
+        SYSCALL_CLOSE_RANGE => sys_close_range(args[0], args[1], args[2] as u32),
+        SYSCALL_SETRLIMIT => sys_setrlimit(args[0], args[1]),
+        SYSCALL_GETRLIMIT => sys_getrlimit(args[0], args[1] as *mut usize),
+        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+    }
+}
@@ -0,0 +1,750 @@
+use crate::config::{NUM_CPUS, PAGE_SIZE};
+use crate::fs::{open_file, File, OpenFlags};
+use crate::mm::{
+    read_user, translated_byte_buffer, translated_ref, translated_refmut, translated_str,
+    try_translated_byte_buffer, write_user, MapPermission, MmapBacking, UserBuffer, VirtAddr,
+    VirtPageNum,
+};
+use crate::random::fill_random;
+use crate::task::{
+    add_task, current_task, current_user_token, drain_tasks, exit_current_and_run_next, find_task,
+    register_task, remove_queued_task, sched_stats, set_preferred_task,
+    suspend_current_and_run_next, CloneFlags, ProcFlags, SchedPolicy, SchedStats, TaskStatus,
+    SIGUSR, THREAD_NAME_LENGTH_LIMIT,
+};
+use crate::timer::{get_time_ms, get_time_ns};
+use crate::trap::TrapContext;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub fn sys_exit(exit_code: i32) -> isize {
+    exit_current_and_run_next(exit_code);
+    unreachable!()
+}
+
+pub fn sys_yield() -> isize {
+    suspend_current_and_run_next();
+    0
+}
+
+/// Like `sys_yield`, but hand the CPU straight to `pid` on the very next
+/// switch instead of going through the back of the normal ready/FIFO
+/// queues. `pid` must currently be sitting in one of those queues (i.e.
+/// runnable but not running or blocked off-queue); returns -1 and yields
+/// normally instead if it isn't, since there's then nothing to direct the
+/// handoff to.
+pub fn sys_yield_to(pid: usize) -> isize {
+    let Some(task) = remove_queued_task(pid) else {
+        suspend_current_and_run_next();
+        return -1;
+    };
+    set_preferred_task(task);
+    suspend_current_and_run_next();
+    0
+}
+
+/// Mark `pid` as having a pending signal. Beyond `SIGUSR`, this kernel
+/// doesn't distinguish signal numbers or deliver handlers; `signum` is
+/// otherwise ignored, and the only observable effect is that a blocking
+/// wait the target is in (currently `sys_poll` or `sys_epoll_wait`) returns early. If
+/// `signum` is `SIGUSR` and `pid` has registered a handler via
+/// `sys_sigaction`, it also runs that handler the next time `pid` returns
+/// to user space. Returns -1 if no task with that pid exists.
+pub fn sys_kill(pid: usize, signum: u32) -> isize {
+    let Some(task) = find_task(pid) else {
+        return -1;
+    };
+    let mut inner = task.inner_exclusive_access();
+    inner.pending_signal = true;
+    if signum == SIGUSR {
+        inner.sigusr_pending = true;
+    }
+    0
+}
+
+/// Register `handler` to run in user space the next time `SIGUSR` is
+/// delivered to the calling task via `sys_kill`, in place of the default
+/// "just wake up a blocking wait" behavior. `handler` is a code address
+/// taking the signal number in `a0`; it must end by calling `sigreturn`
+/// rather than returning normally. Passing a `handler` of 0 clears any
+/// registered handler. Returns -1 for any signal other than `SIGUSR`.
+pub fn sys_sigaction(signo: u32, handler: usize) -> isize {
+    if signo != SIGUSR {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().sigusr_handler = if handler == 0 { None } else { Some(handler) };
+    0
+}
+
+/// Undo the trap context diversion `trap_handler` performed to run a
+/// `SIGUSR` handler, resuming exactly where delivery interrupted the task.
+/// Pops the `TrapContext` `trap_handler` pushed onto the user stack at the
+/// handler's `sp` — the two must agree on that layout, since this is the
+/// only place it's read back. Must be the last thing a `SIGUSR` handler
+/// calls. Returns -1 if no handler is currently running, though the
+/// caller never observes it: a successful call never returns to its own
+/// call site at all.
+pub fn sys_sigreturn() -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !inner.in_sigusr_handler {
+        return -1;
+    }
+    let token = inner.user_token();
+    let sp = inner.trap_cx().x[2];
+    let restored: TrapContext = read_user(token, sp as *const TrapContext);
+    inner.in_sigusr_handler = false;
+    let original_a0 = restored.x[10];
+    *inner.trap_cx() = restored;
+    original_a0 as isize
+}
+
+/// `option` for `sys_prctl`: read the calling task's `ProcFlags` bits
+/// (`arg` ignored). Returns the flags as a bit pattern.
+pub const PR_GET_PROC_FLAGS: usize = 1;
+/// `option` for `sys_prctl`: replace the calling task's `ProcFlags` with
+/// `arg`'s bit pattern. Unknown bits are rejected.
+pub const PR_SET_PROC_FLAGS: usize = 2;
+
+/// Umbrella syscall for reading/writing the small set of per-process
+/// behavior flags in `ProcFlags` (see its doc comment for what each bit
+/// does). Unlike most of this kernel's syscalls, `option` isn't a real
+/// Linux `prctl(2)` option number — this kernel only implements its own
+/// flag bits, not the real ones. Returns -1 for an unrecognized `option`
+/// or, for `PR_SET_PROC_FLAGS`, an `arg` with bits outside `ProcFlags`.
+pub fn sys_prctl(option: usize, arg: usize) -> isize {
+    let task = current_task().unwrap();
+    match option {
+        PR_GET_PROC_FLAGS => task.inner_exclusive_access().proc_flags.bits() as isize,
+        PR_SET_PROC_FLAGS => {
+            let Some(flags) = ProcFlags::from_bits(arg as u32) else {
+                return -1;
+            };
+            task.inner_exclusive_access().proc_flags = flags;
+            0
+        }
+        _ => -1,
+    }
+}
+
+pub fn sys_get_time() -> isize {
+    get_time_ms() as isize
+}
+
+/// Report the hardware timer in nanoseconds rather than `sys_get_time`'s
+/// milliseconds, for callers that need sub-microsecond resolution.
+/// `clock_id` is accepted but ignored; this kernel has only one clock.
+pub fn sys_clock_gettime_ns(_clock_id: usize, ns: *mut u64) -> isize {
+    *translated_refmut(current_user_token(), ns) = get_time_ns();
+    0
+}
+
+/// Flag for `sys_clock_nanosleep`: `deadline_ns` names an absolute point
+/// on the monotonic clock rather than a duration relative to now.
+pub const TIMER_ABSTIME: usize = 1;
+
+/// Sleep until `deadline_ns` nanoseconds on the clock `sys_clock_gettime_ns`
+/// reads, if `flags` has `TIMER_ABSTIME` set, or for `deadline_ns`
+/// nanoseconds from now otherwise. Unlike repeatedly sleeping a fixed
+/// relative duration, sleeping to an absolute deadline doesn't accumulate
+/// drift from whatever time each previous sleep actually took, since every
+/// call re-reads the clock rather than adding onto an estimate. Returns
+/// immediately if the deadline has already passed. `clock_id` is accepted
+/// but ignored, like `sys_clock_gettime_ns`.
+pub fn sys_clock_nanosleep(_clock_id: usize, flags: usize, deadline_ns: *const u64) -> isize {
+    let deadline_ns = *translated_ref(current_user_token(), deadline_ns);
+    let deadline_ns = if flags & TIMER_ABSTIME != 0 {
+        deadline_ns
+    } else {
+        get_time_ns() + deadline_ns
+    };
+    while get_time_ns() < deadline_ns {
+        suspend_current_and_run_next();
+    }
+    0
+}
+
+/// User/kernel time split reported by `sys_times`, both in microseconds.
+#[repr(C)]
+pub struct TimeStat {
+    pub utime: usize,
+    pub stime: usize,
+}
+
+/// Report the calling task's accumulated user and kernel time.
+pub fn sys_times(ts: *mut TimeStat) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let stat = TimeStat {
+        utime: inner.user_time,
+        stime: inner.kernel_time,
+    };
+    drop(inner);
+    *translated_refmut(current_user_token(), ts) = stat;
+    0
+}
+
+/// Report the ready queue's current length and longest wait, for
+/// diagnosing scheduling pathologies (e.g. a task stuck behind a busy
+/// `Fifo` task or a stream of directed handoffs).
+pub fn sys_sched_stats(stats: *mut SchedStats) -> isize {
+    *translated_refmut(current_user_token(), stats) = sched_stats();
+    0
+}
+
+/// Memory footprint reported by `sys_getrusage`, both in pages.
+#[repr(C)]
+pub struct MemStat {
+    /// Resident set size: pages currently backed by a physical frame.
+    pub rss_pages: usize,
+    /// Virtual size: pages reserved across every area, including a lazy
+    /// mmap area's pages that haven't been faulted in yet.
+    pub vsize_pages: usize,
+}
+
+/// Report the calling task's memory footprint; see `MemStat`. Backed by
+/// `MemorySet::rss_pages`/`vsize_pages`, which derive these live from the
+/// page table and each area's `data_frames` rather than a separately
+/// maintained counter, so there's nothing for `mmap`/`munmap`/the lazy
+/// fault handler/`fork` to keep in sync by hand.
+pub fn sys_getrusage(stat: *mut MemStat) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mem_stat = MemStat {
+        rss_pages: inner.memory_set.rss_pages(),
+        vsize_pages: inner.memory_set.vsize_pages(),
+    };
+    drop(inner);
+    *translated_refmut(current_user_token(), stat) = mem_stat;
+    0
+}
+
+pub fn sys_getpid() -> isize {
+    current_task().unwrap().pid.0 as isize
+}
+
+/// Always returns 0. This kernel has no real thread model yet — no
+/// `sys_thread_create`, no `TaskControlBlock` shared between tids within a
+/// process — so every task is its own process's one and only thread, and
+/// that thread is canonically tid 0 regardless of its (globally unique,
+/// never-reused-while-live) pid. Kept distinct from `sys_getpid` so
+/// callers that already assume Linux's "main thread's tid equals its
+/// pid" convention don't silently get the wrong number back from here.
+pub fn sys_gettid() -> isize {
+    0
+}
+
+/// Returns -1, instead of forking, if `MAX_PROCESS_COUNT` processes are
+/// already alive — a fork bomb hits this ceiling instead of growing the
+/// kernel's pid/task bookkeeping without bound.
+pub fn sys_fork() -> isize {
+    let current_task = current_task().unwrap();
+    let Some(new_task) = current_task.fork() else {
+        return -1;
+    };
+    let new_pid = new_task.pid.0;
+    let trap_cx = new_task.inner_exclusive_access().trap_cx();
+    trap_cx.x[10] = 0;
+    register_task(&new_task);
+    add_task(new_task);
+    new_pid as isize
+}
+
+/// Generalized `fork`: `flags` picks which resources the child shares with
+/// the parent instead of copying, subsuming both the traditional `fork`
+/// (everything copied, `flags == 0`) and a thread-style spawn (address
+/// space shared) into one call. Currently only `CloneFlags::FILES` (share
+/// the fd table) is backed by anything real; this kernel has no thread
+/// abstraction to share an address space with, so `CloneFlags::VM` is
+/// rejected rather than silently ignored. `stack` is accepted for
+/// source-compatibility with a POSIX-style `clone(2)` signature but is
+/// unused until address-space sharing exists to give it meaning. Returns
+/// -1, same as `sys_fork`, if `MAX_PROCESS_COUNT` processes are already
+/// alive.
+pub fn sys_clone(flags: usize, _stack: usize) -> isize {
+    let Some(flags) = CloneFlags::from_bits(flags) else {
+        return -1;
+    };
+    if flags.contains(CloneFlags::VM) {
+        return -1;
+    }
+    let current_task = current_task().unwrap();
+    let Some(new_task) = current_task.clone_with(flags) else {
+        return -1;
+    };
+    let new_pid = new_task.pid.0;
+    let trap_cx = new_task.inner_exclusive_access().trap_cx();
+    trap_cx.x[10] = 0;
+    register_task(&new_task);
+    add_task(new_task);
+    new_pid as isize
+}
+
+pub fn sys_exec(path: *const u8) -> isize {
+    let token = current_user_token();
+    let path = translated_str(token, path);
+    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
+        let all_data = app_inode.read_all();
+        let task = current_task().unwrap();
+        task.exec(all_data.as_slice());
+        0
+    } else {
+        -1
+    }
+}
+
+/// Like `sys_exec`, but executes the ELF already open at `fd` instead of
+/// resolving a path, so a caller that opened, verified, and wants to run
+/// exactly that inode isn't exposed to a TOCTOU race against whatever the
+/// path now resolves to. Works just as well on an unlinked-but-open fd,
+/// since it never touches the directory entry again. Returns -1 if `fd`
+/// isn't open, isn't readable, or doesn't refer to a regular file.
+pub fn sys_fexecve(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    let Some(Some(file)) = fd_table.get(fd) else {
+        return -1;
+    };
+    if !file.readable() {
+        return -1;
+    }
+    let Some(inode) = file.inode() else {
+        return -1;
+    };
+    drop(fd_table);
+    drop(inner);
+    if inode.is_dir() || inode.is_fifo() || inode.is_device() {
+        return -1;
+    }
+    let all_data = inode.read_all();
+    task.exec(all_data.as_slice());
+    0
+}
+
+/// Resource usage reported for a reaped child through `sys_waitpid`'s
+/// optional `rusage_ptr`. `utime`/`stime`/`switch_count` are read straight
+/// out of the zombie's own `TaskControlBlockInner`, which `exit_current_and_run_next`
+/// never clears. `block_io_count` is always 0: the block cache
+/// (`easy-fs`'s `BlockCacheManager`) only tracks I/O globally, with no
+/// per-task attribution to read back here.
+#[repr(C)]
+pub struct ChildRusage {
+    pub utime: usize,
+    pub stime: usize,
+    pub switch_count: usize,
+    pub block_io_count: usize,
+}
+
+/// Wait for a child (`pid == -1` for any child, otherwise that specific
+/// pid) to become a zombie, reap it, and report its exit code through
+/// `exit_code_ptr`. If `rusage_ptr` is non-null, also writes the reaped
+/// child's accumulated CPU time and voluntary context switches through it.
+/// Returns -1 if `pid` doesn't name a current child at all; blocks until
+/// one does exit otherwise.
+///
+/// The re-check of `inner.children` for a zombie happens every time around
+/// this loop, each time under a fresh acquisition of this task's own PCB
+/// lock — there's no separate "register as waiting" step a child's exit
+/// could race against, so a child that becomes a zombie between one
+/// iteration and the next is simply caught by the following one instead of
+/// being missed. Same idiom as `sys_poll`/`sys_epoll_wait`.
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, rusage_ptr: *mut ChildRusage) -> isize {
+    let task = current_task().unwrap();
+    loop {
+        let mut inner = task.inner_exclusive_access();
+        if !inner
+            .children
+            .iter()
+            .any(|p| pid == -1 || pid as usize == p.getpid())
+        {
+            return -1;
+        }
+        let pair = inner.children.iter().enumerate().find(|(_, p)| {
+            p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
+        });
+        if let Some((idx, _)) = pair {
+            let child = inner.children.remove(idx);
+            assert_eq!(Arc::strong_count(&child), 1);
+            let found_pid = child.getpid();
+            let child_inner = child.inner_exclusive_access();
+            let exit_code = child_inner.exit_code;
+            if !rusage_ptr.is_null() {
+                let rusage = ChildRusage {
+                    utime: child_inner.user_time,
+                    stime: child_inner.kernel_time,
+                    switch_count: child_inner.switch_count,
+                    block_io_count: 0,
+                };
+                drop(child_inner);
+                *translated_refmut(inner.memory_set.token(), rusage_ptr) = rusage;
+            } else {
+                drop(child_inner);
+            }
+            *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
+            return found_pid as isize;
+        }
+        drop(inner);
+        suspend_current_and_run_next();
+    }
+}
+
+/// The only resource `sys_setrlimit`/`sys_getrlimit` know about: the heap,
+/// grown by `sys_sbrk`. Named after Linux's `RLIMIT_DATA`, though this
+/// kernel has nothing like its full `rlimit` family — just this one knob.
+pub const RLIMIT_DATA: usize = 0;
+
+/// Set the calling task's limit on resource `resource`, in bytes. Only
+/// `RLIMIT_DATA` (the heap) is recognized; anything else returns -1
+/// without touching any state.
+pub fn sys_setrlimit(resource: usize, limit: usize) -> isize {
+    if resource != RLIMIT_DATA {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.heap_limit = limit;
+    0
+}
+
+/// Report the calling task's current limit on resource `resource` through
+/// `limit_ptr`. Only `RLIMIT_DATA` is recognized; anything else returns -1
+/// without writing to `limit_ptr`.
+pub fn sys_getrlimit(resource: usize, limit_ptr: *mut usize) -> isize {
+    if resource != RLIMIT_DATA {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let limit = inner.heap_limit;
+    let token = inner.memory_set.token();
+    drop(inner);
+    *translated_refmut(token, limit_ptr) = limit;
+    0
+}
+
+/// Grow or shrink the heap by `size` bytes, returning the old break, or -1
+/// if `size` is negative enough to move the break below `heap_bottom` or
+/// positive enough to grow it past `heap_limit` (see `sys_setrlimit`).
+/// Either way the task keeps running — this only ever fails the one
+/// `sbrk` call, never the caller itself.
+pub fn sys_sbrk(size: i32) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let old_brk = inner.program_brk;
+    let new_brk = old_brk as isize + size as isize;
+    if new_brk < inner.heap_bottom as isize {
+        return -1;
+    }
+    if new_brk as usize - inner.heap_bottom > inner.heap_limit {
+        return -1;
+    }
+    inner.program_brk = new_brk as usize;
+    old_brk as isize
+}
+
+/// `sys_mmap` flag selecting an anonymous, zero-filled mapping rather than
+/// one backed by an open file.
+pub const MAP_ANONYMOUS: usize = 1 << 0;
+
+/// Map `len` bytes at `start`, which must be page-aligned, filled in
+/// lazily on first access (or all at once up front, if the calling task
+/// has `ProcFlags::MMAP_EAGER` set). `prot` is a `MapPermission` bit pattern (`R`,
+/// `W`, `X`); `U` is added automatically. If `flags` has `MAP_ANONYMOUS`
+/// set, pages are zero-filled; otherwise they're copied from `fd`'s file
+/// starting at `start`'s offset into the mapping.
+pub fn sys_mmap(start: usize, len: usize, prot: usize, flags: usize, fd: usize) -> isize {
+    if start % PAGE_SIZE != 0 || prot == 0 || prot & !0b111 != 0 {
+        return -1;
+    }
+    let Some(map_perm) = MapPermission::from_bits((prot as u8) << 1) else {
+        return -1;
+    };
+    let map_perm = map_perm | MapPermission::U;
+    let backing = if flags & MAP_ANONYMOUS != 0 {
+        MmapBacking::Anonymous
+    } else {
+        let task = current_task().unwrap();
+        let inner = task.inner_exclusive_access();
+        let fd_table = inner.fd_table.exclusive_access();
+        let Some(Some(file)) = fd_table.get(fd) else {
+            return -1;
+        };
+        let Some(inode) = file.inode() else {
+            return -1;
+        };
+        drop(fd_table);
+        drop(inner);
+        MmapBacking::File(inode, 0)
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let eager = inner.proc_flags.contains(ProcFlags::MMAP_EAGER);
+    inner.memory_set.insert_mmap_area(
+        VirtAddr::from(start),
+        VirtAddr::from(start + len),
+        map_perm,
+        backing,
+        eager,
+    );
+    start as isize
+}
+
+/// Unmap a region previously returned by `sys_mmap`. Like the reference
+/// `mmap` lab, `start` must exactly match a prior mapping's start address.
+pub fn sys_munmap(start: usize, _len: usize) -> isize {
+    if start % PAGE_SIZE != 0 {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner
+        .memory_set
+        .remove_area_with_start_vpn(VirtAddr::from(start).floor());
+    0
+}
+
+/// Report which pages of `[start, start + len)` are currently backed by a
+/// physical frame — a page a lazy `mmap` hasn't been touched into yet
+/// reports as not resident. Writes one byte per page to `vec_ptr` (1
+/// resident, 0 not), in page order. Purely a page table query; never
+/// allocates or touches a mapping. Returns the number of bytes written, or
+/// -1 if `start` isn't page-aligned.
+pub fn sys_mincore(start: usize, len: usize, vec_ptr: *mut u8) -> isize {
+    if start % PAGE_SIZE != 0 {
+        return -1;
+    }
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let start_vpn = VirtAddr::from(start).floor();
+    let page_count = len.div_ceil(PAGE_SIZE);
+    let mut resident = vec![0u8; page_count];
+    for (i, byte) in resident.iter_mut().enumerate() {
+        let vpn = VirtPageNum(start_vpn.0 + i);
+        if inner
+            .memory_set
+            .translate(vpn)
+            .is_some_and(|pte| pte.is_valid())
+        {
+            *byte = 1;
+        }
+    }
+    drop(inner);
+    let mut out = UserBuffer::new(translated_byte_buffer(token, vec_ptr, page_count)).into_iter();
+    for byte in resident {
+        match out.next() {
+            Some(dst) => unsafe { *dst = byte },
+            None => break,
+        }
+    }
+    page_count as isize
+}
+
+/// Issue a full memory fence, so that a task sharing memory with another
+/// can rely on its prior writes being visible before any later access.
+/// Single-core today, so this just orders this hart's own memory accesses;
+/// once SMP lands it's the hook where cross-hart coordination would go.
+pub fn sys_membarrier() -> isize {
+    unsafe {
+        core::arch::asm!("fence rw, rw");
+    }
+    0
+}
+
+/// Fill `buf` with `len` pseudorandom bytes from the kernel's PRNG. Not
+/// cryptographically secure — see `random::fill_random` — but enough for
+/// hash seeds and test data. Always fills the full length requested.
+pub fn sys_getrandom(buf: *const u8, len: usize) -> isize {
+    let token = current_user_token();
+    let mut bytes = vec![0u8; len];
+    fill_random(&mut bytes);
+    let mut written = UserBuffer::new(translated_byte_buffer(token, buf, len)).into_iter();
+    for byte in bytes {
+        match written.next() {
+            Some(dst) => unsafe { *dst = byte },
+            None => break,
+        }
+    }
+    len as isize
+}
+
+/// Flush every buffered filesystem block to disk and shut the machine down,
+/// unlike the panic the idle process's exit otherwise falls through to.
+/// Only the init process (pid 0) may call this; `cmd` is accepted but
+/// ignored, since this kernel has nothing to distinguish a reboot from a
+/// halt. Every task still sitting on the ready queue is drained, marked a
+/// zombie, and has its resources flushed first, so nothing is left half
+/// torn down. Never returns on success; returns -1 if the caller isn't pid 0.
+pub fn sys_reboot(_cmd: usize) -> isize {
+    if current_task().unwrap().getpid() != 0 {
+        return -1;
+    }
+    for task in drain_tasks() {
+        let mut inner = task.inner_exclusive_access();
+        inner.task_status = TaskStatus::Zombie;
+        inner.fd_table.exclusive_access().clear();
+        crate::fs::release_flocks_for_pid(task.getpid());
+    }
+    easy_fs::block_cache_sync_all();
+    crate::sbi::shutdown();
+}
+
+pub fn sys_set_priority(prio: isize) -> isize {
+    if prio < 2 {
+        -1
+    } else {
+        prio
+    }
+}
+
+/// Like `sys_set_priority`, but by pid rather than the calling task, so a
+/// supervisor can tune another process's scheduling priority. Takes effect
+/// the next time `pid` is placed back on the ready queue. Returns -1 if
+/// `prio < 2` or no task with that pid exists.
+pub fn sys_setpriority(pid: usize, prio: isize) -> isize {
+    if prio < 2 {
+        return -1;
+    }
+    let Some(task) = find_task(pid) else {
+        return -1;
+    };
+    task.inner_exclusive_access().priority = prio as usize;
+    0
+}
+
+/// Read `pid`'s current scheduling priority, as last set by
+/// `sys_setpriority` or `sys_sched_setscheduler`. Returns -1 if no task
+/// with that pid exists.
+pub fn sys_getpriority(pid: usize) -> isize {
+    let Some(task) = find_task(pid) else {
+        return -1;
+    };
+    task.inner_exclusive_access().priority as isize
+}
+
+/// Read `len` bytes out of `pid`'s address space at `remote_addr` into the
+/// caller's own `local_buf`, the kernel support a `ptrace`-lite debugger
+/// needs to inspect a child's memory. Restricted to `pid`'s parent.
+/// Returns -1 if `pid` doesn't exist, the caller isn't its parent, or
+/// `remote_addr..remote_addr+len` isn't fully mapped in its address space.
+pub fn sys_peek(pid: usize, remote_addr: usize, local_buf: *mut u8, len: usize) -> isize {
+    let caller = current_task().unwrap();
+    let Some(target) = find_task(pid) else {
+        return -1;
+    };
+    let remote_token = {
+        let target_inner = target.inner_exclusive_access();
+        let is_parent = target_inner
+            .parent
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .is_some_and(|parent| Arc::ptr_eq(&parent, &caller));
+        if !is_parent {
+            return -1;
+        }
+        target_inner.user_token()
+    };
+    let Some(remote_slices) =
+        try_translated_byte_buffer(remote_token, remote_addr as *const u8, len)
+    else {
+        return -1;
+    };
+    let mut buf = Vec::with_capacity(len);
+    for slice in remote_slices {
+        buf.extend_from_slice(slice);
+    }
+    let mut offset = 0;
+    for slice in translated_byte_buffer(current_user_token(), local_buf as *const u8, len) {
+        slice.copy_from_slice(&buf[offset..offset + slice.len()]);
+        offset += slice.len();
+    }
+    len as isize
+}
+
+/// Set the calling task's debug name, truncated to
+/// `THREAD_NAME_LENGTH_LIMIT` bytes if longer. Always succeeds.
+pub fn sys_set_thread_name(name: *const u8) -> isize {
+    let token = current_user_token();
+    let name = translated_str(token, name);
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .set_thread_name(&name);
+    0
+}
+
+/// Read `tid`'s debug name, as last set by `sys_set_thread_name`, into
+/// `buf` as a NUL-terminated string of at most
+/// `THREAD_NAME_LENGTH_LIMIT + 1` bytes. Returns -1 if no task with that
+/// pid exists.
+pub fn sys_get_thread_name(tid: usize, buf: *mut u8) -> isize {
+    let Some(task) = find_task(tid) else {
+        return -1;
+    };
+    let name = task.inner_exclusive_access().thread_name;
+    write_user(
+        current_user_token(),
+        buf as *mut [u8; THREAD_NAME_LENGTH_LIMIT + 1],
+        name,
+    );
+    0
+}
+
+/// Scheduling policy IDs for `sys_sched_setscheduler`, named after their
+/// Linux `sched_setscheduler(2)` counterparts.
+pub const SCHED_NORMAL: usize = 0;
+pub const SCHED_FIFO: usize = 1;
+
+/// Set the calling task's scheduling policy. `SCHED_FIFO` tasks always run
+/// ahead of every `SCHED_NORMAL` task, ordered by `priority` (higher
+/// first); `priority` must be at least 1 for `SCHED_FIFO` and is ignored
+/// for `SCHED_NORMAL`. The new policy only affects scheduling once this
+/// task is next placed back on the ready queue. Returns -1 on an unknown
+/// policy or an out-of-range priority.
+pub fn sys_sched_setscheduler(policy: usize, priority: usize) -> isize {
+    let (policy, priority) = match policy {
+        SCHED_NORMAL => (SchedPolicy::Normal, 0),
+        SCHED_FIFO if priority >= 1 => (SchedPolicy::Fifo, priority),
+        _ => return -1,
+    };
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.sched_policy = policy;
+    inner.priority = priority;
+    0
+}
+
+/// Every bit set in a CPU affinity mask that `sys_sched_setaffinity` will
+/// accept, one per CPU that actually exists — just CPU 0 on this
+/// single-hart build.
+const VALID_AFFINITY_MASK: usize = (1 << NUM_CPUS) - 1;
+
+/// Set `tid`'s CPU affinity mask. Groundwork for SMP: the scheduler is
+/// single-core for now and doesn't consult this, but the validation
+/// already behaves as it would once more than one CPU exists. Returns -1
+/// if `tid` doesn't exist, or if `mask` is zero or sets any bit for a CPU
+/// that isn't `VALID_AFFINITY_MASK` (on this build, anything but CPU 0).
+pub fn sys_sched_setaffinity(tid: usize, mask: usize) -> isize {
+    if mask == 0 || mask & !VALID_AFFINITY_MASK != 0 {
+        return -1;
+    }
+    let Some(task) = find_task(tid) else {
+        return -1;
+    };
+    task.inner_exclusive_access().cpu_affinity = mask;
+    0
+}
+
+/// Read back `tid`'s CPU affinity mask, as an `isize` (it's always a
+/// small, non-negative bitmask, so there's no need for an output buffer
+/// the way e.g. `sys_get_thread_name` needs one for a string). Returns -1
+/// if `tid` doesn't exist.
+pub fn sys_sched_getaffinity(tid: usize) -> isize {
+    let Some(task) = find_task(tid) else {
+        return -1;
+    };
+    task.inner_exclusive_access().cpu_affinity as isize
+}
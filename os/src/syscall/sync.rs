@@ -0,0 +1,240 @@
+use crate::mm::{PageTable, PhysAddr, VirtAddr};
+use crate::sync::{CancelToken, Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore, FUTEX_TABLE};
+use crate::task::{block_current_and_run_next, current_task, current_user_token};
+use alloc::sync::Arc;
+
+pub fn sys_mutex_create(blocking: bool) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let mutex: Option<Arc<dyn Mutex>> = if blocking {
+        Some(Arc::new(MutexBlocking::new()))
+    } else {
+        Some(Arc::new(MutexSpin::new()))
+    };
+    if let Some(id) = inner
+        .mutex_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        inner.mutex_list[id] = mutex;
+        id as isize
+    } else {
+        inner.mutex_list.push(mutex);
+        inner.mutex_list.len() as isize - 1
+    }
+}
+
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mutex = Arc::clone(inner.mutex_list[mutex_id].as_ref().unwrap());
+    drop(inner);
+    mutex.lock();
+    0
+}
+
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mutex = Arc::clone(inner.mutex_list[mutex_id].as_ref().unwrap());
+    drop(inner);
+    mutex.unlock();
+    0
+}
+
+/// Like `sys_mutex_lock`, but returns immediately instead of blocking.
+/// Returns 0 if the mutex was acquired, -1 if it was already locked.
+pub fn sys_mutex_try_lock(mutex_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mutex = Arc::clone(inner.mutex_list[mutex_id].as_ref().unwrap());
+    drop(inner);
+    if mutex.try_lock() {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_semaphore_create(res_count: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let id = if let Some(id) = inner
+        .semaphore_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        inner.semaphore_list[id] = Some(Arc::new(Semaphore::new(res_count)));
+        id
+    } else {
+        inner
+            .semaphore_list
+            .push(Some(Arc::new(Semaphore::new(res_count))));
+        inner.semaphore_list.len() - 1
+    };
+    id as isize
+}
+
+pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let sem = Arc::clone(inner.semaphore_list[sem_id].as_ref().unwrap());
+    drop(inner);
+    sem.up();
+    0
+}
+
+/// Returns -1 instead of 0 if `destroy` tore this semaphore down while
+/// this call was blocked waiting on it (or had already torn it down
+/// before this call started).
+pub fn sys_semaphore_down(sem_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let sem = Arc::clone(inner.semaphore_list[sem_id].as_ref().unwrap());
+    drop(inner);
+    if sem.down() {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Tear down semaphore `sem_id`: any task blocked in `sys_semaphore_down`
+/// on it wakes immediately with -1 rather than hanging forever, and the
+/// slot in `semaphore_list` is freed for reuse by a later
+/// `sys_semaphore_create`. Always succeeds.
+pub fn sys_semaphore_destroy(sem_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let sem = Arc::clone(inner.semaphore_list[sem_id].as_ref().unwrap());
+    inner.semaphore_list[sem_id] = None;
+    drop(inner);
+    sem.destroy();
+    0
+}
+
+pub fn sys_condvar_create() -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let id = if let Some(id) = inner
+        .condvar_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        inner.condvar_list[id] = Some(Arc::new(Condvar::new()));
+        id
+    } else {
+        inner.condvar_list.push(Some(Arc::new(Condvar::new())));
+        inner.condvar_list.len() - 1
+    };
+    id as isize
+}
+
+pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let condvar = Arc::clone(inner.condvar_list[condvar_id].as_ref().unwrap());
+    drop(inner);
+    condvar.signal();
+    0
+}
+
+pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let condvar = Arc::clone(inner.condvar_list[condvar_id].as_ref().unwrap());
+    let mutex = Arc::clone(inner.mutex_list[mutex_id].as_ref().unwrap());
+    drop(inner);
+    condvar.wait(mutex);
+    0
+}
+
+pub fn sys_cancel_token_create() -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let id = if let Some(id) = inner
+        .cancel_token_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        inner.cancel_token_list[id] = Some(Arc::new(CancelToken::new()));
+        id
+    } else {
+        inner
+            .cancel_token_list
+            .push(Some(Arc::new(CancelToken::new())));
+        inner.cancel_token_list.len() - 1
+    };
+    id as isize
+}
+
+pub fn sys_cancel_token_cancel(condvar_id: usize, token_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let condvar = Arc::clone(inner.condvar_list[condvar_id].as_ref().unwrap());
+    let token = Arc::clone(inner.cancel_token_list[token_id].as_ref().unwrap());
+    drop(inner);
+    condvar.cancel(&token);
+    0
+}
+
+/// Like `sys_condvar_wait`, but also wakes up if another task cancels
+/// `token_id` first. Returns 1 if the wait was cancelled, 0 if it was
+/// signaled normally.
+pub fn sys_condvar_wait_cancellable(condvar_id: usize, mutex_id: usize, token_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let condvar = Arc::clone(inner.condvar_list[condvar_id].as_ref().unwrap());
+    let mutex = Arc::clone(inner.mutex_list[mutex_id].as_ref().unwrap());
+    let token = Arc::clone(inner.cancel_token_list[token_id].as_ref().unwrap());
+    drop(inner);
+    condvar.wait_cancellable(mutex, token) as isize
+}
+
+/// Translate `addr` (in the calling task's address space) to the physical
+/// address `FUTEX_TABLE` keys its wait queues by, plus the `u32` currently
+/// stored there. `None` if `addr` isn't mapped.
+fn futex_word(token: usize, addr: usize) -> Option<(usize, u32)> {
+    let pa: PhysAddr = PageTable::from_token(token).translate_va(VirtAddr::from(addr))?;
+    Some((usize::from(pa), *pa.get_mut::<u32>()))
+}
+
+/// Check that the `u32` at `addr` still equals `expected` and, if so,
+/// block until a matching `sys_futex_wake` runs. Since this kernel never
+/// preempts a task mid-syscall, the check and the enqueue onto
+/// `FUTEX_TABLE` happen without anyone else getting a chance to wake the
+/// queue first. Returns -1 if `addr` isn't mapped or the word no longer
+/// matches `expected` — the caller is expected to retry its fast path
+/// rather than treat that as an error — or 0 once woken.
+pub fn sys_futex_wait(addr: usize, expected: u32) -> isize {
+    let token = current_user_token();
+    let Some((key, actual)) = futex_word(token, addr) else {
+        return -1;
+    };
+    if actual != expected {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    FUTEX_TABLE.exclusive_access().wait(key, task);
+    block_current_and_run_next();
+    0
+}
+
+/// Wake up to `n` tasks blocked in `sys_futex_wait` on the same word as
+/// `addr`. Returns how many were actually woken, or -1 if `addr` isn't
+/// mapped.
+pub fn sys_futex_wake(addr: usize, n: usize) -> isize {
+    let token = current_user_token();
+    let Some((key, _)) = futex_word(token, addr) else {
+        return -1;
+    };
+    FUTEX_TABLE.exclusive_access().wake(key, n) as isize
+}
@@ -0,0 +1,30 @@
+use crate::trap::trap_return;
+
+/// Registers that must survive a `__switch` between two tasks' kernel
+/// stacks (callee-saved registers plus the return address and kernel
+/// stack pointer).
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TaskContext {
+    ra: usize,
+    sp: usize,
+    s: [usize; 12],
+}
+
+impl TaskContext {
+    pub fn zero_init() -> Self {
+        Self {
+            ra: 0,
+            sp: 0,
+            s: [0; 12],
+        }
+    }
+
+    pub fn goto_trap_return(kstack_ptr: usize) -> Self {
+        Self {
+            ra: trap_return as usize,
+            sp: kstack_ptr,
+            s: [0; 12],
+        }
+    }
+}
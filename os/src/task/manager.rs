@@ -0,0 +1,178 @@
+use super::{SchedPolicy, TaskControlBlock};
+use crate::sync::UPSafeCell;
+use crate::timer::get_time_us;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// A queued task together with the timestamp (microseconds, as reported
+/// by `get_time_us`) at which it was added to its queue, used to report
+/// how long it's been waiting via `TaskManager::stats`.
+struct Queued {
+    task: Arc<TaskControlBlock>,
+    ready_since: usize,
+}
+
+/// The ready queue. Scheduling is plain round-robin FIFO, except for tasks
+/// opted into `SchedPolicy::Fifo` via `sys_sched_setscheduler`: those sit
+/// in `fifo_queue`, kept sorted by descending priority, and are always
+/// fetched ahead of every `Normal` task in `ready_queue`.
+pub struct TaskManager {
+    ready_queue: VecDeque<Queued>,
+    fifo_queue: VecDeque<Queued>,
+    /// A task set by a directed handoff (`wakeup_task_directed`/
+    /// `sys_yield_to`) to be served by the very next `fetch`, ahead of even
+    /// `fifo_queue`. Setting a new preference while one is already pending
+    /// demotes the old one back into its normal queue via `add`, so at most
+    /// one task is ever held here.
+    preferred: Option<Queued>,
+}
+
+/// Ready-queue diagnostics reported by `sys_sched_stats`: how many tasks
+/// are currently waiting to run, and the longest any one of them has been
+/// waiting, in microseconds. A `max_wait_us` that keeps climbing across
+/// calls points at a task stuck behind higher-priority or preferred work.
+#[derive(Default, Clone, Copy)]
+pub struct SchedStats {
+    pub ready_len: usize,
+    pub max_wait_us: usize,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+            fifo_queue: VecDeque::new(),
+            preferred: None,
+        }
+    }
+
+    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
+        let inner = task.inner_exclusive_access();
+        let (policy, priority) = (inner.sched_policy, inner.priority);
+        drop(inner);
+        let queued = Queued {
+            task,
+            ready_since: get_time_us(),
+        };
+        if policy == SchedPolicy::Fifo {
+            let pos = self
+                .fifo_queue
+                .iter()
+                .position(|other| other.task.inner_exclusive_access().priority < priority)
+                .unwrap_or(self.fifo_queue.len());
+            self.fifo_queue.insert(pos, queued);
+        } else {
+            self.ready_queue.push_back(queued);
+        }
+    }
+
+    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.preferred
+            .take()
+            .or_else(|| self.fifo_queue.pop_front())
+            .or_else(|| self.ready_queue.pop_front())
+            .map(|queued| queued.task)
+    }
+
+    /// Current ready-queue length and longest wait among everything
+    /// currently queued (`preferred` included).
+    pub fn stats(&self) -> SchedStats {
+        let now = get_time_us();
+        let all = self
+            .preferred
+            .iter()
+            .chain(self.fifo_queue.iter())
+            .chain(self.ready_queue.iter());
+        let mut stats = SchedStats::default();
+        for queued in all {
+            stats.ready_len += 1;
+            stats.max_wait_us = stats.max_wait_us.max(now - queued.ready_since);
+        }
+        stats
+    }
+
+    /// Set `task` to be served by the very next `fetch`, bypassing both
+    /// `fifo_queue` and `ready_queue` once.
+    pub fn add_preferred(&mut self, task: Arc<TaskControlBlock>) {
+        let queued = Queued {
+            task,
+            ready_since: get_time_us(),
+        };
+        if let Some(bumped) = self.preferred.replace(queued) {
+            self.add(bumped.task);
+        }
+    }
+
+    /// Remove and return the queued task with the given pid, from whichever
+    /// queue it's waiting in. Used by `sys_yield_to` to pull a specific
+    /// task out of normal scheduling order and hand it to `add_preferred`.
+    /// Returns `None` if no such task is currently queued (e.g. it's
+    /// running, blocked off-queue, or doesn't exist).
+    pub fn remove(&mut self, pid: usize) -> Option<Arc<TaskControlBlock>> {
+        if let Some(pos) = self
+            .fifo_queue
+            .iter()
+            .position(|queued| queued.task.getpid() == pid)
+        {
+            return self.fifo_queue.remove(pos).map(|queued| queued.task);
+        }
+        if let Some(pos) = self
+            .ready_queue
+            .iter()
+            .position(|queued| queued.task.getpid() == pid)
+        {
+            return self.ready_queue.remove(pos).map(|queued| queued.task);
+        }
+        None
+    }
+
+    /// Remove and return every queued task, `preferred` first, then
+    /// `fifo_queue`, then `ready_queue` (the same order `fetch` would have
+    /// served them in), leaving all three empty. Used by `sys_reboot` to mark every still-runnable task
+    /// exited and flush its resources before shutting down.
+    pub fn drain(&mut self) -> Vec<Arc<TaskControlBlock>> {
+        let mut drained: Vec<_> = self
+            .preferred
+            .take()
+            .into_iter()
+            .map(|queued| queued.task)
+            .collect();
+        drained.extend(self.fifo_queue.drain(..).map(|queued| queued.task));
+        drained.extend(self.ready_queue.drain(..).map(|queued| queued.task));
+        drained
+    }
+}
+
+lazy_static! {
+    pub static ref TASK_MANAGER: UPSafeCell<TaskManager> =
+        unsafe { UPSafeCell::new(TaskManager::new()) };
+}
+
+pub fn add_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add(task);
+}
+
+pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch()
+}
+
+/// See `TaskManager::add_preferred`.
+pub fn set_preferred_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add_preferred(task);
+}
+
+/// See `TaskManager::remove`.
+pub fn remove_queued_task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().remove(pid)
+}
+
+pub fn drain_tasks() -> Vec<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().drain()
+}
+
+/// See `TaskManager::stats`.
+pub fn sched_stats() -> SchedStats {
+    TASK_MANAGER.exclusive_access().stats()
+}
@@ -0,0 +1,108 @@
+mod context;
+mod manager;
+mod pid;
+mod processor;
+mod registry;
+mod switch;
+#[allow(clippy::module_inception)]
+mod task;
+
+use crate::fs::open_file;
+use crate::fs::OpenFlags;
+use alloc::sync::Arc;
+use lazy_static::*;
+pub use task::{
+    CloneFlags, ProcFlags, SchedPolicy, TaskControlBlock, TaskStatus, SIGUSR,
+    STRICT_RLIMIT_EXIT_CODE, THREAD_NAME_LENGTH_LIMIT,
+};
+
+pub use context::TaskContext;
+pub use manager::{
+    add_task, drain_tasks, remove_queued_task, sched_stats, set_preferred_task, SchedStats,
+};
+pub use pid::{kernel_stack_position, pid_alloc, KernelStack, PidHandle};
+pub use processor::{
+    current_task, current_trap_cx, current_user_token, run_tasks, schedule, take_current_task,
+};
+pub use registry::{find_task, register_task};
+
+pub fn suspend_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    task_inner.task_status = TaskStatus::Ready;
+    task_inner.switch_count += 1;
+    drop(task_inner);
+    add_task(task);
+    schedule(task_cx_ptr);
+}
+
+/// Park the current task off the ready queue entirely; it must be woken
+/// explicitly, via [`wakeup_task_directed`] or a plain `add_task`.
+pub fn block_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    task_inner.task_status = TaskStatus::Ready;
+    task_inner.switch_count += 1;
+    drop(task_inner);
+    schedule(task_cx_ptr);
+}
+
+/// Wake a blocked task, handing it straight to the very next `schedule`
+/// instead of making it wait behind the rest of the ready queue. Meant for
+/// producer-consumer handoffs, e.g. `MutexBlocking::unlock` waking the
+/// waiter it just unblocked: the waker knows exactly who should run next,
+/// so directing the handoff cuts the latency a plain `add_task` would add.
+pub fn wakeup_task_directed(task: Arc<TaskControlBlock>) {
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.task_status = TaskStatus::Ready;
+    drop(task_inner);
+    set_preferred_task(task);
+}
+
+lazy_static! {
+    pub static ref INITPROC: Arc<TaskControlBlock> = Arc::new({
+        let inode = open_file("initproc", OpenFlags::RDONLY).unwrap();
+        let v = inode.read_all();
+        TaskControlBlock::new(v.as_slice())
+    });
+}
+
+pub fn add_initproc() {
+    register_task(&INITPROC);
+    add_task(INITPROC.clone());
+}
+
+pub fn exit_current_and_run_next(exit_code: i32) {
+    let task = take_current_task().unwrap();
+    let pid = task.getpid();
+    if pid == 0 {
+        crate::sbi::shutdown();
+    }
+    let mut inner = task.inner_exclusive_access();
+    inner.task_status = TaskStatus::Zombie;
+    inner.exit_code = exit_code;
+    {
+        let mut initproc_inner = INITPROC.inner_exclusive_access();
+        for child in inner.children.iter() {
+            child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
+            initproc_inner.children.push(child.clone());
+        }
+    }
+    inner.children.clear();
+    // Only eagerly close fds if nothing else still shares this table (via
+    // `sys_clone(CloneFlags::FILES, ..)`) — clearing a table another live
+    // task is still using would yank its open files out from under it.
+    // If it is shared, this task's `Arc` handle (and so its share of the
+    // refcount) goes away once `inner`'s own `TaskControlBlockInner` is
+    // eventually dropped.
+    if Arc::strong_count(&inner.fd_table) == 1 {
+        inner.fd_table.exclusive_access().clear();
+    }
+    crate::fs::release_flocks_for_pid(pid);
+    drop(inner);
+    drop(task);
+    let mut _unused = TaskContext::zero_init();
+    schedule(&mut _unused as *mut _);
+}
@@ -0,0 +1,114 @@
+use crate::config::{KERNEL_STACK_SIZE, MAX_PROCESS_COUNT, PAGE_SIZE, TRAMPOLINE};
+use crate::mm::{MapPermission, KERNEL_SPACE};
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+struct PidAllocator {
+    current: usize,
+    recycled: Vec<usize>,
+}
+
+impl PidAllocator {
+    pub fn new() -> Self {
+        Self {
+            current: 0,
+            recycled: Vec::new(),
+        }
+    }
+
+    /// Allocate a pid, or `None` if `MAX_PROCESS_COUNT` processes are
+    /// already alive. Reusing a recycled pid never counts against the
+    /// limit, since it doesn't grow the number of outstanding processes.
+    pub fn alloc(&mut self) -> Option<PidHandle> {
+        if let Some(pid) = self.recycled.pop() {
+            return Some(PidHandle(pid));
+        }
+        if self.current - self.recycled.len() >= MAX_PROCESS_COUNT {
+            return None;
+        }
+        self.current += 1;
+        Some(PidHandle(self.current - 1))
+    }
+
+    pub fn dealloc(&mut self, pid: usize) {
+        assert!(pid < self.current);
+        assert!(
+            !self.recycled.iter().any(|ppid| *ppid == pid),
+            "pid {} has been deallocated!",
+            pid
+        );
+        self.recycled.push(pid);
+    }
+}
+
+lazy_static! {
+    static ref PID_ALLOCATOR: UPSafeCell<PidAllocator> =
+        unsafe { UPSafeCell::new(PidAllocator::new()) };
+}
+
+pub struct PidHandle(pub usize);
+
+impl Drop for PidHandle {
+    fn drop(&mut self) {
+        PID_ALLOCATOR.exclusive_access().dealloc(self.0);
+    }
+}
+
+/// Allocate a pid, or `None` if `MAX_PROCESS_COUNT` processes are already
+/// alive.
+pub fn pid_alloc() -> Option<PidHandle> {
+    PID_ALLOCATOR.exclusive_access().alloc()
+}
+
+/// Return the kernel stack's (bottom, top) given a task's allocated
+/// position in the kernel address space, leaving a guard page below it.
+pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
+    let top = TRAMPOLINE - app_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let bottom = top - KERNEL_STACK_SIZE;
+    (bottom, top)
+}
+
+pub struct KernelStack {
+    pid: usize,
+}
+
+impl KernelStack {
+    pub fn new(pid_handle: &PidHandle) -> Self {
+        let pid = pid_handle.0;
+        let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(pid);
+        KERNEL_SPACE.exclusive_access().insert_framed_area(
+            kernel_stack_bottom.into(),
+            kernel_stack_top.into(),
+            MapPermission::R | MapPermission::W,
+        );
+        KernelStack { pid }
+    }
+
+    pub fn push_on_top<T>(&self, value: T) -> *mut T
+    where
+        T: Sized,
+    {
+        let kernel_stack_top = self.get_top();
+        let ptr_mut = (kernel_stack_top - core::mem::size_of::<T>()) as *mut T;
+        unsafe {
+            *ptr_mut = value;
+        }
+        ptr_mut
+    }
+
+    pub fn get_top(&self) -> usize {
+        let (_, kernel_stack_top) = kernel_stack_position(self.pid);
+        kernel_stack_top
+    }
+}
+
+impl Drop for KernelStack {
+    fn drop(&mut self) {
+        let (kernel_stack_bottom, _) = kernel_stack_position(self.pid);
+        let kernel_stack_bottom_va = kernel_stack_bottom.into();
+        KERNEL_SPACE
+            .exclusive_access()
+            .remove_area_with_start_vpn(crate::mm::VirtAddr::from(kernel_stack_bottom_va).into());
+    }
+}
@@ -0,0 +1,24 @@
+use super::TaskControlBlock;
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use lazy_static::*;
+
+lazy_static! {
+    /// Live tasks by pid, so `sys_kill` can find a target without every
+    /// caller threading a handle through. Entries go stale on their own
+    /// once a task's last `Arc` drops (e.g. after its parent reaps it via
+    /// `waitpid`); `find_task` treats a dead `Weak` the same as a miss.
+    static ref TASKS: UPSafeCell<BTreeMap<usize, Weak<TaskControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+pub fn register_task(task: &Arc<TaskControlBlock>) {
+    TASKS
+        .exclusive_access()
+        .insert(task.getpid(), Arc::downgrade(task));
+}
+
+pub fn find_task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    TASKS.exclusive_access().get(&pid)?.upgrade()
+}
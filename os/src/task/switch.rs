@@ -0,0 +1,9 @@
+use super::TaskContext;
+
+core::arch::global_asm!(include_str!("switch.S"));
+
+extern "C" {
+    /// Save the current task's registers into `current_task_cx_ptr` and
+    /// restore them from `next_task_cx_ptr`, switching kernel stacks.
+    pub fn __switch(current_task_cx_ptr: *mut TaskContext, next_task_cx_ptr: *const TaskContext);
+}
@@ -0,0 +1,400 @@
+use super::TaskContext;
+use super::{kernel_stack_position, pid_alloc, KernelStack, PidHandle};
+use crate::config::{MAX_FD_COUNT, TRAP_CONTEXT_BASE};
+use crate::fs::{File, Stdin, Stdout};
+use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE};
+use crate::sync::{CancelToken, Condvar, Mutex, Semaphore, UPSafeCell};
+use crate::timer::get_time_us;
+use crate::trap::{trap_handler, TrapContext};
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::cell::RefMut;
+
+bitflags! {
+    /// Which resources `sys_clone` gives a child task a shared handle to,
+    /// instead of its own copy of. Unset bits fall back to `fork`'s usual
+    /// copy-everything behavior.
+    pub struct CloneFlags: usize {
+        /// Share this task's fd table instead of copying it: an open,
+        /// close, or dup by either task is visible through the other's
+        /// fds too.
+        const FILES = 1 << 0;
+        /// Share the parent's address space instead of copying it.
+        /// Rejected by `sys_clone`: every task here owns its `MemorySet`,
+        /// kernel stack, and trap context outright, with nothing like a
+        /// thread group to hang a shared one off of, so there's no honest
+        /// way to support this without first building that abstraction.
+        const VM = 1 << 1;
+    }
+}
+
+bitflags! {
+    /// Per-process behavior knobs toggled at runtime by `sys_prctl`. All
+    /// bits start clear; `sys_fork`/`sys_clone` never inherit them into
+    /// the child, matching how `sched_policy`/`priority` are also reset
+    /// rather than copied in `clone_with`.
+    pub struct ProcFlags: u32 {
+        /// A hit against a per-process resource limit (currently: the fd
+        /// table filling up) kills the task with a fatal exit code
+        /// instead of the syscall that hit it returning -1.
+        const STRICT_RLIMIT = 1 << 0;
+        /// `sys_mmap` maps and fills every page of a new mapping
+        /// immediately instead of deferring each page to
+        /// `handle_lazy_page_fault` on first access.
+        const MMAP_EAGER = 1 << 1;
+    }
+}
+
+/// Exit code `exit_current_and_run_next` is given when `ProcFlags::STRICT_RLIMIT`
+/// turns a resource-limit hit into a fatal kill. Distinct from the trap
+/// handler's `-2`/`-3` so the two causes can be told apart from the exit
+/// status alone.
+pub const STRICT_RLIMIT_EXIT_CODE: i32 = -4;
+
+pub struct TaskControlBlock {
+    pub pid: PidHandle,
+    pub kernel_stack: KernelStack,
+    inner: UPSafeCell<TaskControlBlockInner>,
+}
+
+pub struct TaskControlBlockInner {
+    pub trap_cx_ppn: PhysPageNum,
+    pub base_size: usize,
+    pub task_cx: TaskContext,
+    pub task_status: TaskStatus,
+    pub memory_set: MemorySet,
+    pub parent: Option<Weak<TaskControlBlock>>,
+    pub children: Vec<Arc<TaskControlBlock>>,
+    pub exit_code: i32,
+    pub heap_bottom: usize,
+    pub program_brk: usize,
+    /// Largest `program_brk - heap_bottom` `sys_sbrk` will grow the heap
+    /// to, in bytes. `usize::MAX` (the default) means unbounded. Settable
+    /// via `sys_setrlimit(RLIMIT_DATA, ..)`, mirrored back by
+    /// `sys_getrlimit`; enforced directly in `sys_sbrk`. Analogous to
+    /// `MemorySet`'s fixed `USER_STACK_MAX_SIZE` stack floor, but
+    /// per-process and runtime-adjustable rather than a global constant.
+    pub heap_limit: usize,
+    /// Open file descriptors. Wrapped in its own `UPSafeCell` (rather than
+    /// being a plain field, like every other piece of per-task state here)
+    /// so `sys_clone(CloneFlags::FILES, ..)` can hand a child task an
+    /// `Arc` clone of the very same table instead of a copy of its
+    /// contents — see `TaskControlBlock::clone_with`.
+    pub fd_table: Arc<UPSafeCell<Vec<Option<Arc<dyn File + Send + Sync>>>>>,
+    /// Close-on-exec bit for each `fd_table` slot, same length and shared
+    /// (or copied) alongside it by `clone_with` so the two never fall out
+    /// of sync. Swept by `exec`, which closes every fd still flagged here
+    /// before jumping to the new program.
+    pub close_on_exec: Arc<UPSafeCell<Vec<bool>>>,
+    /// Accumulated time spent running in user mode, in microseconds.
+    pub user_time: usize,
+    /// Accumulated time spent running in kernel mode, in microseconds.
+    pub kernel_time: usize,
+    /// Timestamp of the last user/kernel mode switch, used to attribute
+    /// elapsed time to `user_time`/`kernel_time` at the next switch.
+    pub last_switch_time: usize,
+    /// Number of times this task has voluntarily given up the CPU —
+    /// incremented by `suspend_current_and_run_next` and
+    /// `block_current_and_run_next`, not by `exit_current_and_run_next`
+    /// (exiting isn't a switch back to this task later). Survives into
+    /// the zombie TCB for `sys_waitpid`'s rusage out-param to read at
+    /// reap, same as `user_time`/`kernel_time`.
+    pub switch_count: usize,
+    pub mutex_list: Vec<Option<Arc<dyn Mutex>>>,
+    pub semaphore_list: Vec<Option<Arc<Semaphore>>>,
+    pub condvar_list: Vec<Option<Arc<Condvar>>>,
+    pub cancel_token_list: Vec<Option<Arc<CancelToken>>>,
+    pub sched_policy: SchedPolicy,
+    pub priority: usize,
+    /// Set by `sys_kill`; consumed (and cleared) by the next blocking wait
+    /// loop that checks it, e.g. `sys_poll`.
+    pub pending_signal: bool,
+    /// User entry point registered via `sys_sigaction(SIGUSR, ...)`, or
+    /// `None` if no handler is installed.
+    pub sigusr_handler: Option<usize>,
+    /// Set by `sys_kill(pid, SIGUSR)`; consumed by `trap_handler`, which
+    /// diverts execution to `sigusr_handler` the next time this task
+    /// returns to user space.
+    pub sigusr_pending: bool,
+    /// Set while a `SIGUSR` handler is running (the interrupted
+    /// `TrapContext` is pushed onto the user stack, not kept here; see
+    /// `trap::handle_signal`). Guards against a second `SIGUSR` nesting
+    /// into a handler that's already running.
+    pub in_sigusr_handler: bool,
+    /// Debug name set via `sys_set_thread_name`, NUL-padded. Empty (all
+    /// zero) until set. Read back via `sys_get_thread_name`.
+    pub thread_name: [u8; THREAD_NAME_LENGTH_LIMIT + 1],
+    /// Behavior flags set via `sys_prctl`. See `ProcFlags`.
+    pub proc_flags: ProcFlags,
+    /// CPU affinity bitmask set via `sys_sched_setaffinity`, one bit per
+    /// CPU. Defaults to every currently valid CPU (just bit 0, since this
+    /// is a single-hart build) so a task that never calls it is still
+    /// schedulable everywhere it's allowed to be. The single-core
+    /// scheduler doesn't otherwise look at this yet; it's groundwork for
+    /// when more than one CPU actually exists to pick among.
+    pub cpu_affinity: usize,
+}
+
+/// Max length, excluding the trailing NUL, of a debug name set via
+/// `sys_set_thread_name`. Longer names are silently truncated.
+pub const THREAD_NAME_LENGTH_LIMIT: usize = 15;
+
+/// The only catchable signal `sys_sigaction`/`sys_kill` know about. This
+/// kernel has no signal set or default dispositions beyond this.
+pub const SIGUSR: u32 = 10;
+
+impl TaskControlBlockInner {
+    pub fn trap_cx(&self) -> &'static mut TrapContext {
+        self.trap_cx_ppn.get_mut()
+    }
+
+    pub fn user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+
+    fn get_status(&self) -> TaskStatus {
+        self.task_status
+    }
+
+    pub fn is_zombie(&self) -> bool {
+        self.get_status() == TaskStatus::Zombie
+    }
+
+    /// Returns `None` once the table already holds `MAX_FD_COUNT` open
+    /// fds; callers are responsible for honoring `ProcFlags::STRICT_RLIMIT`
+    /// in that case (see `fd_limit_hit` in `syscall::fs`).
+    pub fn alloc_fd(&mut self) -> Option<usize> {
+        let mut fd_table = self.fd_table.exclusive_access();
+        if let Some(fd) = (0..fd_table.len()).find(|fd| fd_table[*fd].is_none()) {
+            Some(fd)
+        } else if fd_table.len() < MAX_FD_COUNT {
+            fd_table.push(None);
+            self.close_on_exec.exclusive_access().push(false);
+            Some(fd_table.len() - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Set this task's debug name, truncating to `THREAD_NAME_LENGTH_LIMIT`
+    /// bytes.
+    pub fn set_thread_name(&mut self, name: &str) {
+        let mut bytes = [0u8; THREAD_NAME_LENGTH_LIMIT + 1];
+        let len = name.len().min(THREAD_NAME_LENGTH_LIMIT);
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        self.thread_name = bytes;
+    }
+
+    /// This task's debug name as set by `set_thread_name`, or `""` if never
+    /// set.
+    pub fn thread_name(&self) -> &str {
+        let len = self
+            .thread_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.thread_name.len());
+        core::str::from_utf8(&self.thread_name[..len]).unwrap_or("")
+    }
+}
+
+impl TaskControlBlock {
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+
+    pub fn new(elf_data: &[u8]) -> Self {
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT_BASE).into())
+            .unwrap()
+            .ppn();
+        let pid_handle = pid_alloc().expect("pid exhausted while creating the very first task");
+        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack_top = kernel_stack.get_top();
+        let task_control_block = Self {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: user_sp,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set,
+                    parent: None,
+                    children: Vec::new(),
+                    exit_code: 0,
+                    heap_bottom: user_sp,
+                    program_brk: user_sp,
+                    heap_limit: usize::MAX,
+                    fd_table: Arc::new(unsafe {
+                        UPSafeCell::new(vec![
+                            Some(Arc::new(Stdin) as Arc<dyn File + Send + Sync>),
+                            Some(Arc::new(Stdout)),
+                            Some(Arc::new(Stdout)),
+                        ])
+                    }),
+                    close_on_exec: Arc::new(unsafe { UPSafeCell::new(vec![false; 3]) }),
+                    user_time: 0,
+                    kernel_time: 0,
+                    last_switch_time: get_time_us(),
+                    switch_count: 0,
+                    mutex_list: Vec::new(),
+                    semaphore_list: Vec::new(),
+                    condvar_list: Vec::new(),
+                    cancel_token_list: Vec::new(),
+                    sched_policy: SchedPolicy::Normal,
+                    priority: 0,
+                    pending_signal: false,
+                    sigusr_handler: None,
+                    sigusr_pending: false,
+                    in_sigusr_handler: false,
+                    thread_name: [0u8; THREAD_NAME_LENGTH_LIMIT + 1],
+                    proc_flags: ProcFlags::empty(),
+                    cpu_affinity: 1,
+                })
+            },
+        };
+        let trap_cx = task_control_block.inner_exclusive_access().trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            user_sp,
+            KERNEL_SPACE.exclusive_access().token(),
+            kernel_stack_top,
+            trap_handler as usize,
+        );
+        task_control_block
+    }
+
+    /// Load a new ELF image into this task's own address space, keeping
+    /// the pid and open file table (used to implement `exec`).
+    pub fn exec(&self, elf_data: &[u8]) {
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT_BASE).into())
+            .unwrap()
+            .ppn();
+        let mut inner = self.inner_exclusive_access();
+        inner.memory_set = memory_set;
+        inner.trap_cx_ppn = trap_cx_ppn;
+        let mut fd_table = inner.fd_table.exclusive_access();
+        let mut close_on_exec = inner.close_on_exec.exclusive_access();
+        for (fd, flagged) in close_on_exec.iter_mut().enumerate() {
+            if *flagged {
+                fd_table[fd] = None;
+                *flagged = false;
+            }
+        }
+        drop(fd_table);
+        drop(close_on_exec);
+        let trap_cx = inner.trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            user_sp,
+            KERNEL_SPACE.exclusive_access().token(),
+            self.kernel_stack.get_top(),
+            trap_handler as usize,
+        );
+    }
+
+    /// Fork a child task, duplicating the address space and open files.
+    /// Shorthand for `clone_with(CloneFlags::empty())`.
+    pub fn fork(self: &Arc<Self>) -> Option<Arc<Self>> {
+        self.clone_with(CloneFlags::empty())
+    }
+
+    /// Like `fork`, but `flags` picks which resources the child shares
+    /// with the parent instead of getting its own copy of. Only
+    /// `CloneFlags::FILES` is supported; `sys_clone` is responsible for
+    /// rejecting `CloneFlags::VM` before this is ever called. Returns
+    /// `None` if `MAX_PROCESS_COUNT` processes are already alive, checked
+    /// before the address space is copied so a fork bomb at the limit
+    /// fails cheaply instead of paging in a whole new `MemorySet` first.
+    pub fn clone_with(self: &Arc<Self>, flags: CloneFlags) -> Option<Arc<Self>> {
+        let mut parent_inner = self.inner_exclusive_access();
+        let pid_handle = pid_alloc()?;
+        let memory_set = MemorySet::from_existing_user(&parent_inner.memory_set);
+        let trap_cx_ppn = memory_set
+            .translate(VirtAddr::from(TRAP_CONTEXT_BASE).into())
+            .unwrap()
+            .ppn();
+        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack_top = kernel_stack.get_top();
+        let fd_table = if flags.contains(CloneFlags::FILES) {
+            parent_inner.fd_table.clone()
+        } else {
+            let copied = parent_inner.fd_table.exclusive_access().clone();
+            Arc::new(unsafe { UPSafeCell::new(copied) })
+        };
+        let close_on_exec = if flags.contains(CloneFlags::FILES) {
+            parent_inner.close_on_exec.clone()
+        } else {
+            let copied = parent_inner.close_on_exec.exclusive_access().clone();
+            Arc::new(unsafe { UPSafeCell::new(copied) })
+        };
+        let task_control_block = Arc::new(Self {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: parent_inner.base_size,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    heap_bottom: parent_inner.heap_bottom,
+                    program_brk: parent_inner.program_brk,
+                    heap_limit: parent_inner.heap_limit,
+                    fd_table,
+                    close_on_exec,
+                    user_time: 0,
+                    kernel_time: 0,
+                    last_switch_time: get_time_us(),
+                    switch_count: 0,
+                    mutex_list: Vec::new(),
+                    semaphore_list: Vec::new(),
+                    condvar_list: Vec::new(),
+                    cancel_token_list: Vec::new(),
+                    sched_policy: SchedPolicy::Normal,
+                    priority: 0,
+                    pending_signal: false,
+                    sigusr_handler: None,
+                    sigusr_pending: false,
+                    in_sigusr_handler: false,
+                    thread_name: [0u8; THREAD_NAME_LENGTH_LIMIT + 1],
+                    proc_flags: ProcFlags::empty(),
+                    cpu_affinity: 1,
+                })
+            },
+        });
+        parent_inner.children.push(task_control_block.clone());
+        let trap_cx = task_control_block.inner_exclusive_access().trap_cx();
+        trap_cx.kernel_sp = kernel_stack_top;
+        Some(task_control_block)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TaskStatus {
+    Ready,
+    Running,
+    Zombie,
+}
+
+/// A task's scheduling class. `Fifo` tasks always run before any `Normal`
+/// task, broken by `priority` (higher first) and then FIFO order among
+/// equal priorities; `Normal` tasks share the CPU round-robin as before.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SchedPolicy {
+    Normal,
+    Fifo,
+}
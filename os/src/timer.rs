@@ -0,0 +1,41 @@
+use crate::config::CLOCK_FREQ;
+use crate::sbi::set_timer;
+use riscv::register::time;
+
+const TICKS_PER_SEC: usize = 100;
+const MSEC_PER_SEC: usize = 1000;
+const USEC_PER_SEC: usize = 1_000_000;
+const NSEC_PER_SEC: u128 = 1_000_000_000;
+
+pub fn get_time() -> usize {
+    time::read()
+}
+
+/// Like `get_time`, but converted to milliseconds via a 128-bit
+/// intermediate. Dividing `CLOCK_FREQ` down to a per-millisecond tick
+/// count first (as a plain `time::read() / (CLOCK_FREQ / MSEC_PER_SEC)`
+/// would) truncates whenever `CLOCK_FREQ` isn't an exact multiple of
+/// `MSEC_PER_SEC`, and multiplying `time::read()` up first would overflow
+/// a 64-bit multiply well before the timer itself wraps around; widening
+/// to `u128` for the multiply avoids both.
+pub fn get_time_ms() -> usize {
+    (time::read() as u128 * MSEC_PER_SEC as u128 / CLOCK_FREQ as u128) as usize
+}
+
+/// Like `get_time_ms`, but in microseconds; see its comment for why this
+/// goes through a 128-bit intermediate rather than `time::read() /
+/// (CLOCK_FREQ / USEC_PER_SEC)`.
+pub fn get_time_us() -> usize {
+    (time::read() as u128 * USEC_PER_SEC as u128 / CLOCK_FREQ as u128) as usize
+}
+
+/// Like `get_time_us`, but in nanoseconds and with a 128-bit intermediate,
+/// since `time::read() * NSEC_PER_SEC` would overflow a 64-bit multiply
+/// well before the timer itself wraps around.
+pub fn get_time_ns() -> u64 {
+    (time::read() as u128 * NSEC_PER_SEC / CLOCK_FREQ as u128) as u64
+}
+
+pub fn set_next_trigger() {
+    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
+}
@@ -0,0 +1,201 @@
+mod context;
+
+use crate::config::{
+    BLOCK_CACHE_WRITEBACK_INTERVAL_TICKS, BLOCK_CACHE_WRITEBACK_MAX_AGE_TICKS, TRAMPOLINE,
+    TRAP_CONTEXT_BASE,
+};
+use crate::mm::write_user;
+use crate::syscall::syscall;
+use crate::task::{
+    current_task, current_trap_cx, current_user_token, exit_current_and_run_next,
+    suspend_current_and_run_next, SIGUSR,
+};
+use crate::timer::{get_time_us, set_next_trigger};
+use core::arch::{asm, global_asm};
+use riscv::register::{
+    mtvec::TrapMode,
+    scause::{self, Exception, Interrupt, Trap},
+    sie, stval, stvec,
+};
+
+global_asm!(include_str!("trap.S"));
+
+pub fn init() {
+    set_kernel_trap_entry();
+}
+
+fn set_kernel_trap_entry() {
+    unsafe {
+        stvec::write(trap_from_kernel as usize, TrapMode::Direct);
+    }
+}
+
+fn set_user_trap_entry() {
+    unsafe {
+        stvec::write(TRAMPOLINE as usize, TrapMode::Direct);
+    }
+}
+
+pub fn enable_timer_interrupt() {
+    unsafe {
+        sie::set_stimer();
+    }
+}
+
+/// Attribute the time elapsed since the last recorded switch to the
+/// current task's `user_time` or `kernel_time`, then reset the marker.
+fn record_switch(charge_to_user: bool) {
+    if let Some(task) = current_task() {
+        let mut inner = task.inner_exclusive_access();
+        let now = get_time_us();
+        let elapsed = now - inner.last_switch_time;
+        if charge_to_user {
+            inner.user_time += elapsed;
+        } else {
+            inner.kernel_time += elapsed;
+        }
+        inner.last_switch_time = now;
+    }
+}
+
+#[no_mangle]
+pub fn trap_handler() -> ! {
+    set_kernel_trap_entry();
+    record_switch(true);
+    let cx = current_trap_cx();
+    let scause = scause::read();
+    let stval = stval::read();
+    match scause.cause() {
+        Trap::Exception(Exception::UserEnvCall) => {
+            cx.sepc += 4;
+            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14]]);
+            let cx = current_trap_cx();
+            cx.x[10] = result as usize;
+        }
+        Trap::Exception(Exception::StorePageFault) | Trap::Exception(Exception::LoadPageFault) => {
+            let resolved = current_task()
+                .map(|task| {
+                    task.inner_exclusive_access()
+                        .memory_set
+                        .handle_lazy_page_fault(stval.into())
+                })
+                .unwrap_or(false);
+            if !resolved {
+                println!(
+                    "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+                    scause.cause(),
+                    stval,
+                    cx.sepc
+                );
+                exit_current_and_run_next(-2);
+            }
+        }
+        Trap::Exception(Exception::StoreFault)
+        | Trap::Exception(Exception::InstructionFault)
+        | Trap::Exception(Exception::InstructionPageFault)
+        | Trap::Exception(Exception::LoadFault) => {
+            println!(
+                "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+                scause.cause(),
+                stval,
+                cx.sepc
+            );
+            exit_current_and_run_next(-2);
+        }
+        Trap::Exception(Exception::IllegalInstruction) => {
+            println!("[kernel] IllegalInstruction in application, core dumped.");
+            exit_current_and_run_next(-3);
+        }
+        Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            set_next_trigger();
+            tick_block_cache_writeback();
+            suspend_current_and_run_next();
+        }
+        _ => {
+            panic!(
+                "Unsupported trap {:?}, stval = {:#x}!",
+                scause.cause(),
+                stval
+            );
+        }
+    }
+    handle_signal();
+    trap_return();
+}
+
+/// Advance easy-fs's dirty-block age counter and, every
+/// `BLOCK_CACHE_WRITEBACK_INTERVAL_TICKS` timer interrupts, flush any
+/// block that's been dirty for at least `BLOCK_CACHE_WRITEBACK_MAX_AGE_TICKS`
+/// ticks. Bounds how long a write can sit only in volatile cache without
+/// costing a full sync on every interrupt.
+fn tick_block_cache_writeback() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    static TICKS_SINCE_WRITEBACK: AtomicUsize = AtomicUsize::new(0);
+    easy_fs::tick();
+    if TICKS_SINCE_WRITEBACK.fetch_add(1, Ordering::Relaxed) + 1
+        >= BLOCK_CACHE_WRITEBACK_INTERVAL_TICKS
+    {
+        TICKS_SINCE_WRITEBACK.store(0, Ordering::Relaxed);
+        easy_fs::writeback_stale(BLOCK_CACHE_WRITEBACK_MAX_AGE_TICKS);
+    }
+}
+
+/// If the current task has a pending `SIGUSR` with a handler registered,
+/// and isn't already running one, divert its trap context to the handler
+/// so the next `trap_return` enters it instead of resuming normally. The
+/// interrupted `TrapContext` is pushed onto the task's own user stack,
+/// below its current `sp`, for `sys_sigreturn` to pop back off once the
+/// handler is done; the handler gets a fresh `sp` below that. A `SIGUSR`
+/// with no handler, or delivered while one is already running, is
+/// silently dropped.
+fn handle_signal() {
+    let Some(task) = current_task() else {
+        return;
+    };
+    let mut inner = task.inner_exclusive_access();
+    if !core::mem::take(&mut inner.sigusr_pending) || inner.in_sigusr_handler {
+        return;
+    }
+    let Some(handler) = inner.sigusr_handler else {
+        return;
+    };
+    let token = inner.user_token();
+    let cx = inner.trap_cx();
+    let saved = *cx;
+    let new_sp = (cx.x[2] - core::mem::size_of::<TrapContext>()) & !0xf;
+    write_user(token, new_sp as *mut TrapContext, saved);
+    inner.in_sigusr_handler = true;
+    cx.sepc = handler;
+    cx.x[10] = SIGUSR as usize;
+    cx.x[2] = new_sp;
+}
+
+#[no_mangle]
+pub fn trap_return() -> ! {
+    record_switch(false);
+    set_user_trap_entry();
+    let trap_cx_ptr = TRAP_CONTEXT_BASE;
+    let user_satp = current_user_token();
+    extern "C" {
+        fn __alltraps();
+        fn __restore();
+    }
+    let restore_va = __restore as usize - __alltraps as usize + TRAMPOLINE;
+    unsafe {
+        asm!(
+            "fence.i",
+            "jr {restore_va}",
+            restore_va = in(reg) restore_va,
+            in("a0") trap_cx_ptr,
+            in("a1") user_satp,
+            options(noreturn)
+        );
+    }
+}
+
+#[no_mangle]
+pub fn trap_from_kernel() -> ! {
+    panic!("a trap from kernel!");
+}
+
+pub use context::TrapContext;
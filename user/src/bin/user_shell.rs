@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::string::String;
+use user_lib::{exec, fork, read, waitpid};
+
+const LF: u8 = 0x0au8;
+const CR: u8 = 0x0du8;
+const BS: u8 = 0x08u8;
+const DL: u8 = 0x7fu8;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    println!("Rust user shell");
+    let mut line: String = String::new();
+    print!(">> ");
+    loop {
+        let mut c = [0u8; 1];
+        read(0, &mut c);
+        match c[0] {
+            LF | CR => {
+                println!("");
+                if !line.is_empty() {
+                    line.push('\0');
+                    let pid = fork();
+                    if pid == 0 {
+                        if exec(line.as_str()) == -1 {
+                            println!("Error when executing!");
+                            return -4;
+                        }
+                        unreachable!();
+                    } else {
+                        let mut exit_code: i32 = 0;
+                        waitpid(pid as usize, &mut exit_code);
+                    }
+                    line.clear();
+                }
+                print!(">> ");
+            }
+            BS | DL => {
+                if !line.is_empty() {
+                    print!("{}", BS as char);
+                    print!(" ");
+                    print!("{}", BS as char);
+                    line.pop();
+                }
+            }
+            _ => {
+                print!("{}", c[0] as char);
+                line.push(c[0] as char);
+            }
+        }
+    }
+}
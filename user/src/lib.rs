@@ -0,0 +1,689 @@
+#![no_std]
+#![feature(linkage)]
+#![feature(alloc_error_handler)]
+
+#[macro_use]
+pub mod console;
+mod lang_items;
+mod syscall;
+
+extern crate alloc;
+extern crate bitflags;
+
+use buddy_system_allocator::LockedHeap;
+use syscall::*;
+
+const USER_HEAP_SIZE: usize = 16384;
+
+static mut HEAP_SPACE: [u8; USER_HEAP_SIZE] = [0; USER_HEAP_SIZE];
+
+#[global_allocator]
+static HEAP: LockedHeap<32> = LockedHeap::empty();
+
+#[alloc_error_handler]
+pub fn handle_alloc_error(layout: core::alloc::Layout) -> ! {
+    panic!("Heap allocation error, layout = {:?}", layout);
+}
+
+#[no_mangle]
+#[link_section = ".text.entry"]
+pub extern "C" fn _start() -> ! {
+    unsafe {
+        HEAP.lock()
+            .init(HEAP_SPACE.as_ptr() as usize, USER_HEAP_SIZE);
+    }
+    exit(main());
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+fn main() -> i32 {
+    panic!("Cannot find main!");
+}
+
+bitflags::bitflags! {
+    pub struct OpenFlags: u32 {
+        const RDONLY = 0;
+        const WRONLY = 1 << 0;
+        const RDWR = 1 << 1;
+        const CREATE = 1 << 9;
+        const TRUNC = 1 << 10;
+        /// Open a handle to the path itself, neither readable nor
+        /// writable: `read`/`write` on the resulting fd always fail, but
+        /// `fstat` still works off it. There is no working `linkat` to use
+        /// it with.
+        const O_PATH = 1 << 13;
+        /// Close this fd automatically on `exec`.
+        const CLOEXEC = 1 << 14;
+    }
+}
+
+pub fn dup(fd: usize) -> isize {
+    sys_dup(fd)
+}
+pub fn open(path: &str, flags: OpenFlags) -> isize {
+    sys_open(path, flags.bits)
+}
+/// Passed as `dirfd` to `openat` to resolve `path` against the current
+/// working directory instead of an open directory fd.
+pub const AT_FDCWD: isize = -100;
+/// Like `open`, but `path` (if relative) is resolved against the open
+/// directory fd `dirfd` instead of always against the root; pass
+/// `AT_FDCWD` for `open`'s old behavior.
+pub fn openat(dirfd: isize, path: &str, flags: OpenFlags) -> isize {
+    sys_openat(dirfd, path, flags.bits)
+}
+pub fn close(fd: usize) -> isize {
+    sys_close(fd)
+}
+pub fn pipe(pipe_fd: &mut [usize]) -> isize {
+    sys_pipe(pipe_fd)
+}
+pub fn read(fd: usize, buf: &mut [u8]) -> isize {
+    sys_read(fd, buf)
+}
+pub fn write(fd: usize, buf: &[u8]) -> isize {
+    sys_write(fd, buf)
+}
+/// The subset of `fstat(2)`'s `struct stat` the kernel reports.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Stat {
+    pub dev: u64,
+    pub ino: u64,
+    pub mode: StatMode,
+    pub nlink: u32,
+    /// Number of 512-byte blocks actually allocated to this file, as `du`
+    /// wants.
+    pub blocks: u64,
+    /// The owning filesystem's block size in bytes.
+    pub blksize: u32,
+    pad: [u64; 5],
+}
+bitflags::bitflags! {
+    pub struct StatMode: u32 {
+        const NULL  = 0;
+        const DIR   = 0o040000;
+        const FILE  = 0o100000;
+        const CHAR  = 0o020000;
+        const BLOCK = 0o060000;
+        const FIFO  = 0o010000;
+    }
+}
+pub fn fstat(fd: usize, st: &mut Stat) -> isize {
+    sys_fstat(fd, st as *mut Stat)
+}
+/// Like `fstat`, but by path rather than fd, skipping the open/close dance.
+pub fn stat(path: &str, st: &mut Stat) -> isize {
+    sys_stat(path, st as *mut Stat)
+}
+/// Cumulative bytes moved through an fd's `read`/`write`, reported by
+/// `fd_stats` so an I/O monitor can attribute bandwidth to specific files.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FdStats {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+/// Report cumulative read/write bandwidth through `fd`. Returns -1 if `fd`
+/// isn't open.
+pub fn fd_stats(fd: usize, stats: &mut FdStats) -> isize {
+    sys_fd_stats(fd, stats as *mut FdStats)
+}
+/// Flush every buffered filesystem block to disk, across every open file.
+pub fn sync() -> isize {
+    sys_sync()
+}
+/// Flush `fd`'s data and inode metadata to disk.
+pub fn fsync(fd: usize) -> isize {
+    sys_fsync(fd)
+}
+/// Like `fsync`, but skips rewriting inode metadata that isn't needed to
+/// read the data back (e.g. a future timestamp-only update).
+pub fn fdatasync(fd: usize) -> isize {
+    sys_fdatasync(fd)
+}
+pub fn exit(exit_code: i32) -> ! {
+    sys_exit(exit_code);
+    panic!("sys_exit never returns!");
+}
+pub fn yield_() -> isize {
+    sys_yield()
+}
+/// Like `yield_`, but hand the CPU straight to `pid` on the very next
+/// switch instead of going through the back of the normal scheduling
+/// order. Useful for producer-consumer handoffs where the caller knows
+/// exactly which task should run next. Returns -1 (and still yields
+/// normally) if `pid` isn't currently runnable and queued.
+pub fn yield_to(pid: usize) -> isize {
+    sys_yield_to(pid)
+}
+/// Bound how long a `write` to the pipe write end `fd` will wait for
+/// buffer space before giving up and returning whatever it's written so
+/// far, instead of blocking on a slow or stuck reader forever. A negative
+/// `timeout_ms` clears the timeout and restores the old block-forever
+/// behavior. Returns -1 if `fd` isn't open.
+pub fn set_pipe_write_timeout(fd: usize, timeout_ms: isize) -> isize {
+    sys_set_pipe_write_timeout(fd, timeout_ms)
+}
+/// Mark `pid` as having a pending signal. Beyond `SIGUSR`, this kernel
+/// doesn't deliver handlers or distinguish signal numbers; the only
+/// observable effect is that a blocking wait the target is in (currently
+/// `poll` or `epoll_wait`) returns early. If `signum` is `SIGUSR` and `pid` has a
+/// handler registered via `sigaction`, it also runs that handler the next
+/// time `pid` returns to user space. Returns -1 if no task with that pid
+/// exists.
+pub fn kill(pid: usize, signum: u32) -> isize {
+    sys_kill(pid, signum)
+}
+/// The only catchable signal `sigaction`/`kill` know about.
+pub const SIGUSR: u32 = 10;
+/// Register `handler` to run the next time `SIGUSR` is delivered to the
+/// calling task, in place of the default "just wake up a blocking wait"
+/// behavior. `handler` is called with the signal number in its first
+/// argument and must end by calling `sigreturn` rather than returning
+/// normally. Passing a `handler` of 0 clears any registered handler.
+/// Returns -1 for any signal other than `SIGUSR`.
+pub fn sigaction(signo: u32, handler: usize) -> isize {
+    sys_sigaction(signo, handler)
+}
+/// Resume exactly where `SIGUSR` delivery interrupted the calling task.
+/// Must be the last thing a `SIGUSR` handler calls, and never called
+/// outside of one.
+pub fn sigreturn() -> isize {
+    sys_sigreturn()
+}
+/// `option` for `prctl`: read the calling task's behavior flags (`arg`
+/// ignored).
+pub const PR_GET_PROC_FLAGS: usize = 1;
+/// `option` for `prctl`: replace the calling task's behavior flags with
+/// `arg`'s bit pattern.
+pub const PR_SET_PROC_FLAGS: usize = 2;
+/// `PR_SET_PROC_FLAGS` bit: a hit against a per-process resource limit
+/// (currently: the fd table filling up) kills the calling task instead of
+/// the syscall that hit it returning -1.
+pub const PROC_FLAG_STRICT_RLIMIT: usize = 1 << 0;
+/// `PR_SET_PROC_FLAGS` bit: `mmap` maps and fills every page of a new
+/// mapping immediately instead of deferring each page to the first access
+/// that touches it.
+pub const PROC_FLAG_MMAP_EAGER: usize = 1 << 1;
+/// Read or write the calling task's `PR_*` behavior flags; see
+/// `PR_GET_PROC_FLAGS`/`PR_SET_PROC_FLAGS`. Returns -1 for an unrecognized
+/// `option`, or for `PR_SET_PROC_FLAGS`, an `arg` with bits outside the
+/// known `PROC_FLAG_*` set.
+pub fn prctl(option: usize, arg: usize) -> isize {
+    sys_prctl(option, arg)
+}
+pub fn get_time() -> isize {
+    sys_get_time()
+}
+/// Like `get_time`, but in nanoseconds rather than milliseconds. `clock_id`
+/// is accepted but ignored; this kernel has only one clock.
+pub fn clock_gettime_ns(clock_id: usize) -> u64 {
+    let mut ns = 0u64;
+    sys_clock_gettime_ns(clock_id, &mut ns as *mut u64);
+    ns
+}
+/// Flag for `clock_nanosleep`: `deadline_ns` names an absolute point on
+/// the clock rather than a duration relative to now.
+pub const TIMER_ABSTIME: usize = 1;
+/// Sleep until `deadline_ns` nanoseconds on `clock_id`'s clock, if `flags`
+/// has `TIMER_ABSTIME` set, or for `deadline_ns` nanoseconds from now
+/// otherwise. Prefer the absolute mode for periodic loops — each call
+/// re-reads the clock rather than adding onto how long the previous sleep
+/// actually took, so drift doesn't accumulate.
+pub fn clock_nanosleep(clock_id: usize, flags: usize, deadline_ns: u64) -> isize {
+    sys_clock_nanosleep(clock_id, flags, &deadline_ns as *const u64)
+}
+/// Create a FIFO (named pipe) at `path`. The only supported `kind` is
+/// `StatMode::FIFO`'s bit pattern; anything else fails.
+pub fn mknod(path: &str, kind: u32) -> isize {
+    sys_mknod(path, kind)
+}
+/// Scheduling policy IDs for `sched_setscheduler`.
+pub const SCHED_NORMAL: usize = 0;
+pub const SCHED_FIFO: usize = 1;
+/// Set the calling task's scheduling policy. `SCHED_FIFO` tasks always run
+/// ahead of every `SCHED_NORMAL` task, ordered by `priority` (higher
+/// first); `priority` is ignored for `SCHED_NORMAL` but must be at least 1
+/// for `SCHED_FIFO`. Returns -1 on an unknown policy or bad priority.
+pub fn sched_setscheduler(policy: usize, priority: usize) -> isize {
+    sys_sched_setscheduler(policy, priority)
+}
+/// A file descriptor to watch, passed to `poll`. Only `POLLIN` is
+/// meaningful; the kernel never sets anything else in `revents`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16,
+}
+pub const POLLIN: i16 = 0x0001;
+/// Returned by `poll`/`epoll_wait` when the wait was cut short by a `kill`-delivered
+/// signal rather than a ready fd or the timeout elapsing.
+pub const EINTR: isize = -2;
+/// Wait until a fd in `fds` is ready, `timeout_ms` milliseconds pass (a
+/// negative `timeout_ms` waits forever), or a signal arrives. Returns the
+/// number of ready fds (with `revents` filled in), 0 on timeout, or
+/// `EINTR` if interrupted.
+pub fn poll(fds: &mut [PollFd], timeout_ms: isize) -> isize {
+    sys_poll(fds, timeout_ms)
+}
+/// Copy up to `len` bytes currently buffered in `in_fd`'s pipe into
+/// `out_fd`'s pipe, without consuming them from `in_fd` — its reader still
+/// sees every byte. Returns the number of bytes copied, or -1 if either fd
+/// isn't a pipe.
+pub fn tee(in_fd: usize, out_fd: usize, len: usize) -> isize {
+    sys_tee(in_fd, out_fd, len)
+}
+/// Move up to `len` bytes between a pipe and a file without passing the
+/// data through a userspace buffer. Exactly one of `in_fd`/`out_fd` must
+/// refer to a pipe and the other to a regular file; `in_off`/`out_off`
+/// give the file's starting offset on whichever side is the file (the
+/// pipe side ignores its matching offset). Returns the number of bytes
+/// moved, or -1 on error.
+pub fn splice(in_fd: usize, in_off: usize, out_fd: usize, out_off: usize, len: usize) -> isize {
+    sys_splice(in_fd, in_off, out_fd, out_off, len)
+}
+/// Set the calling task's debug name, truncated if it's longer than the
+/// kernel's fixed-size name buffer.
+pub fn set_thread_name(name: &str) -> isize {
+    sys_set_thread_name(name.as_ptr())
+}
+/// Read `tid`'s debug name, as last set by `set_thread_name`, into `buf`.
+/// Returns -1 if no task with that pid exists.
+pub fn get_thread_name(tid: usize, buf: &mut [u8]) -> isize {
+    sys_get_thread_name(tid, buf.as_mut_ptr())
+}
+/// Fill `buf` with pseudorandom bytes from the kernel's PRNG. Not
+/// cryptographically secure — fine for hash seeds and test data, not for
+/// anything that needs to resist prediction.
+pub fn getrandom(buf: &mut [u8]) -> isize {
+    sys_getrandom(buf)
+}
+/// Advisory lock flags for `flock`, named after their Linux `flock(2)`
+/// counterparts. `LOCK_NB` is OR'd with `LOCK_SH`/`LOCK_EX` to fail instead
+/// of blocking when the lock can't be granted immediately.
+pub const LOCK_SH: u32 = 1;
+pub const LOCK_EX: u32 = 2;
+pub const LOCK_NB: u32 = 4;
+pub const LOCK_UN: u32 = 8;
+/// Take or release an advisory lock on `fd`'s underlying file, shared with
+/// every other fd this process has open on the same file. Released
+/// automatically once none of them are left open, or explicitly via
+/// `LOCK_UN`. Returns -1 if `fd` has no backing file, `op` is invalid, or
+/// (with `LOCK_NB`) the lock is already held incompatibly elsewhere.
+pub fn flock(fd: usize, op: u32) -> isize {
+    sys_flock(fd, op)
+}
+/// Flush every buffered filesystem block to disk and shut the machine down.
+/// Only the init process may call this; `cmd` is accepted but ignored.
+/// Never returns on success; returns -1 if the caller isn't the init
+/// process.
+pub fn reboot(cmd: usize) -> isize {
+    sys_reboot(cmd)
+}
+/// One scatter-gather segment, passed to `preadv`/`pwritev`. Modeled on
+/// POSIX `struct iovec`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IoVec {
+    pub base: *const u8,
+    pub len: usize,
+}
+/// Read `iov`'s segments from `fd` in order starting at `offset`, without
+/// moving `fd`'s own read cursor. Returns the total bytes read, which is
+/// less than the sum of `iov` lengths at EOF, or -1 if `fd` has no backing
+/// file.
+pub fn preadv(fd: usize, iov: &[IoVec], offset: usize) -> isize {
+    sys_preadv(fd, iov, offset)
+}
+/// Write `iov`'s segments to `fd` in order starting at `offset`, without
+/// moving `fd`'s own write cursor. Returns the total bytes written, or -1
+/// if `fd` has no backing file.
+pub fn pwritev(fd: usize, iov: &[IoVec], offset: usize) -> isize {
+    sys_pwritev(fd, iov, offset)
+}
+/// User/kernel time split, both in microseconds, as reported by `times`.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+pub struct TimeStat {
+    pub utime: usize,
+    pub stime: usize,
+}
+pub fn times(ts: &mut TimeStat) -> isize {
+    sys_times(ts as *mut _)
+}
+/// Ready-queue length and longest wait (microseconds), as reported by
+/// `sched_stats`.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+pub struct SchedStats {
+    pub ready_len: usize,
+    pub max_wait_us: usize,
+}
+pub fn sched_stats(stats: &mut SchedStats) -> isize {
+    sys_sched_stats(stats as *mut _)
+}
+pub fn getpid() -> isize {
+    sys_getpid()
+}
+/// Always 0 — this kernel has no real thread model, so a process's one
+/// and only thread is canonically tid 0, distinct from its pid.
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+/// Memory footprint, both in pages, as reported by `getrusage`.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+pub struct MemStat {
+    /// Pages currently backed by a physical frame.
+    pub rss_pages: usize,
+    /// Pages reserved across every mapping, including a lazy mmap area's
+    /// pages that haven't been faulted in yet.
+    pub vsize_pages: usize,
+}
+pub fn getrusage(stat: &mut MemStat) -> isize {
+    sys_getrusage(stat as *mut _)
+}
+bitflags::bitflags! {
+    /// Mirrors the kernel's `CloseRangeFlags`; see `close_range`.
+    pub struct CloseRangeFlags: u32 {
+        const CLOEXEC = 1 << 0;
+    }
+}
+/// Close every open fd in `[first, last]` (inclusive) — or, with
+/// `CloseRangeFlags::CLOEXEC`, leave each open but mark it close-on-exec
+/// instead. Tolerates an already-closed fd or a range past the end of
+/// the fd table.
+pub fn close_range(first: usize, last: usize, flags: CloseRangeFlags) -> isize {
+    sys_close_range(first, last, flags.bits)
+}
+/// Mirrors the kernel's only recognized `sys_setrlimit`/`sys_getrlimit`
+/// resource: the heap grown by `sbrk`.
+pub const RLIMIT_DATA: usize = 0;
+/// Set the calling process's limit on `resource`, in bytes. Only
+/// `RLIMIT_DATA` is recognized; anything else returns -1.
+pub fn setrlimit(resource: usize, limit: usize) -> isize {
+    sys_setrlimit(resource, limit)
+}
+/// Read the calling process's current limit on `resource` back into
+/// `limit`. Only `RLIMIT_DATA` is recognized; anything else returns -1.
+pub fn getrlimit(resource: usize, limit: &mut usize) -> isize {
+    sys_getrlimit(resource, limit as *mut _)
+}
+pub fn fork() -> isize {
+    sys_fork()
+}
+bitflags::bitflags! {
+    /// Mirrors the kernel's `CloneFlags`; see `clone`.
+    pub struct CloneFlags: usize {
+        /// Share the fd table with the parent instead of copying it.
+        const FILES = 1 << 0;
+        /// Share the parent's address space. Not currently supported;
+        /// `clone` returns -1 if this bit is set.
+        const VM = 1 << 1;
+    }
+}
+/// Generalized `fork`: `flags` picks which resources the child shares
+/// with the parent instead of copying. `flags` empty is equivalent to
+/// `fork`. `stack` is reserved for future address-space-sharing support
+/// and is currently unused.
+pub fn clone(flags: CloneFlags, stack: usize) -> isize {
+    sys_clone(flags.bits, stack)
+}
+/// Add/modify/remove `fd`'s interest on an epoll instance, per `op`.
+pub const EPOLL_CTL_ADD: usize = 1;
+pub const EPOLL_CTL_MOD: usize = 2;
+pub const EPOLL_CTL_DEL: usize = 3;
+/// There's data to read on a registered fd, the only bit `epoll_wait` ever
+/// sets.
+pub const EPOLLIN: i32 = 0x0001;
+/// One ready fd, filled in by `epoll_wait`. `data` carries back whatever
+/// was passed to `epoll_ctl` when `fd` was registered (typically the fd
+/// itself). Modeled on POSIX `epoll_event`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct EpollEvent {
+    pub events: i32,
+    pub data: u64,
+}
+/// Create a new epoll instance, returning its fd. Unlike `poll`, interest
+/// is registered once via `epoll_ctl` instead of being re-specified on
+/// every wait.
+pub fn epoll_create() -> isize {
+    sys_epoll_create()
+}
+/// Register/modify/remove `fd`'s interest on the epoll instance `epfd`.
+/// `event` is read for `EPOLL_CTL_ADD`/`EPOLL_CTL_MOD`; ignored for
+/// `EPOLL_CTL_DEL`. Returns -1 if `epfd` isn't an open epoll instance, or
+/// `op` doesn't apply (e.g. `ADD` on an already-registered `fd`).
+pub fn epoll_ctl(epfd: usize, op: usize, fd: usize, event: &EpollEvent) -> isize {
+    sys_epoll_ctl(epfd, op, fd, event as *const _)
+}
+/// Wait until a fd registered on the epoll instance `epfd` is ready,
+/// `timeout_ms` milliseconds pass (a negative `timeout_ms` waits forever),
+/// or a signal arrives. Writes up to `events.len()` ready fds and returns
+/// how many, 0 on timeout, or `EINTR` if interrupted.
+pub fn epoll_wait(epfd: usize, events: &mut [EpollEvent], timeout_ms: isize) -> isize {
+    sys_epoll_wait(epfd, events.as_mut_ptr(), events.len(), timeout_ms)
+}
+pub fn exec(path: &str) -> isize {
+    sys_exec(path)
+}
+/// Like `exec`, but runs the ELF already open at `fd` instead of
+/// resolving a path, so a caller that opened and verified the file isn't
+/// exposed to a TOCTOU race against whatever the path now resolves to.
+/// Works on an unlinked-but-open fd too. Returns -1 if `fd` isn't open,
+/// isn't readable, or isn't a regular file.
+pub fn fexecve(fd: usize) -> isize {
+    sys_fexecve(fd)
+}
+/// Wait for any child to exit, reporting its exit code through
+/// `exit_code` and returning its pid. Blocks in the kernel rather than
+/// spinning here; returns -1 if the caller has no children at all.
+pub fn wait(exit_code: &mut i32) -> isize {
+    sys_waitpid(-1, exit_code as *mut _, core::ptr::null_mut())
+}
+/// Like `wait`, but for a specific `pid` rather than any child.
+pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
+    sys_waitpid(pid as isize, exit_code as *mut _, core::ptr::null_mut())
+}
+/// Resource usage accumulated by a reaped child, as reported by
+/// `waitpid_rusage`. `block_io_count` is always 0 — see `ChildRusage` on
+/// the kernel side.
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+pub struct ChildRusage {
+    pub utime: usize,
+    pub stime: usize,
+    pub switch_count: usize,
+    pub block_io_count: usize,
+}
+/// Like `waitpid`, but also reports the reaped child's accumulated CPU
+/// time and voluntary context switches through `rusage`.
+pub fn waitpid_rusage(pid: usize, exit_code: &mut i32, rusage: &mut ChildRusage) -> isize {
+    sys_waitpid(pid as isize, exit_code as *mut _, rusage as *mut _)
+}
+pub fn sbrk(size: i32) -> isize {
+    sys_sbrk(size)
+}
+pub fn set_priority(prio: isize) -> isize {
+    sys_set_priority(prio)
+}
+/// Like `set_priority`, but for `pid` rather than the caller, so a
+/// supervisor can tune another process's scheduling priority. Returns -1 if
+/// `prio < 2` or no task with that pid exists.
+pub fn setpriority(pid: usize, prio: isize) -> isize {
+    sys_setpriority(pid, prio)
+}
+/// Read `pid`'s current scheduling priority. Returns -1 if no task with
+/// that pid exists.
+pub fn getpriority(pid: usize) -> isize {
+    sys_getpriority(pid)
+}
+/// Read `local_buf.len()` bytes out of `pid`'s address space at
+/// `remote_addr` into `local_buf`. Only `pid`'s parent may call this.
+/// Returns -1 if `pid` doesn't exist, the caller isn't its parent, or the
+/// requested range isn't fully mapped in its address space.
+pub fn peek(pid: usize, remote_addr: usize, local_buf: &mut [u8]) -> isize {
+    sys_peek(pid, remote_addr, local_buf)
+}
+/// Report which pages of a mapping are currently backed by a physical
+/// frame (a lazily-mapped page that hasn't been touched yet isn't). Writes
+/// one byte per page of `[start, start + len)` into `vec` (1 resident, 0
+/// not) and returns the number of pages, or -1 if `start` isn't
+/// page-aligned.
+pub fn mincore(start: usize, len: usize, vec: &mut [u8]) -> isize {
+    sys_mincore(start, len, vec)
+}
+pub fn mutex_create() -> isize {
+    sys_mutex_create(false)
+}
+pub fn mutex_blocking_create() -> isize {
+    sys_mutex_create(true)
+}
+pub fn mutex_lock(mutex_id: usize) {
+    sys_mutex_lock(mutex_id);
+}
+pub fn mutex_unlock(mutex_id: usize) {
+    sys_mutex_unlock(mutex_id);
+}
+/// Like `mutex_lock`, but returns immediately instead of blocking. Returns
+/// `true` if the mutex was acquired, `false` if it was already locked.
+pub fn mutex_try_lock(mutex_id: usize) -> bool {
+    sys_mutex_try_lock(mutex_id) == 0
+}
+pub fn semaphore_create(res_count: usize) -> isize {
+    sys_semaphore_create(res_count)
+}
+pub fn semaphore_up(sem_id: usize) {
+    sys_semaphore_up(sem_id);
+}
+/// `true` if a resource was acquired, `false` if `sem_id` was destroyed
+/// (via `semaphore_destroy`) either before this call or while it was
+/// blocked waiting.
+pub fn semaphore_down(sem_id: usize) -> bool {
+    sys_semaphore_down(sem_id) == 0
+}
+/// Wake every task blocked in `semaphore_down` on `sem_id` with a failure
+/// and free its slot for reuse.
+pub fn semaphore_destroy(sem_id: usize) {
+    sys_semaphore_destroy(sem_id);
+}
+pub fn condvar_create() -> isize {
+    sys_condvar_create()
+}
+pub fn condvar_signal(condvar_id: usize) {
+    sys_condvar_signal(condvar_id);
+}
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) {
+    sys_condvar_wait(condvar_id, mutex_id);
+}
+/// Create a cancellation token for use with `condvar_wait_cancellable`.
+pub fn cancel_token_create() -> isize {
+    sys_cancel_token_create()
+}
+/// Abort a wait on `condvar_id` that's blocked on `token_id`, if one exists.
+pub fn cancel_token_cancel(condvar_id: usize, token_id: usize) {
+    sys_cancel_token_cancel(condvar_id, token_id);
+}
+/// Like `condvar_wait`, but also returns if `token_id` is cancelled first.
+/// Returns `true` if the wait was cancelled, `false` if it was signaled.
+pub fn condvar_wait_cancellable(condvar_id: usize, mutex_id: usize, token_id: usize) -> bool {
+    sys_condvar_wait_cancellable(condvar_id, mutex_id, token_id) == 1
+}
+/// Create an eventfd-style counter fd seeded at `initval`, returning its fd.
+pub fn eventfd(initval: u64) -> isize {
+    sys_eventfd(initval)
+}
+/// Issue a full memory fence so writes made before this call are visible
+/// to other tasks' reads made after they observe the effect of this call.
+pub fn membarrier() {
+    sys_membarrier();
+}
+/// List a directory fd's entries into `buf` as NUL-terminated names,
+/// picking up after wherever the previous call on this fd left off.
+/// Returns the number of bytes written, 0 once every entry has been
+/// returned, or -1 if `fd` isn't a directory.
+pub fn getdents(fd: usize, buf: &mut [u8]) -> isize {
+    sys_getdents(fd, buf.as_mut_ptr(), buf.len())
+}
+/// `filter` values for `getdents_filtered`.
+pub const GETDENTS_FILTER_ALL: i32 = 0;
+pub const GETDENTS_FILTER_DIRS_ONLY: i32 = 1;
+pub const GETDENTS_FILTER_FILES_ONLY: i32 = 2;
+/// Like `getdents`, but only returns entries matching `filter`
+/// (`GETDENTS_FILTER_*`), so e.g. a shell completing directory names
+/// doesn't have to `fstat` every entry itself to throw away the ones it
+/// doesn't care about. Shares `getdents`'s fd cursor: entries the filter
+/// skips still advance it.
+pub fn getdents_filtered(fd: usize, buf: &mut [u8], filter: i32) -> isize {
+    sys_getdents_filtered(fd, buf.as_mut_ptr(), buf.len(), filter)
+}
+/// Flush only the cached blocks backing `[offset, offset + len)` of `fd`'s
+/// file, rather than a whole-file fsync.
+pub fn sync_file_range(fd: usize, offset: usize, len: usize) -> isize {
+    sys_sync_file_range(fd, offset, len)
+}
+/// Create an anonymous, RAM-backed file and return a readable+writable fd
+/// to it. `name` is purely descriptive (there's nowhere in the directory
+/// tree to show it) and must be NUL-terminated. The file is reclaimed as
+/// soon as every fd referencing it is closed; it never touches disk.
+pub fn memfd_create(name: &str) -> isize {
+    sys_memfd_create(name)
+}
+/// Resize the memfd (or other file supporting it) open at `fd` to exactly
+/// `len` bytes, zero-filling any new space. Returns -1 if `fd` doesn't
+/// support being resized this way.
+pub fn ftruncate(fd: usize, len: usize) -> isize {
+    sys_ftruncate(fd, len)
+}
+/// Set `tid`'s CPU affinity mask, one bit per CPU. Groundwork for SMP:
+/// this build has only CPU 0, so only a mask of exactly `1` succeeds.
+/// Returns -1 if `tid` doesn't exist or `mask` isn't valid.
+pub fn sched_setaffinity(tid: usize, mask: usize) -> isize {
+    sys_sched_setaffinity(tid, mask)
+}
+/// Read back `tid`'s CPU affinity mask. Returns -1 if `tid` doesn't
+/// exist.
+pub fn sched_getaffinity(tid: usize) -> isize {
+    sys_sched_getaffinity(tid)
+}
+/// Set `owner`'s filesystem block quota, in data blocks. Always succeeds;
+/// there's only one mounted filesystem, so this isn't per-fd. A write that
+/// would push `owner`'s usage over this limit fails once the quota is
+/// reached, regardless of which open fd it comes through.
+pub fn setquota(owner: usize, blocks: usize) -> isize {
+    sys_setquota(owner, blocks)
+}
+/// Reassign the quota-tracking owner id of the file open at `fd`. Returns
+/// -1 if `fd` has no backing filesystem inode to reassign (a pipe,
+/// `Stdin`/`Stdout`, or a `memfd_create` fd).
+pub fn set_owner(fd: usize, owner: usize) -> isize {
+    sys_set_owner(fd, owner)
+}
+/// Free `[offset, offset + len)` of `fd`'s file, deallocating every data
+/// block fully covered by the range so it reads back as zeros, without
+/// changing the file's size.
+pub fn fpunch_hole(fd: usize, offset: usize, len: usize) -> isize {
+    sys_fpunch_hole(fd, offset, len)
+}
+/// Block until another task calls `futex_wake` on `addr`, but only if the
+/// `u32` stored there still equals `expected` at the moment this is
+/// called. Meant to be paired with a fast-path atomic compare-and-swap in
+/// userspace: only fall back to this once the fast path has found the
+/// word already held by someone else, so contended and uncontended locks
+/// cost the same when uncontended. Returns -1 if `addr` isn't mapped or
+/// the word no longer matches `expected` (the caller should retry its
+/// fast path instead of treating this as an error), 0 once woken.
+pub fn futex_wait(addr: &u32, expected: u32) -> isize {
+    sys_futex_wait(addr as *const u32 as usize, expected)
+}
+/// Wake up to `n` tasks blocked in `futex_wait` on the same word as
+/// `addr`. Returns how many were actually woken, or -1 if `addr` isn't
+/// mapped.
+pub fn futex_wake(addr: &u32, n: usize) -> isize {
+    sys_futex_wake(addr as *const u32 as usize, n)
+}
@@ -0,0 +1,437 @@
+use crate::{ChildRusage, EpollEvent, FdStats, IoVec, MemStat, PollFd, SchedStats, Stat, TimeStat};
+
+const SYSCALL_DUP: usize = 24;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_READ: usize = 63;
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_SYNC: usize = 81;
+const SYSCALL_FSYNC: usize = 82;
+const SYSCALL_FDATASYNC: usize = 83;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_STAT: usize = 79;
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_PRCTL: usize = 167;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_MUTEX_CREATE: usize = 1010;
+const SYSCALL_MUTEX_LOCK: usize = 1011;
+const SYSCALL_MUTEX_UNLOCK: usize = 1012;
+const SYSCALL_MUTEX_TRY_LOCK: usize = 1013;
+const SYSCALL_SEMAPHORE_CREATE: usize = 1020;
+const SYSCALL_SEMAPHORE_UP: usize = 1021;
+const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
+const SYSCALL_SEMAPHORE_DESTROY: usize = 1023;
+const SYSCALL_CONDVAR_CREATE: usize = 1030;
+const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
+const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_EVENTFD: usize = 1040;
+const SYSCALL_MEMBARRIER: usize = 1041;
+const SYSCALL_GETDENTS: usize = 1042;
+const SYSCALL_SYNC_FILE_RANGE: usize = 1043;
+const SYSCALL_CANCEL_TOKEN_CREATE: usize = 1044;
+const SYSCALL_CANCEL_TOKEN_CANCEL: usize = 1045;
+const SYSCALL_CONDVAR_WAIT_CANCELLABLE: usize = 1046;
+const SYSCALL_CLOCK_GETTIME_NS: usize = 1050;
+const SYSCALL_MKNOD: usize = 1051;
+const SYSCALL_SCHED_SETSCHEDULER: usize = 1052;
+const SYSCALL_POLL: usize = 1053;
+const SYSCALL_TEE: usize = 1054;
+const SYSCALL_GETRANDOM: usize = 1055;
+const SYSCALL_FLOCK: usize = 1056;
+const SYSCALL_REBOOT: usize = 1057;
+const SYSCALL_PREADV: usize = 1058;
+const SYSCALL_PWRITEV: usize = 1059;
+const SYSCALL_SETPRIORITY: usize = 1060;
+const SYSCALL_GETPRIORITY: usize = 1061;
+const SYSCALL_MINCORE: usize = 1062;
+const SYSCALL_SIGACTION: usize = 1063;
+const SYSCALL_SIGRETURN: usize = 1064;
+const SYSCALL_OPENAT: usize = 1065;
+const SYSCALL_PEEK: usize = 1066;
+const SYSCALL_SPLICE: usize = 1067;
+const SYSCALL_SET_THREAD_NAME: usize = 1068;
+const SYSCALL_GET_THREAD_NAME: usize = 1069;
+const SYSCALL_CLOCK_NANOSLEEP: usize = 1070;
+const SYSCALL_YIELD_TO: usize = 1071;
+const SYSCALL_SET_PIPE_WRITE_TIMEOUT: usize = 1072;
+const SYSCALL_CLONE: usize = 1073;
+const SYSCALL_SCHED_STATS: usize = 1074;
+const SYSCALL_EPOLL_CREATE: usize = 1075;
+const SYSCALL_EPOLL_CTL: usize = 1076;
+const SYSCALL_EPOLL_WAIT: usize = 1077;
+const SYSCALL_FPUNCH_HOLE: usize = 1078;
+const SYSCALL_FUTEX_WAIT: usize = 1079;
+const SYSCALL_FUTEX_WAKE: usize = 1080;
+const SYSCALL_FD_STATS: usize = 1081;
+const SYSCALL_FEXECVE: usize = 1082;
+const SYSCALL_GETDENTS_FILTERED: usize = 1083;
+const SYSCALL_MEMFD_CREATE: usize = 1084;
+const SYSCALL_FTRUNCATE: usize = 1085;
+const SYSCALL_SCHED_SETAFFINITY: usize = 1086;
+const SYSCALL_SCHED_GETAFFINITY: usize = 1087;
+const SYSCALL_SETQUOTA: usize = 1088;
+const SYSCALL_SET_OWNER: usize = 1089;
+const SYSCALL_GETTID: usize = 1090;
+const SYSCALL_GETRUSAGE: usize = 1091;
+const SYSCALL_CLOSE_RANGE: usize = 1092;
+const SYSCALL_SETRLIMIT: usize = 1093;
+const SYSCALL_GETRLIMIT: usize = 1094;
+
+fn syscall(id: usize, args: [usize; 3]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x17") id,
+        );
+    }
+    ret
+}
+
+/// Like `syscall`, but for the rare call that needs a fourth argument.
+fn syscall4(id: usize, args: [usize; 4]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x17") id,
+        );
+    }
+    ret
+}
+
+/// Like `syscall4`, but for the rarer call that needs a fifth argument.
+fn syscall5(id: usize, args: [usize; 5]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x14") args[4],
+            in("x17") id,
+        );
+    }
+    ret
+}
+
+pub fn sys_dup(fd: usize) -> isize {
+    syscall(SYSCALL_DUP, [fd, 0, 0])
+}
+pub fn sys_open(path: &str, flags: u32) -> isize {
+    syscall(SYSCALL_OPEN, [path.as_ptr() as usize, flags as usize, 0])
+}
+pub fn sys_openat(dirfd: isize, path: &str, flags: u32) -> isize {
+    syscall(
+        SYSCALL_OPENAT,
+        [dirfd as usize, path.as_ptr() as usize, flags as usize],
+    )
+}
+pub fn sys_close(fd: usize) -> isize {
+    syscall(SYSCALL_CLOSE, [fd, 0, 0])
+}
+pub fn sys_pipe(pipe: &mut [usize]) -> isize {
+    syscall(SYSCALL_PIPE, [pipe.as_mut_ptr() as usize, 0, 0])
+}
+pub fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_READ,
+        [fd, buffer.as_mut_ptr() as usize, buffer.len()],
+    )
+}
+pub fn sys_write(fd: usize, buffer: &[u8]) -> isize {
+    syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
+}
+pub fn sys_sync() -> isize {
+    syscall(SYSCALL_SYNC, [0, 0, 0])
+}
+pub fn sys_fsync(fd: usize) -> isize {
+    syscall(SYSCALL_FSYNC, [fd, 0, 0])
+}
+pub fn sys_fdatasync(fd: usize) -> isize {
+    syscall(SYSCALL_FDATASYNC, [fd, 0, 0])
+}
+pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
+    syscall(SYSCALL_FSTAT, [fd, st as usize, 0])
+}
+pub fn sys_stat(path: &str, st: *mut Stat) -> isize {
+    syscall(SYSCALL_STAT, [path.as_ptr() as usize, st as usize, 0])
+}
+pub fn sys_exit(exit_code: i32) -> isize {
+    syscall(SYSCALL_EXIT, [exit_code as usize, 0, 0])
+}
+pub fn sys_yield() -> isize {
+    syscall(SYSCALL_YIELD, [0, 0, 0])
+}
+pub fn sys_kill(pid: usize, signum: u32) -> isize {
+    syscall(SYSCALL_KILL, [pid, signum as usize, 0])
+}
+pub fn sys_set_priority(prio: isize) -> isize {
+    syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
+}
+pub fn sys_get_time() -> isize {
+    syscall(SYSCALL_GET_TIME, [0, 0, 0])
+}
+pub fn sys_clock_gettime_ns(clock_id: usize, ns: *mut u64) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME_NS, [clock_id, ns as usize, 0])
+}
+pub fn sys_mknod(path: &str, kind: u32) -> isize {
+    syscall(SYSCALL_MKNOD, [path.as_ptr() as usize, kind as usize, 0])
+}
+pub fn sys_sched_setscheduler(policy: usize, priority: usize) -> isize {
+    syscall(SYSCALL_SCHED_SETSCHEDULER, [policy, priority, 0])
+}
+pub fn sys_poll(fds: &mut [PollFd], timeout_ms: isize) -> isize {
+    syscall(
+        SYSCALL_POLL,
+        [fds.as_mut_ptr() as usize, fds.len(), timeout_ms as usize],
+    )
+}
+pub fn sys_tee(in_fd: usize, out_fd: usize, len: usize) -> isize {
+    syscall(SYSCALL_TEE, [in_fd, out_fd, len])
+}
+pub fn sys_splice(in_fd: usize, in_off: usize, out_fd: usize, out_off: usize, len: usize) -> isize {
+    syscall5(SYSCALL_SPLICE, [in_fd, in_off, out_fd, out_off, len])
+}
+pub fn sys_set_thread_name(name: *const u8) -> isize {
+    syscall(SYSCALL_SET_THREAD_NAME, [name as usize, 0, 0])
+}
+pub fn sys_get_thread_name(tid: usize, buf: *mut u8) -> isize {
+    syscall(SYSCALL_GET_THREAD_NAME, [tid, buf as usize, 0])
+}
+pub fn sys_clock_nanosleep(clock_id: usize, flags: usize, deadline_ns: *const u64) -> isize {
+    syscall(
+        SYSCALL_CLOCK_NANOSLEEP,
+        [clock_id, flags, deadline_ns as usize],
+    )
+}
+pub fn sys_yield_to(pid: usize) -> isize {
+    syscall(SYSCALL_YIELD_TO, [pid, 0, 0])
+}
+pub fn sys_set_pipe_write_timeout(fd: usize, timeout_ms: isize) -> isize {
+    syscall(SYSCALL_SET_PIPE_WRITE_TIMEOUT, [fd, timeout_ms as usize, 0])
+}
+pub fn sys_getrandom(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETRANDOM, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+pub fn sys_flock(fd: usize, op: u32) -> isize {
+    syscall(SYSCALL_FLOCK, [fd, op as usize, 0])
+}
+pub fn sys_reboot(cmd: usize) -> isize {
+    syscall(SYSCALL_REBOOT, [cmd, 0, 0])
+}
+pub fn sys_peek(pid: usize, remote_addr: usize, local_buf: &mut [u8]) -> isize {
+    syscall4(
+        SYSCALL_PEEK,
+        [
+            pid,
+            remote_addr,
+            local_buf.as_mut_ptr() as usize,
+            local_buf.len(),
+        ],
+    )
+}
+pub fn sys_preadv(fd: usize, iov: &[IoVec], offset: usize) -> isize {
+    syscall4(
+        SYSCALL_PREADV,
+        [fd, iov.as_ptr() as usize, iov.len(), offset],
+    )
+}
+pub fn sys_pwritev(fd: usize, iov: &[IoVec], offset: usize) -> isize {
+    syscall4(
+        SYSCALL_PWRITEV,
+        [fd, iov.as_ptr() as usize, iov.len(), offset],
+    )
+}
+pub fn sys_setpriority(pid: usize, prio: isize) -> isize {
+    syscall(SYSCALL_SETPRIORITY, [pid, prio as usize, 0])
+}
+pub fn sys_getpriority(pid: usize) -> isize {
+    syscall(SYSCALL_GETPRIORITY, [pid, 0, 0])
+}
+pub fn sys_mincore(start: usize, len: usize, vec: &mut [u8]) -> isize {
+    syscall(SYSCALL_MINCORE, [start, len, vec.as_mut_ptr() as usize])
+}
+pub fn sys_sigaction(signo: u32, handler: usize) -> isize {
+    syscall(SYSCALL_SIGACTION, [signo as usize, handler, 0])
+}
+pub fn sys_sigreturn() -> isize {
+    syscall(SYSCALL_SIGRETURN, [0, 0, 0])
+}
+pub fn sys_prctl(option: usize, arg: usize) -> isize {
+    syscall(SYSCALL_PRCTL, [option, arg, 0])
+}
+pub fn sys_times(ts: *mut TimeStat) -> isize {
+    syscall(SYSCALL_TIMES, [ts as usize, 0, 0])
+}
+pub fn sys_sched_stats(stats: *mut SchedStats) -> isize {
+    syscall(SYSCALL_SCHED_STATS, [stats as usize, 0, 0])
+}
+pub fn sys_getpid() -> isize {
+    syscall(SYSCALL_GETPID, [0, 0, 0])
+}
+pub fn sys_gettid() -> isize {
+    syscall(SYSCALL_GETTID, [0, 0, 0])
+}
+pub fn sys_getrusage(stat: *mut MemStat) -> isize {
+    syscall(SYSCALL_GETRUSAGE, [stat as usize, 0, 0])
+}
+pub fn sys_close_range(first: usize, last: usize, flags: u32) -> isize {
+    syscall(SYSCALL_CLOSE_RANGE, [first, last, flags as usize])
+}
+pub fn sys_setrlimit(resource: usize, limit: usize) -> isize {
+    syscall(SYSCALL_SETRLIMIT, [resource, limit, 0])
+}
+pub fn sys_getrlimit(resource: usize, limit: *mut usize) -> isize {
+    syscall(SYSCALL_GETRLIMIT, [resource, limit as usize, 0])
+}
+pub fn sys_sbrk(size: i32) -> isize {
+    syscall(SYSCALL_SBRK, [size as usize, 0, 0])
+}
+pub fn sys_fork() -> isize {
+    syscall(SYSCALL_FORK, [0, 0, 0])
+}
+pub fn sys_clone(flags: usize, stack: usize) -> isize {
+    syscall(SYSCALL_CLONE, [flags, stack, 0])
+}
+pub fn sys_epoll_create() -> isize {
+    syscall(SYSCALL_EPOLL_CREATE, [0, 0, 0])
+}
+pub fn sys_epoll_ctl(epfd: usize, op: usize, fd: usize, event: *const EpollEvent) -> isize {
+    syscall4(SYSCALL_EPOLL_CTL, [epfd, op, fd, event as usize])
+}
+pub fn sys_epoll_wait(
+    epfd: usize,
+    events: *mut EpollEvent,
+    maxevents: usize,
+    timeout_ms: isize,
+) -> isize {
+    syscall4(
+        SYSCALL_EPOLL_WAIT,
+        [epfd, events as usize, maxevents, timeout_ms as usize],
+    )
+}
+pub fn sys_fpunch_hole(fd: usize, offset: usize, len: usize) -> isize {
+    syscall(SYSCALL_FPUNCH_HOLE, [fd, offset, len])
+}
+pub fn sys_futex_wait(addr: usize, expected: u32) -> isize {
+    syscall(SYSCALL_FUTEX_WAIT, [addr, expected as usize, 0])
+}
+pub fn sys_futex_wake(addr: usize, n: usize) -> isize {
+    syscall(SYSCALL_FUTEX_WAKE, [addr, n, 0])
+}
+pub fn sys_fd_stats(fd: usize, stats: *mut FdStats) -> isize {
+    syscall(SYSCALL_FD_STATS, [fd, stats as usize, 0])
+}
+pub fn sys_exec(path: &str) -> isize {
+    syscall(SYSCALL_EXEC, [path.as_ptr() as usize, 0, 0])
+}
+pub fn sys_fexecve(fd: usize) -> isize {
+    syscall(SYSCALL_FEXECVE, [fd, 0, 0])
+}
+pub fn sys_waitpid(pid: isize, exit_code: *mut i32, rusage: *mut ChildRusage) -> isize {
+    syscall(
+        SYSCALL_WAITPID,
+        [pid as usize, exit_code as usize, rusage as usize],
+    )
+}
+pub fn sys_mutex_create(blocking: bool) -> isize {
+    syscall(SYSCALL_MUTEX_CREATE, [blocking as usize, 0, 0])
+}
+pub fn sys_mutex_lock(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_LOCK, [id, 0, 0])
+}
+pub fn sys_mutex_unlock(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_UNLOCK, [id, 0, 0])
+}
+pub fn sys_mutex_try_lock(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_TRY_LOCK, [id, 0, 0])
+}
+pub fn sys_semaphore_create(res_count: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_CREATE, [res_count, 0, 0])
+}
+pub fn sys_semaphore_up(id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_UP, [id, 0, 0])
+}
+pub fn sys_semaphore_down(id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_DOWN, [id, 0, 0])
+}
+pub fn sys_semaphore_destroy(id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_DESTROY, [id, 0, 0])
+}
+pub fn sys_condvar_create() -> isize {
+    syscall(SYSCALL_CONDVAR_CREATE, [0, 0, 0])
+}
+pub fn sys_condvar_signal(id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_SIGNAL, [id, 0, 0])
+}
+pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_WAIT, [condvar_id, mutex_id, 0])
+}
+pub fn sys_eventfd(initval: u64) -> isize {
+    syscall(SYSCALL_EVENTFD, [initval as usize, 0, 0])
+}
+pub fn sys_membarrier() -> isize {
+    syscall(SYSCALL_MEMBARRIER, [0, 0, 0])
+}
+pub fn sys_getdents(fd: usize, buf: *mut u8, len: usize) -> isize {
+    syscall(SYSCALL_GETDENTS, [fd, buf as usize, len])
+}
+pub fn sys_getdents_filtered(fd: usize, buf: *mut u8, len: usize, filter: i32) -> isize {
+    syscall4(
+        SYSCALL_GETDENTS_FILTERED,
+        [fd, buf as usize, len, filter as usize],
+    )
+}
+pub fn sys_sync_file_range(fd: usize, offset: usize, len: usize) -> isize {
+    syscall(SYSCALL_SYNC_FILE_RANGE, [fd, offset, len])
+}
+pub fn sys_memfd_create(name: &str) -> isize {
+    syscall(SYSCALL_MEMFD_CREATE, [name.as_ptr() as usize, 0, 0])
+}
+pub fn sys_ftruncate(fd: usize, len: usize) -> isize {
+    syscall(SYSCALL_FTRUNCATE, [fd, len, 0])
+}
+pub fn sys_sched_setaffinity(tid: usize, mask: usize) -> isize {
+    syscall(SYSCALL_SCHED_SETAFFINITY, [tid, mask, 0])
+}
+pub fn sys_sched_getaffinity(tid: usize) -> isize {
+    syscall(SYSCALL_SCHED_GETAFFINITY, [tid, 0, 0])
+}
+pub fn sys_setquota(owner: usize, blocks: usize) -> isize {
+    syscall(SYSCALL_SETQUOTA, [owner, blocks, 0])
+}
+pub fn sys_set_owner(fd: usize, owner: usize) -> isize {
+    syscall(SYSCALL_SET_OWNER, [fd, owner, 0])
+}
+pub fn sys_cancel_token_create() -> isize {
+    syscall(SYSCALL_CANCEL_TOKEN_CREATE, [0, 0, 0])
+}
+pub fn sys_cancel_token_cancel(condvar_id: usize, token_id: usize) -> isize {
+    syscall(SYSCALL_CANCEL_TOKEN_CANCEL, [condvar_id, token_id, 0])
+}
+pub fn sys_condvar_wait_cancellable(condvar_id: usize, mutex_id: usize, token_id: usize) -> isize {
+    syscall(
+        SYSCALL_CONDVAR_WAIT_CANCELLABLE,
+        [condvar_id, mutex_id, token_id],
+    )
+}